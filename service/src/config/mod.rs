@@ -1,17 +1,152 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
     #[serde(default = "default_hotkey")]
     pub hotkey: String,
+    /// 按当前应用自动选择并注入提示词的热键（可选）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inject_hotkey: Option<String>,
     pub database_path: String,
     #[serde(default)]
     pub injection: InjectionConfig,
     #[serde(default)]
     pub applications: HashMap<String, ApplicationConfig>,
+    /// 挂载的 Markdown 提示词目录（与 SQLite prompts 表并列）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_sources: Vec<PromptSourceConfig>,
+    /// 全局热键绑定；为空时由 hotkey（轮盘）与 inject_hotkey（按应用注入）生成
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hotkeys: Vec<HotkeyBinding>,
+    /// 前导键序列，例如按下 Ctrl+Alt+P 后依次输入 r、v 注入 "review"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequences: Option<SequenceConfig>,
+    /// 文本扩展：输入提示词的缩写（如 ";;sig"）再输入分隔符时注入该提示词
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expansion: Option<ExpansionConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExpansionConfig {
+    #[serde(default = "default_expansion_enabled")]
+    pub enabled: bool,
+    /// 结束缩写的字符；分隔符随缩写一起被删除
+    #[serde(default = "default_expansion_delimiters")]
+    pub delimiters: String,
+    /// 为 false 时 ";;SIG" 与 ";;sig" 视为同一缩写
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// 只在这些应用（进程名，不区分大小写）中启用；为空时在所有应用中启用，
+    /// 可在 applications 中对单个应用设置 text_expansion: false 关闭
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SequenceConfig {
+    /// 前导热键
+    pub leader: String,
+    /// 每一步等待下一个按键的时间（毫秒）
+    #[serde(default = "default_sequence_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 序列进行中在屏幕上显示可用的后续按键
+    #[serde(default = "default_sequence_show_hints")]
+    pub show_hints: bool,
+    #[serde(default)]
+    pub bindings: Vec<SequenceBinding>,
+}
+
+/// 一个按键序列及其注入的提示词，例如 `{ keys: "r v", prompt: review }`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SequenceBinding {
+    /// 前导热键之后依次输入的按键，以空格分隔
+    pub keys: String,
+    pub prompt: PromptRef,
+}
+
+/// 一个全局热键及其触发的动作，例如
+/// `{ chord: "Ctrl+Alt+1", action: inject_prompt, prompt: "代码审查" }`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HotkeyBinding {
+    pub chord: String,
+    /// chord 无法注册（如已被其他程序占用）时依次尝试的备选组合
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
+    #[serde(flatten)]
+    pub action: HotkeyAction,
+}
+
+impl HotkeyBinding {
+    /// 按注册顺序排列的候选组合：chord 在前，随后是 fallbacks
+    pub fn candidates(&self) -> Vec<String> {
+        std::iter::once(self.chord.clone())
+            .chain(self.fallbacks.iter().cloned())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// 显示提示词轮盘
+    ShowWheel,
+    /// 显示快速选择面板
+    ShowSelector,
+    /// 注入当前选中的提示词（GUI 中选中或由 cycle_prompt 切换）
+    InjectSelected,
+    /// 按当前应用自动匹配并注入提示词
+    InjectForContext,
+    /// 注入指定的提示词（ID 或名称）
+    InjectPrompt { prompt: PromptRef },
+    /// 切换选中的提示词
+    CyclePrompt {
+        #[serde(default)]
+        direction: CycleDirection,
+    },
+}
+
+impl HotkeyAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HotkeyAction::ShowWheel => "show_wheel",
+            HotkeyAction::ShowSelector => "show_selector",
+            HotkeyAction::InjectSelected => "inject_selected",
+            HotkeyAction::InjectForContext => "inject_for_context",
+            HotkeyAction::InjectPrompt { .. } => "inject_prompt",
+            HotkeyAction::CyclePrompt { .. } => "cycle_prompt",
+        }
+    }
+}
+
+/// 按 ID 或名称引用提示词
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PromptRef {
+    Id(i32),
+    Name(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleDirection {
+    #[default]
+    Next,
+    Previous,
+}
+
+/// 提示词目录：目录下（含子目录）每个 .md 文件是一个提示词
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PromptSourceConfig {
+    pub path: String,
+    /// 显示名称，默认取目录名
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 为 true 时 GUI 中的编辑会写回文件；默认只读
+    #[serde(default)]
+    pub writable: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -26,6 +161,40 @@ pub struct InjectionConfig {
     pub debug_mode: bool,
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
+    /// 剪贴板注入期间剪贴板被改写（如用户复制了新内容）时，不再恢复注入前的剪贴板
+    #[serde(default = "default_skip_restore_if_changed")]
+    pub skip_restore_if_changed: bool,
+    /// 模拟键入（sendinput 策略）的分批、限速与换行设置
+    #[serde(default)]
+    pub typing: TypingConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TypingConfig {
+    /// 每批键盘事件包含的最大字符数（一批事件一次性发送，不会被用户输入打断）
+    #[serde(default = "default_typing_chunk_size")]
+    pub chunk_size: usize,
+    /// 键入速度上限（字符/秒），0 表示不限速
+    #[serde(default = "default_typing_chars_per_second")]
+    pub chars_per_second: u32,
+    /// 换行使用的按键组合
+    #[serde(default)]
+    pub newline: NewlineKey,
+    /// 按应用（进程名，不区分大小写）覆盖换行按键，例如聊天应用中 Enter 会直接发送消息
+    #[serde(default = "default_typing_app_newline")]
+    pub app_newline: HashMap<String, NewlineKey>,
+    /// 键入期间暂时关闭目标窗口的输入法，避免字符进入输入法组字或被转换为全角标点
+    #[serde(default = "default_typing_disable_ime")]
+    pub disable_ime: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NewlineKey {
+    #[default]
+    Enter,
+    ShiftEnter,
+    CtrlEnter,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -53,6 +222,9 @@ pub struct ApplicationSettings {
     pub verify_injection: bool,
     #[serde(default = "default_use_accessibility_api")]
     pub use_accessibility_api: bool,
+    /// 是否在该应用中启用文本扩展（如终端、密码管理器中可关闭）
+    #[serde(default = "default_text_expansion")]
+    pub text_expansion: bool,
 }
 
 impl Default for InjectionConfig {
@@ -63,10 +235,35 @@ impl Default for InjectionConfig {
             uia_value_pattern_mode: default_uia_value_pattern_mode(), // 默认为 append
             debug_mode: default_debug_mode(),
             max_retries: default_max_retries(),
+            skip_restore_if_changed: default_skip_restore_if_changed(),
+            typing: TypingConfig::default(),
+        }
+    }
+}
+
+impl Default for TypingConfig {
+    fn default() -> Self {
+        TypingConfig {
+            chunk_size: default_typing_chunk_size(),
+            chars_per_second: default_typing_chars_per_second(),
+            newline: NewlineKey::default(),
+            app_newline: default_typing_app_newline(),
+            disable_ime: default_typing_disable_ime(),
         }
     }
 }
 
+impl TypingConfig {
+    /// 应用的换行按键：app_newline 中配置了该应用时使用其设置，否则使用 newline
+    pub fn newline_for(&self, app_name: &str) -> NewlineKey {
+        let app_name = app_name.to_lowercase();
+        self.app_newline
+            .iter()
+            .find(|(app, _)| app.to_lowercase() == app_name)
+            .map_or(self.newline, |(_, key)| *key)
+    }
+}
+
 impl Default for ApplicationConfig {
     fn default() -> Self {
         ApplicationConfig {
@@ -93,6 +290,7 @@ impl Default for ApplicationSettings {
             focus_retry_count: default_focus_retry_count(),
             verify_injection: default_verify_injection(),
             use_accessibility_api: default_use_accessibility_api(),
+            text_expansion: default_text_expansion(),
         }
     }
 }
@@ -101,6 +299,23 @@ fn default_hotkey() -> String {
     "Ctrl+Alt+Space".to_string()
 }
 
+fn default_sequence_timeout_ms() -> u64 {
+    1500
+}
+
+fn default_sequence_show_hints() -> bool {
+    true
+}
+
+fn default_expansion_enabled() -> bool {
+    true
+}
+
+fn default_expansion_delimiters() -> String {
+    // 默认不含换行：聊天应用中 Enter 会在删除缩写前发送消息
+    " \t".to_string()
+}
+
 fn default_injection_order() -> Vec<String> {
     // Updated priority: Clipboard -> SendInput (UIA removed)
     vec!["clipboard".to_string(), "sendinput".to_string()]
@@ -122,6 +337,37 @@ fn default_max_retries() -> u32 {
     3
 }
 
+fn default_skip_restore_if_changed() -> bool {
+    true
+}
+
+fn default_typing_chunk_size() -> usize {
+    32
+}
+
+fn default_typing_chars_per_second() -> u32 {
+    500
+}
+
+fn default_typing_app_newline() -> HashMap<String, NewlineKey> {
+    // 这些聊天应用中 Enter 会发送消息，Shift+Enter 才是换行
+    [
+        "slack.exe",
+        "slack",
+        "discord.exe",
+        "discord",
+        "ms-teams.exe",
+        "teams.exe",
+    ]
+    .into_iter()
+    .map(|app| (app.to_string(), NewlineKey::ShiftEnter))
+    .collect()
+}
+
+fn default_typing_disable_ime() -> bool {
+    true
+}
+
 fn default_pre_inject_delay() -> u64 {
     80
 }
@@ -138,6 +384,10 @@ fn default_use_accessibility_api() -> bool {
     false
 }
 
+fn default_text_expansion() -> bool {
+    true
+}
+
 impl Config {
     pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
         // 获取配置文件路径
@@ -153,70 +403,88 @@ impl Config {
         // 读取配置文件
         let content = fs::read_to_string(config_path)?;
         let mut config: Config = serde_yaml::from_str(&content)?;
+        config.fill_compat_defaults();
+        Ok(config)
+    }
 
+    /// 兼容性填充：如果某些字段缺失，应用默认值（避免用户配置被覆盖）
+    fn fill_compat_defaults(&mut self) {
         // 如果应用配置为空，填充预定义配置
-        if config.applications.is_empty() {
-            config.applications = Self::get_predefined_applications();
+        if self.applications.is_empty() {
+            self.applications = Self::get_predefined_applications();
         }
 
-        // 兼容性填充：如果某些字段缺失，应用默认值（避免用户配置被覆盖）
-        if config.injection.order.is_empty() {
-            config.injection.order = default_injection_order();
-        } else {
-            // 向后兼容: 过滤掉已废弃的 "uia" 和 "textpattern_enhanced" 策略
-            let original_len = config.injection.order.len();
-            config.injection.order.retain(|s| {
-                let sl = s.to_lowercase();
-                if sl == "uia" || sl == "textpattern_enhanced" {
-                    log::warn!(
-                        "Ignoring deprecated strategy '{}' in config (UIA removed)",
-                        s
-                    );
-                    false
-                } else {
-                    true
-                }
-            });
-            // 如果过滤后为空，使用默认值
-            if config.injection.order.is_empty() && original_len > 0 {
-                log::warn!(
-                    "All configured strategies were deprecated, using default: clipboard → sendinput"
-                );
-                config.injection.order = default_injection_order();
-            }
+        self.injection.order = supported_strategies(std::mem::take(&mut self.injection.order));
+        for app in self.applications.values_mut() {
+            let names = std::iter::once(std::mem::take(&mut app.strategies.primary))
+                .chain(std::mem::take(&mut app.strategies.fallback))
+                .filter(|name| !name.is_empty())
+                .collect();
+            let mut names = supported_strategies(names).into_iter();
+            app.strategies.primary = names.next().unwrap_or_default();
+            app.strategies.fallback = names.collect();
         }
-        if config.injection.uia_value_pattern_mode.is_empty() {
-            config.injection.uia_value_pattern_mode = default_uia_value_pattern_mode();
+
+        if self.injection.uia_value_pattern_mode.is_empty() {
+            self.injection.uia_value_pattern_mode = default_uia_value_pattern_mode();
         }
         // 如果 database_path 为空（历史文件），填充默认路径
-        if config.database_path.trim().is_empty() {
-            config.database_path = Config::default().database_path;
+        if self.database_path.trim().is_empty() {
+            self.database_path = Config::default().database_path;
         }
-
-        Ok(config)
     }
 
     pub fn get_config_path() -> Result<String, Box<dyn std::error::Error>> {
-        // 获取APPDATA路径
-        let appdata = std::env::var("APPDATA")?;
-        let config_dir = format!("{}\\PromptKey", appdata);
+        let config_dir = config_dir().ok_or("无法确定配置目录")?;
 
         // 创建配置目录（如果不存在）
         fs::create_dir_all(&config_dir)?;
 
-        Ok(format!("{}\\config.yaml", config_dir))
+        Ok(config_dir
+            .join("config.yaml")
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// 默认数据库路径（数据目录不可用时为当前目录下的 promptmgr.db）
+    pub fn default_database_path() -> String {
+        data_dir()
+            .map(|dir| dir.join("promptmgr.db"))
+            .unwrap_or_else(|| PathBuf::from("promptmgr.db"))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// 读取配置文件本身（不做 load 中的兼容性填充）；文件不存在时返回默认配置
+    pub fn read_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
     }
 
-    fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// 读取配置文件、修改后写回（GUI 设置页使用）：edit 未改动的设置原样保留
+    pub fn update_file(
+        path: &Path,
+        edit: impl FnOnce(&mut Config),
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        let mut config = Self::read_file(path)?;
+        edit(&mut config);
+        config.save(path)?;
+        Ok(config)
+    }
+
+    fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
         let yaml = serde_yaml::to_string(&self)?; // 添加 &self 引用
         fs::write(path, yaml)?;
         Ok(())
     }
 
     pub fn default_with_predefined_apps() -> Self {
-        let mut config = Config::default();
-        config.applications = Self::get_predefined_applications();
-        config
+        Config {
+            applications: Self::get_predefined_applications(),
+            ..Config::default()
+        }
     }
 
     pub fn get_predefined_applications() -> HashMap<String, ApplicationConfig> {
@@ -227,15 +495,17 @@ impl Config {
             "code.exe".to_string(),
             ApplicationConfig {
                 display_name: "Visual Studio Code".to_string(),
+                // 键入会触发自动缩进与括号补全，多行提示词需用剪贴板粘贴
                 strategies: StrategyConfig {
-                    primary: "textpattern_enhanced".to_string(),
-                    fallback: vec!["sendinput".to_string(), "clipboard".to_string()],
+                    primary: "clipboard".to_string(),
+                    fallback: vec!["sendinput".to_string()],
                 },
                 settings: ApplicationSettings {
                     pre_inject_delay: 150,
                     focus_retry_count: 3,
                     verify_injection: true,
                     use_accessibility_api: false,
+                    text_expansion: true,
                 },
             },
         );
@@ -254,6 +524,7 @@ impl Config {
                     focus_retry_count: 2,
                     verify_injection: true,
                     use_accessibility_api: true,
+                    text_expansion: true,
                 },
             },
         );
@@ -264,14 +535,15 @@ impl Config {
             ApplicationConfig {
                 display_name: "Visual Studio".to_string(),
                 strategies: StrategyConfig {
-                    primary: "uia".to_string(),
-                    fallback: vec!["clipboard".to_string(), "sendinput".to_string()],
+                    primary: "clipboard".to_string(),
+                    fallback: vec!["sendinput".to_string()],
                 },
                 settings: ApplicationSettings {
                     pre_inject_delay: 50,
                     focus_retry_count: 2,
                     verify_injection: true,
                     use_accessibility_api: false,
+                    text_expansion: true,
                 },
             },
         );
@@ -282,14 +554,15 @@ impl Config {
             ApplicationConfig {
                 display_name: "Notepad++".to_string(),
                 strategies: StrategyConfig {
-                    primary: "textpattern_enhanced".to_string(),
-                    fallback: vec!["clipboard".to_string(), "sendinput".to_string()],
+                    primary: "clipboard".to_string(),
+                    fallback: vec!["sendinput".to_string()],
                 },
                 settings: ApplicationSettings {
                     pre_inject_delay: 100,
                     focus_retry_count: 2,
                     verify_injection: false,
                     use_accessibility_api: false,
+                    text_expansion: true,
                 },
             },
         );
@@ -297,28 +570,383 @@ impl Config {
         apps
     }
 
+    /// 生效的热键绑定：配置了 hotkeys 时使用它，否则兼容旧配置，
+    /// 由 hotkey 生成轮盘热键、由 inject_hotkey 生成按应用注入热键
+    pub fn hotkey_bindings(&self) -> Vec<HotkeyBinding> {
+        if !self.hotkeys.is_empty() {
+            return self.hotkeys.clone();
+        }
+        let mut bindings = Vec::new();
+        if let Some(inject_hotkey) = &self.inject_hotkey {
+            bindings.push(HotkeyBinding {
+                chord: inject_hotkey.clone(),
+                fallbacks: Vec::new(),
+                action: HotkeyAction::InjectForContext,
+            });
+        }
+        bindings.push(HotkeyBinding {
+            chord: self.hotkey.clone(),
+            fallbacks: Vec::new(),
+            action: HotkeyAction::ShowWheel,
+        });
+        bindings
+    }
+
+    /// 文本扩展是否在该应用中生效：已配置并启用 expansion，应用在 expansion.apps 中
+    /// （为空表示全部应用），且 applications 中没有为该应用关闭 text_expansion
+    pub fn expansion_enabled_for(&self, app_name: &str) -> bool {
+        let Some(expansion) = self.expansion.as_ref().filter(|e| e.enabled) else {
+            return false;
+        };
+        let listed = expansion.apps.is_empty()
+            || expansion
+                .apps
+                .iter()
+                .any(|app| app.eq_ignore_ascii_case(app_name));
+        listed && self.get_app_config(app_name).settings.text_expansion
+    }
+
     pub fn get_app_config(&self, app_name: &str) -> ApplicationConfig {
         self.applications
             .get(&app_name.to_lowercase())
             .cloned()
             .unwrap_or_else(ApplicationConfig::default)
     }
+
+    /// 应用的注入策略顺序：applications 中配置了该应用时使用其 primary + fallback，
+    /// 否则使用全局 injection.order
+    pub fn strategy_order_for(&self, app_name: &str) -> Vec<String> {
+        match self.applications.get(&app_name.to_lowercase()) {
+            Some(app) => std::iter::once(app.strategies.primary.clone())
+                .chain(app.strategies.fallback.iter().cloned())
+                .collect(),
+            None => self.injection.order.clone(),
+        }
+    }
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        // 获取默认数据库路径
-        let database_path = if let Ok(appdata) = std::env::var("APPDATA") {
-            format!("{}\\PromptKey\\promptmgr.db", appdata)
+/// 配置文件所在目录：Windows 为 %APPDATA%\PromptKey，
+/// 其他平台为 $XDG_CONFIG_HOME/promptkey（默认 ~/.config/promptkey）
+#[cfg(windows)]
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|appdata| PathBuf::from(appdata).join("PromptKey"))
+}
+
+#[cfg(not(windows))]
+fn config_dir() -> Option<PathBuf> {
+    xdg_dir(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+        ".config",
+    )
+}
+
+/// 数据库所在目录：Windows 与配置目录相同，
+/// 其他平台为 $XDG_DATA_HOME/promptkey（默认 ~/.local/share/promptkey）
+#[cfg(windows)]
+fn data_dir() -> Option<PathBuf> {
+    config_dir()
+}
+
+#[cfg(not(windows))]
+fn data_dir() -> Option<PathBuf> {
+    xdg_dir(
+        std::env::var_os("XDG_DATA_HOME"),
+        std::env::var_os("HOME"),
+        ".local/share",
+    )
+}
+
+/// XDG 基础目录下的 promptkey 目录；环境变量为空或不是绝对路径时按规范回落到 $HOME 下的默认目录
+#[cfg(not(windows))]
+fn xdg_dir(
+    xdg_home: Option<std::ffi::OsString>,
+    home: Option<std::ffi::OsString>,
+    home_default: &str,
+) -> Option<PathBuf> {
+    let base = xdg_home
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| {
+            home.filter(|h| !h.is_empty())
+                .map(|h| PathBuf::from(h).join(home_default))
+        })?;
+    Some(base.join("promptkey"))
+}
+
+/// 向后兼容: 过滤掉已废弃的 "uia" 和 "textpattern_enhanced" 策略；
+/// 为空或过滤后为空时使用默认顺序
+fn supported_strategies(mut names: Vec<String>) -> Vec<String> {
+    if names.is_empty() {
+        return default_injection_order();
+    }
+    names.retain(|s| {
+        let sl = s.to_lowercase();
+        if sl == "uia" || sl == "textpattern_enhanced" {
+            log::warn!(
+                "Ignoring deprecated strategy '{}' in config (UIA removed)",
+                s
+            );
+            false
         } else {
-            "promptmgr.db".to_string() // fallback
-        };
+            true
+        }
+    });
+    if names.is_empty() {
+        log::warn!(
+            "All configured strategies were deprecated, using default: clipboard → sendinput"
+        );
+        names = default_injection_order();
+    }
+    names
+}
 
+impl Default for Config {
+    fn default() -> Self {
         Config {
             hotkey: default_hotkey(),
-            database_path,
+            inject_hotkey: None,
+            database_path: Config::default_database_path(),
             injection: InjectionConfig::default(),
             applications: HashMap::new(),
+            prompt_sources: Vec::new(),
+            hotkeys: Vec::new(),
+            sequences: None,
+            expansion: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deprecated_strategies_are_dropped() {
+        let yaml = r#"
+database_path: prompts.db
+injection:
+  order: [uia, sendinput]
+applications:
+  code.exe:
+    display_name: Visual Studio Code
+    strategies:
+      primary: textpattern_enhanced
+      fallback: [clipboard, sendinput]
+  devenv.exe:
+    display_name: Visual Studio
+    strategies:
+      primary: uia
+      fallback: []
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.fill_compat_defaults();
+        assert_eq!(config.injection.order, ["sendinput"]);
+        assert_eq!(
+            config.strategy_order_for("Code.exe"),
+            ["clipboard", "sendinput"]
+        );
+        assert_eq!(
+            config.strategy_order_for("devenv.exe"),
+            default_injection_order()
+        );
+
+        // 预定义应用只使用已注册的策略，剪贴板优先
+        for app in Config::get_predefined_applications().values() {
+            assert_eq!(app.strategies.primary, "clipboard");
+            assert_eq!(app.strategies.fallback, ["sendinput"]);
         }
     }
+
+    #[test]
+    fn test_update_file_keeps_other_settings() {
+        let dir = std::env::temp_dir().join(format!("promptkey-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        let yaml = r#"
+hotkey: Ctrl+Alt+Space
+database_path: prompts.db
+injection:
+  max_retries: 7
+  debug_mode: true
+applications:
+  slack.exe:
+    display_name: Slack
+    strategies:
+      primary: sendinput
+      fallback: [clipboard]
+    settings:
+      verify_injection: false
+      focus_retry_count: 5
+      text_expansion: false
+"#;
+        fs::write(&path, yaml).unwrap();
+
+        Config::update_file(&path, |c| c.hotkey = "Ctrl+Shift+F5".to_string()).unwrap();
+
+        let saved = Config::read_file(&path).unwrap();
+        assert_eq!(saved.hotkey, "Ctrl+Shift+F5");
+        assert_eq!(saved.database_path, "prompts.db");
+        assert_eq!(saved.injection.max_retries, 7);
+        assert!(saved.injection.debug_mode);
+        let slack = &saved.applications["slack.exe"];
+        assert_eq!(slack.strategies.primary, "sendinput");
+        assert!(!slack.settings.verify_injection);
+        assert_eq!(slack.settings.focus_retry_count, 5);
+        assert!(!slack.settings.text_expansion);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_xdg_dirs() {
+        let dir = |xdg: Option<&str>, home: Option<&str>| {
+            xdg_dir(xdg.map(Into::into), home.map(Into::into), ".config")
+        };
+        assert_eq!(
+            dir(Some("/xdg"), Some("/home/a")),
+            Some(PathBuf::from("/xdg/promptkey"))
+        );
+        // 相对路径与空值不符合 XDG 规范，回落到 $HOME
+        assert_eq!(
+            dir(Some("relative"), Some("/home/a")),
+            Some(PathBuf::from("/home/a/.config/promptkey"))
+        );
+        assert_eq!(
+            dir(Some(""), Some("/home/a")),
+            Some(PathBuf::from("/home/a/.config/promptkey"))
+        );
+        assert_eq!(dir(None, None), None);
+    }
+
+    #[test]
+    fn test_hotkey_bindings() {
+        let yaml = r#"
+database_path: prompts.db
+hotkeys:
+  - chord: Ctrl+Alt+Space
+    fallbacks: [Ctrl+Alt+W, Win+Shift+W]
+    action: show_wheel
+  - chord: Ctrl+Alt+1
+    action: inject_prompt
+    prompt: 42
+  - chord: Ctrl+Alt+2
+    action: inject_prompt
+    prompt: Code Review
+  - chord: Ctrl+Alt+N
+    action: cycle_prompt
+  - chord: Ctrl+Alt+P
+    action: cycle_prompt
+    direction: previous
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.hotkeys[0].candidates(),
+            vec!["Ctrl+Alt+Space", "Ctrl+Alt+W", "Win+Shift+W"]
+        );
+        let actions: Vec<HotkeyAction> = config
+            .hotkey_bindings()
+            .into_iter()
+            .map(|b| b.action)
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                HotkeyAction::ShowWheel,
+                HotkeyAction::InjectPrompt {
+                    prompt: PromptRef::Id(42)
+                },
+                HotkeyAction::InjectPrompt {
+                    prompt: PromptRef::Name("Code Review".to_string())
+                },
+                HotkeyAction::CyclePrompt {
+                    direction: CycleDirection::Next
+                },
+                HotkeyAction::CyclePrompt {
+                    direction: CycleDirection::Previous
+                },
+            ]
+        );
+
+        // 序列化后保持原样
+        let round_trip: Config =
+            serde_yaml::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(round_trip.hotkeys, config.hotkeys);
+    }
+
+    #[test]
+    fn test_sequence_config() {
+        let yaml = r#"
+database_path: prompts.db
+sequences:
+  leader: Ctrl+Alt+P
+  bindings:
+    - keys: r v
+      prompt: review
+    - keys: "1"
+      prompt: 7
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let sequences = config.sequences.unwrap();
+        assert_eq!(sequences.timeout_ms, 1500);
+        assert!(sequences.show_hints);
+        assert_eq!(
+            sequences.bindings[0].prompt,
+            PromptRef::Name("review".to_string())
+        );
+        assert_eq!(sequences.bindings[1].prompt, PromptRef::Id(7));
+    }
+
+    #[test]
+    fn test_expansion_enabled_per_app() {
+        let yaml = r#"
+database_path: prompts.db
+expansion:
+  apps: [Code.exe, notepad.exe]
+applications:
+  notepad.exe:
+    display_name: Notepad
+    settings:
+      text_expansion: false
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        let expansion = config.expansion.clone().unwrap();
+        assert!(expansion.enabled);
+        assert_eq!(expansion.delimiters, " \t");
+        assert!(!expansion.case_sensitive);
+
+        assert!(config.expansion_enabled_for("code.exe"));
+        assert!(!config.expansion_enabled_for("notepad.exe"));
+        assert!(!config.expansion_enabled_for("slack.exe"));
+
+        config.expansion.as_mut().unwrap().apps.clear();
+        assert!(config.expansion_enabled_for("slack.exe"));
+        config.expansion.as_mut().unwrap().enabled = false;
+        assert!(!config.expansion_enabled_for("code.exe"));
+        assert!(!Config::default().expansion_enabled_for("code.exe"));
+    }
+
+    #[test]
+    fn test_legacy_hotkeys_become_bindings() {
+        let config = Config {
+            hotkey: "Ctrl+Alt+Space".to_string(),
+            inject_hotkey: Some("Ctrl+Alt+I".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.hotkey_bindings(),
+            vec![
+                HotkeyBinding {
+                    chord: "Ctrl+Alt+I".to_string(),
+                    fallbacks: Vec::new(),
+                    action: HotkeyAction::InjectForContext,
+                },
+                HotkeyBinding {
+                    chord: "Ctrl+Alt+Space".to_string(),
+                    fallbacks: Vec::new(),
+                    action: HotkeyAction::ShowWheel,
+                },
+            ]
+        );
+    }
 }
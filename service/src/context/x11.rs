@@ -0,0 +1,218 @@
+// X11 foreground context: _NET_ACTIVE_WINDOW + _NET_WM_PID → /proc/<pid>/comm
+
+use super::{AppContext, ContextProvider, WindowHandle};
+use std::result::Result as StdResult;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, Window};
+use x11rb::rust_connection::RustConnection;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_PID,
+        _NET_WM_NAME,
+        UTF8_STRING,
+    }
+}
+
+/// 读取属性时的最大长度（32 位单位）
+const MAX_PROPERTY_LEN: u32 = 4096;
+
+pub struct X11Context;
+
+impl ContextProvider for X11Context {
+    fn get_foreground_context(&self) -> StdResult<AppContext, Box<dyn std::error::Error>> {
+        // 每次查询使用独立连接，避免长连接在 X server 重启后失效
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+
+        let window = active_window(&conn, root, &atoms)?;
+        if window == x11rb::NONE {
+            log::warn!("No foreground window found");
+            return Err("No foreground window found".into());
+        }
+        log::debug!("Foreground window: 0x{:x}", window);
+
+        let window_title = window_title(&conn, window, &atoms)?;
+        let process_name = match window_pid(&conn, window, &atoms)? {
+            Some(pid) => process_name(pid).unwrap_or_default(),
+            None => String::new(),
+        };
+        // 部分窗口（例如远程 X 客户端）没有 _NET_WM_PID，退回到 WM_CLASS
+        let process_name = if process_name.is_empty() {
+            wm_class(&conn, window)?.unwrap_or_default()
+        } else {
+            process_name
+        };
+        log::debug!("Process name: {}, title: {}", process_name, window_title);
+
+        Ok(AppContext {
+            process_name,
+            window_title,
+            window_handle: WindowHandle(u64::from(window)),
+        })
+    }
+}
+
+fn active_window(
+    conn: &RustConnection,
+    root: Window,
+    atoms: &Atoms,
+) -> StdResult<Window, Box<dyn std::error::Error>> {
+    let reply = conn
+        .get_property(
+            false,
+            root,
+            atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+            0,
+            1,
+        )?
+        .reply()?;
+    Ok(reply
+        .value32()
+        .and_then(|mut v| v.next())
+        .unwrap_or(x11rb::NONE))
+}
+
+fn window_title(
+    conn: &RustConnection,
+    window: Window,
+    atoms: &Atoms,
+) -> StdResult<String, Box<dyn std::error::Error>> {
+    let reply = conn
+        .get_property(
+            false,
+            window,
+            atoms._NET_WM_NAME,
+            atoms.UTF8_STRING,
+            0,
+            MAX_PROPERTY_LEN,
+        )?
+        .reply()?;
+    if !reply.value.is_empty() {
+        return Ok(String::from_utf8_lossy(&reply.value).into_owned());
+    }
+    // 旧式客户端只设置 WM_NAME（Latin-1 / 任意类型）
+    let reply = conn
+        .get_property(
+            false,
+            window,
+            AtomEnum::WM_NAME,
+            AtomEnum::ANY,
+            0,
+            MAX_PROPERTY_LEN,
+        )?
+        .reply()?;
+    Ok(String::from_utf8_lossy(&reply.value).into_owned())
+}
+
+fn window_pid(
+    conn: &RustConnection,
+    window: Window,
+    atoms: &Atoms,
+) -> StdResult<Option<u32>, Box<dyn std::error::Error>> {
+    let reply = conn
+        .get_property(false, window, atoms._NET_WM_PID, AtomEnum::CARDINAL, 0, 1)?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut v| v.next()))
+}
+
+fn wm_class(
+    conn: &RustConnection,
+    window: Window,
+) -> StdResult<Option<String>, Box<dyn std::error::Error>> {
+    let reply = conn
+        .get_property(
+            false,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            0,
+            MAX_PROPERTY_LEN,
+        )?
+        .reply()?;
+    // WM_CLASS = "instance\0class\0"，取 instance
+    Ok(reply
+        .value
+        .split(|b| *b == 0)
+        .next()
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).into_owned()))
+}
+
+fn process_name(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .map(|s| s.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::protocol::xproto::{CreateWindowAux, PropMode, WindowClass};
+    use x11rb::wrapper::ConnectionExt as _;
+
+    #[test]
+    fn test_process_name_of_self() {
+        let name = process_name(std::process::id()).unwrap();
+        assert!(!name.is_empty());
+    }
+
+    /// 需要 X server：`xvfb-run cargo test -p service -- --ignored`
+    #[test]
+    #[ignore = "requires an X server (run under xvfb-run)"]
+    fn test_foreground_context_on_xvfb() {
+        let (conn, screen_num) = x11rb::connect(None).unwrap();
+        let screen = &conn.setup().roots[screen_num];
+        let atoms = Atoms::new(&conn).unwrap().reply().unwrap();
+
+        // 没有窗口管理器时自己设置 _NET_ACTIVE_WINDOW 模拟前台窗口
+        let win = conn.generate_id().unwrap();
+        conn.create_window(
+            0,
+            win,
+            screen.root,
+            0,
+            0,
+            10,
+            10,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            win,
+            atoms._NET_WM_NAME,
+            atoms.UTF8_STRING,
+            "main.rs — 编辑器".as_bytes(),
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            win,
+            atoms._NET_WM_PID,
+            AtomEnum::CARDINAL,
+            &[std::process::id()],
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            screen.root,
+            atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+            &[win],
+        )
+        .unwrap();
+        conn.sync().unwrap();
+
+        let ctx = X11Context.get_foreground_context().unwrap();
+        assert_eq!(ctx.window_title, "main.rs — 编辑器");
+        assert_eq!(ctx.window_handle, WindowHandle(u64::from(win)));
+        assert_eq!(Some(ctx.process_name), process_name(std::process::id()));
+    }
+}
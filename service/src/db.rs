@@ -1,3 +1,14 @@
+pub mod migrations;
+mod queries;
+mod sources;
+
+pub use queries::{
+    PromptForSelector, PromptWithPin, SelectorStats, TopPromptStat, UsageLogEntry, WheelPrompt,
+    WheelPromptsPage,
+};
+
+use crate::diff::{DiffLine, diff_lines};
+use crate::scope::AppScope;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
@@ -14,8 +25,169 @@ pub struct Prompt {
     pub inject_order: Option<String>,
     pub version: Option<i32>,
     pub updated_at: Option<String>,
+    #[serde(default)]
+    pub is_pinned: bool,
+    /// 来源目录名称；None 表示仅存储在 SQLite 中
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub source_path: Option<String>,
+    /// 来自只读目录的提示词不能在 GUI 中修改
+    #[serde(default)]
+    pub read_only: bool,
+    /// 文本扩展缩写（如 ";;sig"），输入缩写再输入分隔符即注入该提示词
+    #[serde(default)]
+    pub abbreviation: Option<String>,
+}
+
+/// GUI 编辑表单提交的字段；表单中没有的列（content_type、变量定义、应用作用域、注入顺序）
+/// 沿用数据库中的值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptEdit {
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    pub content: String,
+    #[serde(default)]
+    pub abbreviation: Option<String>,
+}
+
+impl PromptEdit {
+    /// 把表单字段写入已存储的提示词
+    pub fn apply(&self, prompt: &mut Prompt) {
+        prompt.name = self.name.clone();
+        prompt.tags = self.tags.clone();
+        prompt.content = self.content.clone();
+        prompt.abbreviation = self.abbreviation.clone();
+    }
+}
+
+/// 一次注入的使用记录（usage_logs 的一行）
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecord<'a> {
+    pub prompt_id: Option<i32>,
+    pub prompt_name: &'a str,
+    pub target_app: &'a str,
+    pub window_title: &'a str,
+    /// 触发注入的热键；从 GUI 直接点选时为 "GUI"
+    pub hotkey_used: &'a str,
+    /// 最终成功的策略；全部失败时为空
+    pub strategy: &'a str,
+    /// 依次尝试过的策略，例如 "Clipboard → SendInput"
+    pub fallback_chain: &'a str,
+    pub injection_time_ms: u64,
+    pub success: bool,
+    pub error: Option<&'a str>,
+    pub result: &'a str,
+    pub action: &'a str, // TW007: action field (e.g., 'wheel_select', 'hotkey_inject')
+}
+
+/// prompts 表的标准查询列，与 prompt_from_row 一一对应
+const PROMPT_COLUMNS: &str = "id, name, tags, content, content_type, variables_json, \
+     app_scopes_json, inject_order, version, updated_at, COALESCE(is_pinned, 0), \
+     source, source_path, source_path IS NOT NULL AND COALESCE(source_writable, 0) = 0, \
+     abbreviation";
+
+fn prompt_from_row(row: &rusqlite::Row) -> rusqlite::Result<Prompt> {
+    // 反序列化tags字段，解析失败时忽略
+    let tags = row
+        .get::<_, Option<String>>(2)?
+        .and_then(|s| serde_json::from_str(&s).ok());
+
+    Ok(Prompt {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        tags,
+        content: row.get(3)?,
+        content_type: row.get(4)?,
+        variables_json: row.get(5)?,
+        app_scopes_json: row.get(6)?,
+        inject_order: row.get(7)?,
+        version: row.get(8)?,
+        updated_at: row.get(9)?,
+        is_pinned: row.get::<_, i32>(10)? == 1,
+        source: row.get(11)?,
+        source_path: row.get(12)?,
+        read_only: row.get(13)?,
+        abbreviation: row.get(14)?,
+    })
+}
+
+/// 缩写去掉首尾空白，空字符串视为未设置
+pub(crate) fn normalize_abbreviation(abbreviation: Option<&str>) -> Option<&str> {
+    abbreviation.map(str::trim).filter(|a| !a.is_empty())
+}
+
+/// 自动选择时单个提示词的评估结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelectionCandidate {
+    pub prompt_id: i32,
+    pub prompt_name: String,
+    pub matched: bool,
+    pub priority: i32,
+    pub specificity: u32,
+    pub reasons: Vec<String>,
 }
 
+/// 解释某个上下文下为何选中某个提示词
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelectionExplanation {
+    pub app_name: String,
+    pub window_title: String,
+    pub chosen_id: Option<i32>,
+    pub reason: String,
+    pub candidates: Vec<SelectionCandidate>,
+}
+
+/// 提示词历史版本快照（每次更新前保存旧内容）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptRevision {
+    pub id: i32,
+    pub prompt_id: i32,
+    pub version: i32,
+    pub name: String,
+    pub tags: Option<Vec<String>>,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub variables_json: Option<String>,
+    pub app_scopes_json: Option<String>,
+    pub inject_order: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// 两个版本之间的差异
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RevisionDiff {
+    pub prompt_id: i32,
+    pub from_version: i32,
+    pub to_version: i32,
+    /// 名称变化 (旧, 新)，未变化时为 None
+    pub name_change: Option<(String, String)>,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 全文检索结果（字段与选择面板的 PromptForSelector 保持一致）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptSearchHit {
+    pub id: i32,
+    pub name: String,
+    pub content: String,
+    pub category: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub usage_count: i64,
+    pub last_used_at: Option<i64>, // Unix ms
+    pub source: Option<String>,
+    pub score: f64,
+}
+
+/// 使用频率对相关度的放大系数：score *= 1 + FREQUENCY_WEIGHT * ln(1 + 使用次数)
+const SEARCH_FREQUENCY_WEIGHT: f64 = 0.2;
+/// 最近使用加分：RECENCY_WEIGHT / (1 + 距上次使用的天数)
+const SEARCH_RECENCY_WEIGHT: f64 = 2.0;
+/// BM25 列权重：name, tags, content
+const SEARCH_BM25_WEIGHTS: &str = "10.0, 5.0, 1.0";
+
 pub struct Database {
     conn: Connection,
 }
@@ -28,242 +200,50 @@ impl Database {
                 .map_err(|e| -> Box<dyn std::error::Error> { Box::new(e) })?;
         }
 
-        let conn = Connection::open(db_path)?;
+        let mut conn = Connection::open(db_path)?;
 
+        // GUI 与 service 可能同时访问数据库
+        conn.busy_timeout(std::time::Duration::from_millis(2000))?;
         // 启用WAL模式
         conn.execute_batch("PRAGMA journal_mode=WAL;")?;
 
-        let db = Database { conn };
-        db.initialize_tables()?;
+        // 统一的 schema 迁移（PRAGMA user_version）
+        migrations::run(&mut conn)?;
 
-        Ok(db)
+        Ok(Database { conn })
     }
 
-    fn initialize_tables(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // 创建prompts表
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS prompts (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                tags TEXT,
-                content TEXT NOT NULL,
-                content_type TEXT,
-                variables_json TEXT,
-                app_scopes_json TEXT,
-                inject_order TEXT,
-                version INTEGER DEFAULT 1,
-                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-
-        // 创建或迁移usage_logs表
-        // 首先检查表是否存在以及是否需要迁移
-        let table_info = self.conn.prepare("PRAGMA table_info(usage_logs)");
-        match table_info {
-            Ok(mut stmt) => {
-                let rows: Result<Vec<_>, _> = stmt
-                    .query_map([], |row| {
-                        Ok(row.get::<_, String>(1)?) // 获取列名
-                    })
-                    .and_then(|iter| iter.collect());
-
-                match rows {
-                    Ok(columns) => {
-                        let has_prompt_name = columns.iter().any(|col| col == "prompt_name");
-                        let has_hotkey_used = columns.iter().any(|col| col == "hotkey_used");
-                        let has_injection_time =
-                            columns.iter().any(|col| col == "injection_time_ms");
-
-                        if !has_prompt_name || !has_hotkey_used || !has_injection_time {
-                            log::info!("检测到旧版usage_logs表结构，开始迁移...");
-                            self.migrate_usage_logs_table()?;
-                        } else {
-                            log::debug!("usage_logs表结构已是最新版本");
-                        }
-                    }
-                    Err(_) => {
-                        // 表不存在，创建新表
-                        log::info!("创建新的usage_logs表");
-                        self.conn.execute(
-                            "CREATE TABLE usage_logs (
-                                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                                prompt_id INTEGER,
-                                prompt_name TEXT,
-                                target_app TEXT,
-                                window_title TEXT,
-                                hotkey_used TEXT,
-                                strategy TEXT,
-                                injection_time_ms INTEGER,
-                                success INTEGER,
-                                error TEXT,
-                                result TEXT,
-                                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-                            )",
-                            [],
-                        )?;
-                    }
-                }
-            }
-            Err(_) => {
-                // 无法检查表信息，直接创建
-                self.conn.execute(
-                    "CREATE TABLE IF NOT EXISTS usage_logs (
-                        id INTEGER PRIMARY KEY AUTOINCREMENT,
-                        prompt_id INTEGER,
-                        prompt_name TEXT,
-                        target_app TEXT,
-                        window_title TEXT,
-                        hotkey_used TEXT,
-                        strategy TEXT,
-                        injection_time_ms INTEGER,
-                        success INTEGER,
-                        error TEXT,
-                        result TEXT,
-                        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-                    )",
-                    [],
-                )?;
-            }
-        }
-
-        // 创建selected_prompt表用于存储选中的提示词ID
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS selected_prompt (
-                id INTEGER PRIMARY KEY,
-                prompt_id INTEGER NOT NULL
-            )",
-            [],
-        )?;
-
-        // 插入默认选中记录（如果不存在）
-        self.conn.execute(
-            "INSERT OR IGNORE INTO selected_prompt (id, prompt_id) VALUES (1, 0)",
-            [],
-        )?;
-
-        // TW007: Ensure action column exists in usage_logs
-        let mut table_info = self.conn.prepare("PRAGMA table_info(usage_logs)")?;
-        let has_action: bool = table_info
-            .query_map([], |row| Ok(row.get::<_, String>(1)?))
-            .ok()
-            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>().ok())
-            .map(|cols| cols.iter().any(|col| col == "action"))
-            .unwrap_or(false);
-
-        if !has_action {
-            log::info!("Adding 'action' column to usage_logs table");
-            self.conn.execute(
-                "ALTER TABLE usage_logs ADD COLUMN action TEXT DEFAULT 'hotkey_inject'",
-                [],
-            )?;
-        }
-
-        Ok(())
-    }
-
-    fn migrate_usage_logs_table(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // 备份旧数据
-        self.conn
-            .execute("ALTER TABLE usage_logs RENAME TO usage_logs_old", [])?;
-
-        // 创建新表结构
-        self.conn.execute(
-            "CREATE TABLE usage_logs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                prompt_id INTEGER,
-                prompt_name TEXT,
-                target_app TEXT,
-                window_title TEXT,
-                hotkey_used TEXT,
-                strategy TEXT,
-                injection_time_ms INTEGER,
-                success INTEGER,
-                error TEXT,
-                result TEXT,
-                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-
-        // 迁移数据（尽可能保留旧数据）
-        let migrate_sql = "
-            INSERT INTO usage_logs (
-                id, prompt_id, prompt_name, target_app, window_title, 
-                hotkey_used, strategy, injection_time_ms, success, error, result, created_at
-            )
-            SELECT 
-                id,
-                COALESCE(prompt_id, NULL) as prompt_id,
-                COALESCE(prompt_name, 'Unknown') as prompt_name,
-                COALESCE(target_app, 'Unknown') as target_app,
-                COALESCE(window_title, 'Unknown') as window_title,
-                COALESCE(hotkey_used, 'Unknown') as hotkey_used,
-                COALESCE(strategy, 'Unknown') as strategy,
-                COALESCE(injection_time_ms, 0) as injection_time_ms,
-                COALESCE(success, 0) as success,
-                error,
-                COALESCE(result, '') as result,
-                COALESCE(created_at, datetime('now')) as created_at
-            FROM usage_logs_old
-        ";
-
-        match self.conn.execute(migrate_sql, []) {
-            Ok(count) => {
-                log::info!("成功迁移 {} 条usage_logs记录", count);
-                // 删除旧表
-                self.conn.execute("DROP TABLE usage_logs_old", [])?;
-            }
-            Err(e) => {
-                log::warn!("数据迁移失败: {}，将保留旧表并使用新表结构", e);
-                // 如果迁移失败，保留旧表但使用新表
-            }
-        }
-
-        Ok(())
-    }
-
-    pub fn log_usage(
-        &self,
-        prompt_id: Option<i32>,
-        prompt_name: &str,
-        target_app: &str,
-        window_title: &str,
-        hotkey_used: &str,
-        strategy: &str,
-        injection_time_ms: u128,
-        success: bool,
-        error: Option<&str>,
-        result: &str,
-        action: &str, // TW007: action field (e.g., 'wheel_select', 'hotkey_inject')
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn log_usage(&self, usage: &UsageRecord) -> Result<(), Box<dyn std::error::Error>> {
         // 调试：打印接收到的参数
         log::debug!(
-            "DB log_usage called with - prompt_id: {:?}, prompt_name: '{}', strategy: '{}', time: {}ms, action: '{}'",
-            prompt_id,
-            prompt_name,
-            strategy,
-            injection_time_ms,
-            action
+            "DB log_usage called with - prompt_id: {:?}, prompt_name: '{}', strategy: '{}', chain: '{}', time: {}ms, success: {}, action: '{}'",
+            usage.prompt_id,
+            usage.prompt_name,
+            usage.strategy,
+            usage.fallback_chain,
+            usage.injection_time_ms,
+            usage.success,
+            usage.action
         );
 
         let mut stmt = self.conn.prepare(
-            "INSERT INTO usage_logs (prompt_id, prompt_name, target_app, window_title, hotkey_used, strategy, injection_time_ms, success, error, result, action)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+            "INSERT INTO usage_logs (prompt_id, prompt_name, target_app, window_title, hotkey_used, strategy, fallback_chain, injection_time_ms, success, error, result, action)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"
         )?;
 
         let result = stmt.execute(rusqlite::params![
-            &prompt_id,
-            &prompt_name,
-            &target_app,
-            &window_title,
-            &hotkey_used,
-            &strategy,
-            &(injection_time_ms as i64),
-            &(if success { 1 } else { 0 }),
-            &error,
-            &result,
-            &action, // TW007: Insert action value
+            &usage.prompt_id,
+            &usage.prompt_name,
+            &usage.target_app,
+            &usage.window_title,
+            &usage.hotkey_used,
+            &usage.strategy,
+            &usage.fallback_chain,
+            &(usage.injection_time_ms as i64),
+            &(if usage.success { 1 } else { 0 }),
+            &usage.error,
+            &usage.result,
+            &usage.action, // TW007: Insert action value
         ]);
 
         match &result {
@@ -284,8 +264,8 @@ impl Database {
 
     pub fn create_prompt(&self, prompt: &Prompt) -> Result<i32, Box<dyn std::error::Error>> {
         let mut stmt = self.conn.prepare(
-            "INSERT INTO prompts (name, tags, content, content_type, variables_json, app_scopes_json, inject_order, version)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+            "INSERT INTO prompts (name, tags, content, content_type, variables_json, app_scopes_json, inject_order, version, is_pinned, abbreviation)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
         )?;
 
         // 将tags序列化为JSON字符串
@@ -302,51 +282,325 @@ impl Database {
             &prompt.variables_json,
             &prompt.app_scopes_json,
             &prompt.inject_order,
-            &prompt.version.unwrap_or(1)
+            &prompt.version.unwrap_or(1),
+            &(prompt.is_pinned as i32),
+            &normalize_abbreviation(prompt.abbreviation.as_deref())
         ])?;
 
         Ok(id as i32)
     }
 
-    pub fn get_all_prompts(&self) -> Result<Vec<Prompt>, Box<dyn std::error::Error>> {
+    pub fn delete_prompt(&self, id: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn
+            .execute("DELETE FROM prompts WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// 切换置顶状态，返回新的状态
+    pub fn toggle_prompt_pin(&self, id: i32) -> Result<bool, Box<dyn std::error::Error>> {
+        let current_pin: i32 = self.conn.query_row(
+            "SELECT COALESCE(is_pinned, 0) FROM prompts WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        let new_pin = if current_pin == 0 { 1 } else { 0 };
+        self.conn.execute(
+            "UPDATE prompts SET is_pinned = ?1 WHERE id = ?2",
+            [new_pin, id],
+        )?;
+        Ok(new_pin == 1)
+    }
+
+    pub fn set_prompt_pin(&self, id: i32, pinned: bool) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute(
+            "UPDATE prompts SET is_pinned = ?1 WHERE id = ?2",
+            [pinned as i32, id],
+        )?;
+        Ok(())
+    }
+
+    /// 更新提示词：先把当前内容保存为历史版本，再写入新内容并自动递增 version
+    ///
+    /// 客户端传入的 version 会被忽略；返回新的版本号。
+    pub fn update_prompt(&self, prompt: &Prompt) -> Result<i32, Box<dyn std::error::Error>> {
+        let id = prompt.id.ok_or("Prompt id is required for update")?;
+        let tx = self.conn.unchecked_transaction()?;
+
+        let current_version: i32 = tx.query_row(
+            "SELECT COALESCE(version, 1) FROM prompts WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO prompt_revisions
+                (prompt_id, version, name, tags, content, content_type, variables_json, app_scopes_json, inject_order)
+             SELECT id, COALESCE(version, 1), name, tags, content, content_type, variables_json, app_scopes_json, inject_order
+             FROM prompts WHERE id = ?1",
+            [id],
+        )?;
+
+        let tags_json = prompt
+            .tags
+            .as_ref()
+            .map(|tags| serde_json::to_string(tags).unwrap_or_default());
+        let new_version = current_version + 1;
+
+        tx.execute(
+            "UPDATE prompts SET name = ?1, tags = ?2, content = ?3, content_type = ?4,
+             variables_json = ?5, app_scopes_json = ?6, inject_order = ?7, version = ?8,
+             abbreviation = ?9, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?10",
+            rusqlite::params![
+                &prompt.name,
+                &tags_json,
+                &prompt.content,
+                &prompt.content_type,
+                &prompt.variables_json,
+                &prompt.app_scopes_json,
+                &prompt.inject_order,
+                &new_version,
+                &normalize_abbreviation(prompt.abbreviation.as_deref()),
+                &id
+            ],
+        )?;
+
+        tx.commit()?;
+        Ok(new_version)
+    }
+
+    /// 列出提示词的历史版本（新版本在前，不含当前版本）
+    pub fn list_prompt_revisions(
+        &self,
+        prompt_id: i32,
+    ) -> Result<Vec<PromptRevision>, Box<dyn std::error::Error>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, tags, content, content_type, variables_json, app_scopes_json, inject_order, version, updated_at
-             FROM prompts"
+            "SELECT id, prompt_id, version, name, tags, content, content_type, variables_json, app_scopes_json, inject_order, created_at
+             FROM prompt_revisions WHERE prompt_id = ?1 ORDER BY version DESC",
         )?;
+        let rows = stmt.query_map([prompt_id], |row| {
+            Ok(PromptRevision {
+                id: row.get(0)?,
+                prompt_id: row.get(1)?,
+                version: row.get(2)?,
+                name: row.get(3)?,
+                tags: row
+                    .get::<_, Option<String>>(4)?
+                    .and_then(|s| serde_json::from_str(&s).ok()),
+                content: row.get(5)?,
+                content_type: row.get(6)?,
+                variables_json: row.get(7)?,
+                app_scopes_json: row.get(8)?,
+                inject_order: row.get(9)?,
+                created_at: row.get(10)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
 
-        let rows = stmt.query_map([], |row| {
-            // 反序列化tags字段
-            let tags_str: Option<String> = row.get(2)?;
-            let tags = match tags_str {
-                Some(s) => {
-                    match serde_json::from_str(&s) {
-                        Ok(tags) => Some(tags),
-                        Err(_) => None, // 如果解析失败，忽略tags
-                    }
-                }
-                None => None,
-            };
+    /// 获取指定版本的内容；version 等于当前版本时返回当前行
+    fn prompt_at_version(
+        &self,
+        prompt_id: i32,
+        version: i32,
+    ) -> Result<Prompt, Box<dyn std::error::Error>> {
+        let current = self.get_prompt_by_id(prompt_id)?;
+        if current.version.unwrap_or(1) == version {
+            return Ok(current);
+        }
+        self.list_prompt_revisions(prompt_id)?
+            .into_iter()
+            .find(|r| r.version == version)
+            .map(|r| Prompt {
+                id: Some(r.prompt_id),
+                name: r.name,
+                tags: r.tags,
+                content: r.content,
+                content_type: r.content_type,
+                variables_json: r.variables_json,
+                app_scopes_json: r.app_scopes_json,
+                inject_order: r.inject_order,
+                version: Some(r.version),
+                updated_at: r.created_at,
+                is_pinned: current.is_pinned,
+                source: current.source.clone(),
+                source_path: current.source_path.clone(),
+                read_only: current.read_only,
+                abbreviation: current.abbreviation.clone(),
+            })
+            .ok_or_else(|| format!("Revision {} of prompt {} not found", version, prompt_id).into())
+    }
+
+    /// 比较两个版本（可包含当前版本）的逐行差异
+    pub fn diff_prompt_revisions(
+        &self,
+        prompt_id: i32,
+        from_version: i32,
+        to_version: i32,
+    ) -> Result<RevisionDiff, Box<dyn std::error::Error>> {
+        let from = self.prompt_at_version(prompt_id, from_version)?;
+        let to = self.prompt_at_version(prompt_id, to_version)?;
+        Ok(RevisionDiff {
+            prompt_id,
+            from_version,
+            to_version,
+            name_change: (from.name != to.name).then(|| (from.name.clone(), to.name.clone())),
+            lines: diff_lines(&from.content, &to.content),
+        })
+    }
+
+    /// 将历史版本恢复为一个新版本（当前内容同样会被保存进历史）
+    pub fn restore_prompt_revision(
+        &self,
+        prompt_id: i32,
+        version: i32,
+    ) -> Result<i32, Box<dyn std::error::Error>> {
+        let snapshot = self.prompt_at_version(prompt_id, version)?;
+        self.update_prompt(&snapshot)
+    }
+
+    pub fn get_all_prompts(&self) -> Result<Vec<Prompt>, Box<dyn std::error::Error>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {} FROM prompts", PROMPT_COLUMNS))?;
+        let rows = stmt.query_map([], prompt_from_row)?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 全文检索提示词：BM25 相关度结合 usage_logs 中的使用频率与最近使用时间排序
+    pub fn search_prompts(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<PromptSearchHit>, Box<dyn std::error::Error>> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
 
-            Ok(Prompt {
-                id: Some(row.get(0)?),
+        // 先按文本相关度取一个候选池，再结合使用统计重新排序
+        let pool = (limit * 5).clamp(50, 500) as i64;
+        let usage_columns = "(SELECT COUNT(*) FROM usage_logs u WHERE u.prompt_id = p.id),
+                (SELECT CAST(MAX(strftime('%s', u.created_at)) AS INTEGER)
+                 FROM usage_logs u WHERE u.prompt_id = p.id)";
+
+        // trigram 分词器无法匹配少于 3 个字符的词，此时回退到 LIKE
+        let use_fts = terms.iter().all(|t| t.chars().count() >= 3);
+        let (sql, patterns) = if use_fts {
+            let match_expr = terms
+                .iter()
+                .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            (
+                format!(
+                    "SELECT p.id, p.name, p.content, p.tags, bm25(prompts_fts, {}), {}, p.source
+                     FROM prompts_fts JOIN prompts p ON p.id = prompts_fts.rowid
+                     WHERE prompts_fts MATCH ?2
+                     ORDER BY bm25(prompts_fts, {})
+                     LIMIT ?1",
+                    SEARCH_BM25_WEIGHTS, usage_columns, SEARCH_BM25_WEIGHTS
+                ),
+                vec![match_expr],
+            )
+        } else {
+            // 与 FTS 一致：每个词都要命中（name、tags 或 content），相关度按各词命中的列累加
+            let (relevance, conditions): (Vec<String>, Vec<String>) = (2..terms.len() + 2)
+                .map(|n| {
+                    (
+                        format!(
+                            "CASE WHEN p.name LIKE ?{n} ESCAPE '\\' THEN -10.0
+                                  WHEN p.tags LIKE ?{n} ESCAPE '\\' THEN -5.0
+                                  ELSE -1.0 END"
+                        ),
+                        format!(
+                            "(p.name LIKE ?{n} ESCAPE '\\' OR p.tags LIKE ?{n} ESCAPE '\\'
+                              OR p.content LIKE ?{n} ESCAPE '\\')"
+                        ),
+                    )
+                })
+                .unzip();
+            let patterns = terms
+                .iter()
+                .map(|t| {
+                    let escaped = t
+                        .replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_");
+                    format!("%{}%", escaped)
+                })
+                .collect();
+            (
+                format!(
+                    "SELECT p.id, p.name, p.content, p.tags, {} AS relevance, {}, p.source
+                     FROM prompts p
+                     WHERE {}
+                     ORDER BY relevance, p.id
+                     LIMIT ?1",
+                    relevance.join(" + "),
+                    usage_columns,
+                    conditions.join(" AND ")
+                ),
+                patterns,
+            )
+        };
+
+        let now_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let params = std::iter::once(rusqlite::types::Value::from(pool))
+            .chain(patterns.into_iter().map(rusqlite::types::Value::from));
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            let tags: Option<Vec<String>> = row
+                .get::<_, Option<String>>(3)?
+                .and_then(|s| serde_json::from_str(&s).ok());
+            let bm25: f64 = row.get(4)?;
+            let usage_count: i64 = row.get(5)?;
+            let last_used_secs: Option<i64> = row.get(6)?;
+
+            // bm25() 越小越相关（负数），取反得到正向相关度
+            let relevance = -bm25;
+            let frequency = 1.0 + SEARCH_FREQUENCY_WEIGHT * (usage_count as f64).ln_1p();
+            let recency = last_used_secs
+                .map(|t| {
+                    let age_days = (now_secs - t).max(0) as f64 / 86_400.0;
+                    SEARCH_RECENCY_WEIGHT / (1.0 + age_days)
+                })
+                .unwrap_or(0.0);
+
+            Ok(PromptSearchHit {
+                id: row.get(0)?,
                 name: row.get(1)?,
+                content: row.get(2)?,
+                category: tags.as_ref().and_then(|t| t.first().cloned()),
                 tags,
-                content: row.get(3)?,
-                content_type: row.get(4)?,
-                variables_json: row.get(5)?,
-                app_scopes_json: row.get(6)?,
-                inject_order: row.get(7)?,
-                version: row.get(8)?,
-                updated_at: row.get(9)?,
+                usage_count,
+                last_used_at: last_used_secs.map(|t| t * 1000),
+                source: row.get(7)?,
+                score: relevance * frequency + recency,
             })
         })?;
 
-        let mut prompts = Vec::new();
-        for prompt in rows {
-            prompts.push(prompt?);
-        }
+        let mut hits = rows.collect::<Result<Vec<_>, _>>()?;
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.id.cmp(&b.id))
+        });
+        hits.truncate(limit);
+        Ok(hits)
+    }
 
-        Ok(prompts)
+    pub fn set_selected_prompt_id(&self, prompt_id: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute(
+            "UPDATE selected_prompt SET prompt_id = ?1 WHERE id = 1",
+            [prompt_id],
+        )?;
+        Ok(())
     }
 
     // 新增方法：获取选中的提示词ID
@@ -365,51 +619,400 @@ impl Database {
     }
 
     pub fn get_prompt_by_id(&self, id: i32) -> Result<Prompt, Box<dyn std::error::Error>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, tags, content, content_type, variables_json, app_scopes_json, inject_order, version, updated_at
-             FROM prompts WHERE id = ?1"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM prompts WHERE id = ?1",
+            PROMPT_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map([id], prompt_from_row)?;
 
-        let mut rows = stmt.query_map([id], |row| {
-            let tags_str: Option<String> = row.get(2)?;
-            let tags = match tags_str {
-                Some(s) => serde_json::from_str(&s).ok(),
-                None => None,
-            };
+        if let Some(prompt) = rows.next() {
+            Ok(prompt?)
+        } else {
+            Err("Prompt not found".into())
+        }
+    }
 
-            Ok(Prompt {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                tags,
-                content: row.get(3)?,
-                content_type: row.get(4)?,
-                variables_json: row.get(5)?,
-                app_scopes_json: row.get(6)?,
-                inject_order: row.get(7)?,
-                version: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        })?;
+    /// 按名称（不区分大小写）查找提示词；重名时取 id 最小者
+    pub fn get_prompt_by_name(&self, name: &str) -> Result<Prompt, Box<dyn std::error::Error>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM prompts WHERE name = ?1 COLLATE NOCASE ORDER BY id LIMIT 1",
+            PROMPT_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map([name.trim()], prompt_from_row)?;
 
         if let Some(prompt) = rows.next() {
             Ok(prompt?)
         } else {
-            Err("Prompt not found".into())
+            Err(format!("Prompt not found: {}", name).into())
         }
     }
 
+    /// 把选中的提示词切换为按 id 排序的下一个（forward 为 false 时上一个），首尾循环；
+    /// 返回新选中的提示词，没有任何提示词时返回 None
+    pub fn cycle_selected_prompt(
+        &self,
+        forward: bool,
+    ) -> Result<Option<Prompt>, Box<dyn std::error::Error>> {
+        let mut prompts = self.get_all_prompts()?;
+        if prompts.is_empty() {
+            return Ok(None);
+        }
+        prompts.sort_by_key(|p| p.id);
+
+        let selected_id = self.get_selected_prompt_id()?;
+        let count = prompts.len();
+        let next = match prompts.iter().position(|p| p.id == Some(selected_id)) {
+            Some(i) if forward => (i + 1) % count,
+            Some(i) => (i + count - 1) % count,
+            // 当前未选中（或已被删除）时从首/尾开始
+            None if forward => 0,
+            None => count - 1,
+        };
+        let prompt = prompts.swap_remove(next);
+        if let Some(id) = prompt.id {
+            self.set_selected_prompt_id(id)?;
+        }
+        Ok(Some(prompt))
+    }
+
+    /// 所有设置了缩写的提示词 (id, 缩写)，按 id 排序
+    pub fn list_abbreviations(&self) -> Result<Vec<(i32, String)>, Box<dyn std::error::Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, abbreviation FROM prompts
+             WHERE abbreviation IS NOT NULL AND TRIM(abbreviation) != ''
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 根据当前上下文选择提示词：先按 app_scopes_json 匹配，
+    /// 没有任何作用域命中时回落到全局选中的提示词
     pub fn find_prompt_for_context(
         &self,
-        _app_name: &str,
-        _window_title: &str,
+        app_name: &str,
+        window_title: &str,
     ) -> Result<Option<Prompt>, Box<dyn std::error::Error>> {
+        let explanation = self.explain_prompt_selection(app_name, window_title)?;
+        log::info!("自动选择: {}", explanation.reason);
+        match explanation.chosen_id {
+            Some(id) => match self.get_prompt_by_id(id) {
+                Ok(p) => Ok(Some(p)),
+                Err(_) => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// 评估所有提示词的作用域并给出选择理由
+    ///
+    /// 排序规则：优先级高者优先 → 规则具体度高者优先 → 全局选中的提示词优先 → id 小者优先
+    pub fn explain_prompt_selection(
+        &self,
+        app_name: &str,
+        window_title: &str,
+    ) -> Result<SelectionExplanation, Box<dyn std::error::Error>> {
         let selected_id = self.get_selected_prompt_id()?;
-        if selected_id == 0 {
-            return Ok(None);
+        let mut candidates = Vec::new();
+
+        for prompt in self.get_all_prompts()? {
+            let Some(prompt_id) = prompt.id else { continue };
+            let evaluation =
+                AppScope::parse(prompt.app_scopes_json.as_deref()).and_then(|scope| match scope {
+                    Some(scope) => scope.evaluate(app_name, window_title).map(Some),
+                    None => Ok(None),
+                });
+
+            let candidate = match evaluation {
+                Ok(Some(m)) => SelectionCandidate {
+                    prompt_id,
+                    prompt_name: prompt.name,
+                    matched: m.matched,
+                    priority: m.priority,
+                    specificity: m.specificity,
+                    reasons: m.reasons,
+                },
+                // 未配置作用域的提示词不参与自动匹配
+                Ok(None) => continue,
+                Err(e) => SelectionCandidate {
+                    prompt_id,
+                    prompt_name: prompt.name,
+                    matched: false,
+                    priority: 0,
+                    specificity: 0,
+                    reasons: vec![e.to_string()],
+                },
+            };
+            candidates.push(candidate);
         }
-        match self.get_prompt_by_id(selected_id) {
-            Ok(p) => Ok(Some(p)),
-            Err(_) => Ok(None),
+
+        candidates.sort_by(|a, b| {
+            b.matched
+                .cmp(&a.matched)
+                .then(b.priority.cmp(&a.priority))
+                .then(b.specificity.cmp(&a.specificity))
+                .then((b.prompt_id == selected_id).cmp(&(a.prompt_id == selected_id)))
+                .then(a.prompt_id.cmp(&b.prompt_id))
+        });
+
+        let (chosen_id, reason) = match candidates.first().filter(|c| c.matched) {
+            Some(best) => (
+                Some(best.prompt_id),
+                format!(
+                    "[{}] 作用域匹配 {} / {}: {}",
+                    best.prompt_name,
+                    app_name,
+                    window_title,
+                    best.reasons.join("; ")
+                ),
+            ),
+            None if selected_id != 0 => (
+                Some(selected_id),
+                format!(
+                    "没有作用域匹配 {}，使用全局选中的提示词 ID={}",
+                    app_name, selected_id
+                ),
+            ),
+            None => (
+                None,
+                format!("没有作用域匹配 {}，且未选中任何提示词", app_name),
+            ),
+        };
+
+        Ok(SelectionExplanation {
+            app_name: app_name.to_string(),
+            window_title: window_title.to_string(),
+            chosen_id,
+            reason,
+            candidates,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(name: &str, tags: &[&str], content: &str) -> Prompt {
+        Prompt {
+            id: None,
+            name: name.to_string(),
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            content: content.to_string(),
+            content_type: None,
+            variables_json: None,
+            app_scopes_json: None,
+            inject_order: None,
+            version: None,
+            updated_at: None,
+            is_pinned: false,
+            source: None,
+            source_path: None,
+            read_only: false,
+            abbreviation: None,
         }
     }
+
+    #[test]
+    fn test_edit_keeps_content_type() {
+        let db = Database::new(":memory:").unwrap();
+        let mut md = prompt("Notes", &[], "# Notes");
+        md.content_type = Some("markdown".to_string());
+        let id = db.create_prompt(&md).unwrap();
+
+        let edit = PromptEdit {
+            id,
+            name: "Notes".to_string(),
+            tags: None,
+            content: "# Meeting notes".to_string(),
+            abbreviation: None,
+        };
+        let mut stored = db.get_prompt_by_id(id).unwrap();
+        edit.apply(&mut stored);
+        db.update_prompt(&stored).unwrap();
+
+        let stored = db.get_prompt_by_id(id).unwrap();
+        assert_eq!(stored.content, "# Meeting notes");
+        assert_eq!(stored.content_type.as_deref(), Some("markdown"));
+    }
+
+    #[test]
+    fn test_search_prompts_ranks_and_tracks_changes() {
+        let db = Database::new(":memory:").unwrap();
+        let review = db
+            .create_prompt(&prompt("Code Review", &["dev"], "Review this diff"))
+            .unwrap();
+        let summary = db
+            .create_prompt(&prompt(
+                "Summarize",
+                &["writing"],
+                "Summarize the code review notes",
+            ))
+            .unwrap();
+
+        // name 命中的权重高于 content
+        let hits = db.search_prompts("review", 10).unwrap();
+        assert_eq!(
+            hits.iter().map(|h| h.id).collect::<Vec<_>>(),
+            vec![review, summary]
+        );
+
+        // 使用次数提升排名
+        for _ in 0..20 {
+            db.log_usage(&UsageRecord {
+                prompt_id: Some(summary),
+                prompt_name: "Summarize",
+                success: true,
+                action: "test",
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        let hits = db.search_prompts("review", 10).unwrap();
+        assert_eq!(hits[0].usage_count, 20);
+
+        // 触发器同步更新与删除
+        db.conn
+            .execute("UPDATE prompts SET name = 'Audit' WHERE id = ?1", [review])
+            .unwrap();
+        db.conn
+            .execute("DELETE FROM prompts WHERE id = ?1", [summary])
+            .unwrap();
+        assert!(
+            db.search_prompts("review", 10)
+                .unwrap()
+                .iter()
+                .all(|h| h.id == review)
+        );
+        assert_eq!(db.search_prompts("audit", 10).unwrap().len(), 1);
+
+        // 短查询走 LIKE 回退
+        assert_eq!(db.search_prompts("au", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_like_fallback_matches_each_term() {
+        let db = Database::new(":memory:").unwrap();
+        let split = db
+            .create_prompt(&prompt("Notes", &[], "ab and then cd"))
+            .unwrap();
+        db.create_prompt(&prompt("Other", &[], "only ab")).unwrap();
+
+        // 各个词分别命中即可，不要求整个查询是连续的子串
+        let hits = db.search_prompts("ab cd", 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.id).collect::<Vec<_>>(), [split]);
+
+        // 候选池按相关度截取：大量 content 命中不会挤掉 name 命中
+        for i in 0..60 {
+            db.create_prompt(&prompt(&format!("Filler {}", i), &[], "xy"))
+                .unwrap();
+        }
+        let named = db.create_prompt(&prompt("xy", &[], "body")).unwrap();
+        assert_eq!(db.search_prompts("xy", 1).unwrap()[0].id, named);
+    }
+
+    #[test]
+    fn test_prompt_by_name_and_cycle_selection() {
+        let db = Database::new(":memory:").unwrap();
+        assert!(db.cycle_selected_prompt(true).unwrap().is_none());
+
+        let a = db.create_prompt(&prompt("Alpha", &[], "a")).unwrap();
+        let b = db.create_prompt(&prompt("Beta", &[], "b")).unwrap();
+        let c = db.create_prompt(&prompt("Gamma", &[], "c")).unwrap();
+        assert_eq!(db.get_prompt_by_name(" beta ").unwrap().id, Some(b));
+        assert!(db.get_prompt_by_name("Delta").is_err());
+
+        let cycle = |forward| db.cycle_selected_prompt(forward).unwrap().unwrap().id;
+        assert_eq!(cycle(true), Some(a));
+        assert_eq!(cycle(true), Some(b));
+        assert_eq!(cycle(true), Some(c));
+        assert_eq!(cycle(true), Some(a));
+        assert_eq!(cycle(false), Some(c));
+        assert_eq!(db.get_selected_prompt_id().unwrap(), c);
+    }
+
+    #[test]
+    fn test_abbreviations_are_trimmed_and_listed() {
+        let db = Database::new(":memory:").unwrap();
+        let with = |name, abbreviation: &str| Prompt {
+            abbreviation: Some(abbreviation.to_string()),
+            ..prompt(name, &[], name)
+        };
+        let sig = db.create_prompt(&with("Signature", " ;;sig ")).unwrap();
+        db.create_prompt(&with("Blank", "  ")).unwrap();
+        db.create_prompt(&prompt("Plain", &[], "p")).unwrap();
+        assert_eq!(
+            db.list_abbreviations().unwrap(),
+            vec![(sig, ";;sig".to_string())]
+        );
+
+        let mut renamed = db.get_prompt_by_id(sig).unwrap();
+        renamed.abbreviation = Some(";;s".to_string());
+        db.update_prompt(&renamed).unwrap();
+        assert_eq!(
+            db.get_prompt_by_id(sig).unwrap().abbreviation.as_deref(),
+            Some(";;s")
+        );
+    }
+
+    #[test]
+    fn test_revisions_snapshot_diff_and_restore() {
+        let db = Database::new(":memory:").unwrap();
+        let id = db
+            .create_prompt(&prompt("Greeting", &[], "Hello\nWorld"))
+            .unwrap();
+
+        let mut edited = db.get_prompt_by_id(id).unwrap();
+        edited.content = "Hello\nRust".to_string();
+        edited.version = Some(42); // 客户端传入的版本号被忽略
+        assert_eq!(db.update_prompt(&edited).unwrap(), 2);
+
+        let revisions = db.list_prompt_revisions(id).unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content, "Hello\nWorld");
+
+        let diff = db.diff_prompt_revisions(id, 1, 2).unwrap();
+        assert!(diff.name_change.is_none());
+        assert_eq!(diff.lines.len(), 3);
+
+        assert_eq!(db.restore_prompt_revision(id, 1).unwrap(), 3);
+        let restored = db.get_prompt_by_id(id).unwrap();
+        assert_eq!(restored.content, "Hello\nWorld");
+        assert_eq!(restored.version, Some(3));
+        assert_eq!(db.list_prompt_revisions(id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_usage_log_records_injection_metrics() {
+        let db = Database::new(":memory:").unwrap();
+        db.log_usage(&UsageRecord {
+            prompt_id: Some(1),
+            prompt_name: "Greeting",
+            target_app: "code",
+            hotkey_used: "Ctrl+Alt+I",
+            fallback_chain: "Clipboard → SendInput",
+            injection_time_ms: 512,
+            success: false,
+            error: Some("Clipboard: paste blocked; SendInput: no focus"),
+            result: "Failed",
+            action: "hotkey_inject",
+            ..Default::default()
+        })
+        .unwrap();
+
+        let logs = db.get_usage_logs(10).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].hotkey_used, "Ctrl+Alt+I");
+        assert_eq!(logs[0].strategy, "");
+        assert_eq!(
+            logs[0].fallback_chain.as_deref(),
+            Some("Clipboard → SendInput")
+        );
+        assert_eq!(logs[0].injection_time_ms, 512);
+        assert!(!logs[0].success);
+        assert_eq!(
+            logs[0].error.as_deref(),
+            Some("Clipboard: paste blocked; SendInput: no focus")
+        );
+    }
 }
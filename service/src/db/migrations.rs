@@ -0,0 +1,522 @@
+// Schema Migrations
+// Ordered, transactional migrations tracked with PRAGMA user_version.
+// Shared by the service and the GUI so both sides always agree on the schema.
+
+use rusqlite::{Connection, Transaction};
+use std::fmt;
+
+/// 单个迁移步骤；version 从 1 开始连续递增
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// 全部迁移，按版本顺序排列
+///
+/// 旧版数据库（user_version = 0）可能已经具备部分表结构（由旧 service 或旧 GUI 创建），
+/// 因此每个迁移都必须对已有的表/列保持幂等。
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "core tables: prompts, usage_logs, selected_prompt",
+        up: migrate_core_tables,
+    },
+    Migration {
+        version: 2,
+        description: "prompts.is_pinned",
+        up: migrate_prompt_pinning,
+    },
+    Migration {
+        version: 3,
+        description: "usage_logs.action and usage_logs.query",
+        up: migrate_usage_log_actions,
+    },
+    Migration {
+        version: 4,
+        description: "prompts_fts full-text index",
+        up: migrate_search_index,
+    },
+    Migration {
+        version: 5,
+        description: "prompt_revisions history",
+        up: migrate_prompt_revisions,
+    },
+    Migration {
+        version: 6,
+        description: "prompts.source* for folder-backed prompt sources",
+        up: migrate_prompt_sources,
+    },
+    Migration {
+        version: 7,
+        description: "usage_logs.fallback_chain",
+        up: migrate_usage_log_fallback_chain,
+    },
+    Migration {
+        version: 8,
+        description: "prompts.abbreviation for text expansion",
+        up: migrate_prompt_abbreviations,
+    },
+];
+
+/// 当前代码支持的最新 schema 版本
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// 读取或写入 user_version 失败
+    Version(rusqlite::Error),
+    /// 数据库版本比当前程序新（可能由更新版本的 PromptKey 写入）
+    TooNew { found: u32, supported: u32 },
+    /// 某个迁移执行失败（该迁移已回滚）
+    Failed {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Version(e) => write!(f, "无法读取数据库版本: {}", e),
+            MigrationError::TooNew { found, supported } => write!(
+                f,
+                "数据库版本 {} 高于当前程序支持的版本 {}",
+                found, supported
+            ),
+            MigrationError::Failed {
+                version,
+                description,
+                source,
+            } => write!(
+                f,
+                "数据库迁移 v{} ({}) 失败: {}",
+                version, description, source
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MigrationError::Version(e) | MigrationError::Failed { source: e, .. } => Some(e),
+            MigrationError::TooNew { .. } => None,
+        }
+    }
+}
+
+/// 将数据库升级到最新版本，返回本次执行的迁移版本号
+pub fn run(conn: &mut Connection) -> Result<Vec<u32>, MigrationError> {
+    run_migrations(conn, MIGRATIONS)
+}
+
+fn run_migrations(
+    conn: &mut Connection,
+    migrations: &[Migration],
+) -> Result<Vec<u32>, MigrationError> {
+    let current: u32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(MigrationError::Version)?;
+    let supported = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > supported {
+        return Err(MigrationError::TooNew {
+            found: current,
+            supported,
+        });
+    }
+
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let failed = |source| MigrationError::Failed {
+            version: migration.version,
+            description: migration.description,
+            source,
+        };
+
+        let tx = conn.transaction().map_err(failed)?;
+        (migration.up)(&tx).map_err(failed)?;
+        tx.pragma_update(None, "user_version", migration.version)
+            .map_err(failed)?;
+        tx.commit().map_err(failed)?;
+
+        log::info!(
+            "数据库迁移 v{} 完成: {}",
+            migration.version,
+            migration.description
+        );
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+fn column_exists(tx: &Transaction, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns.iter().any(|c| c == column))
+}
+
+fn add_column_if_missing(
+    tx: &Transaction,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> rusqlite::Result<()> {
+    if !column_exists(tx, table, column)? {
+        log::info!("为 {} 表添加列 {}", table, column);
+        tx.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_core_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS prompts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            tags TEXT,
+            content TEXT NOT NULL,
+            content_type TEXT,
+            variables_json TEXT,
+            app_scopes_json TEXT,
+            inject_order TEXT,
+            version INTEGER DEFAULT 1,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS usage_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            prompt_id INTEGER,
+            prompt_name TEXT,
+            target_app TEXT,
+            window_title TEXT,
+            hotkey_used TEXT,
+            strategy TEXT,
+            injection_time_ms INTEGER,
+            success INTEGER,
+            error TEXT,
+            result TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS selected_prompt (
+            id INTEGER PRIMARY KEY,
+            prompt_id INTEGER NOT NULL
+        );
+        INSERT OR IGNORE INTO selected_prompt (id, prompt_id) VALUES (1, 0);",
+    )?;
+
+    // 旧版 GUI 创建的 usage_logs 缺少这些列
+    add_column_if_missing(tx, "usage_logs", "prompt_name", "TEXT")?;
+    add_column_if_missing(tx, "usage_logs", "hotkey_used", "TEXT")?;
+    add_column_if_missing(tx, "usage_logs", "injection_time_ms", "INTEGER DEFAULT 0")?;
+    Ok(())
+}
+
+fn migrate_prompt_pinning(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "prompts", "is_pinned", "INTEGER DEFAULT 0")
+}
+
+fn migrate_usage_log_actions(tx: &Transaction) -> rusqlite::Result<()> {
+    // TW007: action (e.g. 'wheel_select', 'hotkey_inject'); T1-001: selector query
+    add_column_if_missing(tx, "usage_logs", "action", "TEXT DEFAULT 'hotkey_inject'")?;
+    add_column_if_missing(tx, "usage_logs", "query", "TEXT")
+}
+
+/// FTS5 外部内容表，镜像 prompts 的 name/tags/content，并用触发器保持同步
+///
+/// 使用 trigram 分词器以支持中文与子串匹配；少于 3 个字符的查询由 search_prompts 走 LIKE 回退。
+fn migrate_search_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS prompts_fts USING fts5(
+            name, tags, content,
+            content='prompts', content_rowid='id', tokenize='trigram'
+        );
+        CREATE TRIGGER IF NOT EXISTS prompts_fts_ai AFTER INSERT ON prompts BEGIN
+            INSERT INTO prompts_fts(rowid, name, tags, content)
+            VALUES (new.id, new.name, new.tags, new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS prompts_fts_ad AFTER DELETE ON prompts BEGIN
+            INSERT INTO prompts_fts(prompts_fts, rowid, name, tags, content)
+            VALUES ('delete', old.id, old.name, old.tags, old.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS prompts_fts_au AFTER UPDATE OF name, tags, content ON prompts BEGIN
+            INSERT INTO prompts_fts(prompts_fts, rowid, name, tags, content)
+            VALUES ('delete', old.id, old.name, old.tags, old.content);
+            INSERT INTO prompts_fts(rowid, name, tags, content)
+            VALUES (new.id, new.name, new.tags, new.content);
+        END;
+        INSERT INTO prompts_fts(prompts_fts) VALUES ('rebuild');",
+    )
+}
+
+/// 提示词历史版本表；删除提示词时一并删除其历史
+fn migrate_prompt_revisions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS prompt_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            prompt_id INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            tags TEXT,
+            content TEXT NOT NULL,
+            content_type TEXT,
+            variables_json TEXT,
+            app_scopes_json TEXT,
+            inject_order TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(prompt_id, version)
+        );
+        CREATE TRIGGER IF NOT EXISTS prompt_revisions_ad AFTER DELETE ON prompts BEGIN
+            DELETE FROM prompt_revisions WHERE prompt_id = old.id;
+        END;",
+    )
+}
+
+/// 来自挂载目录的提示词：source 为来源名称，source_path 为文件绝对路径
+fn migrate_prompt_sources(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "prompts", "source", "TEXT")?;
+    add_column_if_missing(tx, "prompts", "source_path", "TEXT")?;
+    add_column_if_missing(tx, "prompts", "source_writable", "INTEGER DEFAULT 0")?;
+    tx.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_prompts_source_path
+            ON prompts(source_path) WHERE source_path IS NOT NULL;",
+    )
+}
+
+/// 注入时依次尝试过的策略，例如 "Clipboard → SendInput"
+fn migrate_usage_log_fallback_chain(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "usage_logs", "fallback_chain", "TEXT")
+}
+
+/// 文本扩展缩写，例如 ";;sig"；输入缩写加分隔符时注入该提示词
+fn migrate_prompt_abbreviations(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "prompts", "abbreviation", "TEXT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT_USAGE_COLUMNS: &[&str] = &[
+        "prompt_name",
+        "hotkey_used",
+        "injection_time_ms",
+        "action",
+        "query",
+        "fallback_chain",
+    ];
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get::<_, String>(1))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    /// 升级后检查 schema 完整且旧数据保留
+    fn assert_current_schema(conn: &mut Connection) {
+        let applied = run(conn).unwrap();
+        assert_eq!(applied.last().copied(), Some(latest_version()));
+        assert_eq!(user_version(conn), latest_version());
+
+        let usage = columns(conn, "usage_logs");
+        for col in CURRENT_USAGE_COLUMNS {
+            assert!(usage.iter().any(|c| c == col), "missing usage_logs.{}", col);
+        }
+        assert!(columns(conn, "prompts").iter().any(|c| c == "is_pinned"));
+        assert!(columns(conn, "prompts").iter().any(|c| c == "abbreviation"));
+        assert!(
+            columns(conn, "prompt_revisions")
+                .iter()
+                .any(|c| c == "version")
+        );
+
+        // 再次运行不做任何事
+        assert!(run(conn).unwrap().is_empty());
+    }
+
+    fn legacy_prompts(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE prompts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                tags TEXT,
+                content TEXT NOT NULL,
+                content_type TEXT,
+                variables_json TEXT,
+                app_scopes_json TEXT,
+                inject_order TEXT,
+                version INTEGER DEFAULT 1,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO prompts (name, content) VALUES ('Legacy review', 'Review this code');",
+        )
+        .unwrap();
+    }
+
+    fn assert_legacy_prompt_searchable(conn: &Connection) {
+        let found: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM prompts_fts WHERE prompts_fts MATCH '\"review\"'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(found, 1);
+    }
+
+    #[test]
+    fn test_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_current_schema(&mut conn);
+    }
+
+    #[test]
+    fn test_upgrade_legacy_gui_schema() {
+        // 旧版 GUI open_db：精简的 usage_logs，没有 prompt_name/hotkey_used/injection_time_ms
+        let mut conn = Connection::open_in_memory().unwrap();
+        legacy_prompts(&conn);
+        conn.execute_batch(
+            "CREATE TABLE usage_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                prompt_id INTEGER,
+                target_app TEXT,
+                window_title TEXT,
+                strategy TEXT,
+                success INTEGER,
+                error TEXT,
+                result TEXT,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO usage_logs (prompt_id, target_app, strategy, success) VALUES (1, 'code.exe', 'Clipboard', 1);",
+        )
+        .unwrap();
+
+        assert_current_schema(&mut conn);
+        assert_legacy_prompt_searchable(&conn);
+        let app: String = conn
+            .query_row("SELECT target_app FROM usage_logs WHERE id = 1", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(app, "code.exe");
+    }
+
+    #[test]
+    fn test_upgrade_legacy_service_schema() {
+        // 旧版 service：完整 usage_logs 但没有 action/query，prompts 没有 is_pinned
+        let mut conn = Connection::open_in_memory().unwrap();
+        legacy_prompts(&conn);
+        conn.execute_batch(
+            "CREATE TABLE usage_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                prompt_id INTEGER,
+                prompt_name TEXT,
+                target_app TEXT,
+                window_title TEXT,
+                hotkey_used TEXT,
+                strategy TEXT,
+                injection_time_ms INTEGER,
+                success INTEGER,
+                error TEXT,
+                result TEXT,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+            CREATE TABLE selected_prompt (id INTEGER PRIMARY KEY, prompt_id INTEGER NOT NULL);
+            INSERT INTO selected_prompt (id, prompt_id) VALUES (1, 1);",
+        )
+        .unwrap();
+
+        assert_current_schema(&mut conn);
+        assert_legacy_prompt_searchable(&conn);
+        let selected: i32 = conn
+            .query_row(
+                "SELECT prompt_id FROM selected_prompt WHERE id = 1",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(selected, 1);
+    }
+
+    #[test]
+    fn test_upgrade_legacy_pinned_schema_with_action() {
+        // 旧版 GUI + TW007：prompts 已有 is_pinned，usage_logs 已有 action 但没有 query
+        let mut conn = Connection::open_in_memory().unwrap();
+        legacy_prompts(&conn);
+        conn.execute_batch(
+            "ALTER TABLE prompts ADD COLUMN is_pinned INTEGER DEFAULT 0;
+            UPDATE prompts SET is_pinned = 1;
+            CREATE TABLE usage_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                prompt_id INTEGER,
+                prompt_name TEXT,
+                target_app TEXT,
+                window_title TEXT,
+                hotkey_used TEXT,
+                strategy TEXT,
+                injection_time_ms INTEGER,
+                success INTEGER,
+                error TEXT,
+                result TEXT,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                action TEXT DEFAULT 'hotkey_inject'
+            );",
+        )
+        .unwrap();
+
+        assert_current_schema(&mut conn);
+        let pinned: i32 = conn
+            .query_row("SELECT is_pinned FROM prompts WHERE id = 1", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(pinned, 1);
+    }
+
+    #[test]
+    fn test_failed_migration_is_rolled_back_and_reported() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "ok",
+                up: |tx| tx.execute_batch("CREATE TABLE a (id INTEGER)"),
+            },
+            Migration {
+                version: 2,
+                description: "broken",
+                up: |tx| tx.execute_batch("CREATE TABLE b (id INTEGER); SELECT * FROM missing"),
+            },
+        ];
+
+        let err = run_migrations(&mut conn, &migrations).unwrap_err();
+        assert!(matches!(err, MigrationError::Failed { version: 2, .. }));
+        assert_eq!(user_version(&conn), 1);
+        assert!(columns(&conn, "b").is_empty());
+    }
+
+    #[test]
+    fn test_newer_database_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+        assert!(matches!(run(&mut conn), Err(MigrationError::TooNew { .. })));
+    }
+}
@@ -0,0 +1,349 @@
+// Read models for the GUI: Quick Selection Panel, PromptWheel and usage log views
+
+use super::Database;
+use serde::{Deserialize, Serialize};
+
+// T1-002: Quick Selection Panel prompt data structure
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PromptForSelector {
+    pub id: i32,
+    pub name: String,
+    pub content: String,           // Full content (frontend will truncate)
+    pub category: Option<String>,  // Extracted from tags[0]
+    pub tags: Option<Vec<String>>, // Full tag list
+    pub usage_count: i64,          // Usage statistics
+    pub last_used_at: Option<i64>, // Last used timestamp (Unix ms)
+    pub source: Option<String>,    // Mounted folder name, None for SQLite prompts
+}
+
+// T1-004: Quick Selection Panel statistics data structure
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SelectorStats {
+    pub top_prompts: Vec<TopPromptStat>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TopPromptStat {
+    pub name: String,
+    pub usage_count: i64,
+}
+
+// TW006: PromptWheel data structures
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WheelPromptsPage {
+    pub prompts: Vec<WheelPrompt>,
+    pub current_page: u32,
+    pub total_pages: u32,
+    pub total_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WheelPrompt {
+    pub id: i32,
+    pub name: String,
+    pub content: String,
+    pub source: Option<String>,
+}
+
+// Wheel: prompts with pin status for wheel config panel
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PromptWithPin {
+    pub id: i32,
+    pub name: String,
+    pub content: String,
+    pub is_pinned: bool,
+    pub source: Option<String>,
+}
+
+/// 使用日志（字段名与前端日志页保持一致）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UsageLogEntry {
+    pub id: i32,
+    pub prompt_id: Option<i32>,
+    pub prompt_name: String,
+    pub target_app: String,
+    pub window_title: String,
+    pub hotkey_used: String,
+    pub strategy: String,
+    pub fallback_chain: Option<String>, // e.g. "Clipboard → SendInput"
+    pub injection_time_ms: i64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub result: String,
+    pub created_at: i64, // Unix ms
+}
+
+type DbResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+impl Database {
+    // T1-002: All prompts with selector usage statistics
+    pub fn get_prompts_for_selector(&self) -> DbResult<Vec<PromptForSelector>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT
+                p.id,
+                p.name,
+                p.content,
+                p.tags,
+                COUNT(u.id) as usage_count,
+                MAX(strftime('%s', u.created_at)) * 1000 as last_used_at_ms,
+                p.source
+             FROM prompts p
+             LEFT JOIN usage_logs u ON u.prompt_id = p.id AND u.action = 'selector_select'
+             GROUP BY p.id
+             ORDER BY p.id ASC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let tags: Option<Vec<String>> = row
+                .get::<_, Option<String>>(3)?
+                .and_then(|s| serde_json::from_str(&s).ok());
+
+            Ok(PromptForSelector {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                content: row.get(2)?,
+                category: tags.as_ref().and_then(|t| t.first().cloned()),
+                tags,
+                usage_count: row.get(4)?,
+                last_used_at: row.get::<_, Option<i64>>(5)?,
+                source: row.get(6)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    // T1-003: Log Quick Selection Panel usage events
+    pub fn log_selector_usage(
+        &self,
+        prompt_id: i32,
+        prompt_name: &str,
+        query: Option<&str>,
+    ) -> DbResult<()> {
+        self.conn.execute(
+            "INSERT INTO usage_logs (
+                prompt_id, prompt_name, target_app, window_title, action, query, strategy, success, created_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'))",
+            rusqlite::params![
+                prompt_id,
+                prompt_name,
+                "Selector Panel",        // target_app (fixed)
+                "Quick Selection Panel", // window_title
+                "selector_select",       // action
+                query,
+                "selector", // strategy
+                1,          // success (always true for selection)
+            ],
+        )?;
+        Ok(())
+    }
+
+    // T1-004: Top most-used prompts based on selector_select actions
+    pub fn get_selector_stats(&self, limit: u32) -> DbResult<SelectorStats> {
+        let mut stmt = self.conn.prepare(
+            "SELECT
+                p.name,
+                COUNT(u.id) as usage_count
+             FROM usage_logs u
+             INNER JOIN prompts p ON p.id = u.prompt_id
+             WHERE u.action = 'selector_select'
+             GROUP BY u.prompt_id
+             ORDER BY usage_count DESC
+             LIMIT ?1",
+        )?;
+
+        let rows = stmt.query_map([limit], |row| {
+            Ok(TopPromptStat {
+                name: row.get(0)?,
+                usage_count: row.get(1)?,
+            })
+        })?;
+
+        Ok(SelectorStats {
+            top_prompts: rows.collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    // TW006: Prompts for the wheel, ordered by pinned → most recent use → frequency
+    pub fn get_top_prompts_paginated(
+        &self,
+        page: u32,
+        per_page: u32,
+    ) -> DbResult<WheelPromptsPage> {
+        let total_count: u32 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM prompts", [], |row| row.get(0))?;
+        let total_pages = if per_page == 0 {
+            0
+        } else {
+            total_count.div_ceil(per_page)
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT
+                p.id,
+                p.name,
+                p.content,
+                p.source
+             FROM prompts p
+             LEFT JOIN usage_logs u ON u.prompt_id = p.id
+             GROUP BY p.id
+             ORDER BY
+                COALESCE(p.is_pinned, 0) DESC,
+                MAX(COALESCE(u.created_at, 0)) DESC,
+                COUNT(u.id) DESC
+             LIMIT ?1 OFFSET ?2",
+        )?;
+
+        let rows = stmt.query_map([per_page, page * per_page], |row| {
+            Ok(WheelPrompt {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                content: row.get(2)?,
+                source: row.get(3)?,
+            })
+        })?;
+
+        Ok(WheelPromptsPage {
+            prompts: rows.collect::<Result<Vec<_>, _>>()?,
+            current_page: page,
+            total_pages,
+            total_count,
+        })
+    }
+
+    // Wheel: all prompts with pin status (pinned first)
+    pub fn get_prompts_with_pin(&self) -> DbResult<Vec<PromptWithPin>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, content, COALESCE(is_pinned, 0) as is_pinned, source
+             FROM prompts
+             ORDER BY COALESCE(is_pinned, 0) DESC, id ASC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(PromptWithPin {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                content: row.get(2)?,
+                is_pinned: row.get::<_, i32>(3)? == 1,
+                source: row.get(4)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 最近的使用日志（新记录在前）
+    pub fn get_usage_logs(&self, limit: u32) -> DbResult<Vec<UsageLogEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT
+                u.id,
+                u.prompt_id,
+                COALESCE(u.prompt_name, p.name) AS prompt_name,
+                u.target_app,
+                u.window_title,
+                u.hotkey_used,
+                u.strategy,
+                CASE
+                    WHEN u.success = 1 THEN
+                        CASE WHEN u.injection_time_ms IS NULL OR u.injection_time_ms < 1 THEN 1 ELSE u.injection_time_ms END
+                    ELSE COALESCE(u.injection_time_ms, 0)
+                END AS injection_time_ms,
+                u.success,
+                u.error,
+                u.result,
+                CAST(strftime('%s', u.created_at) AS INTEGER) AS created_at_epoch,
+                u.fallback_chain
+             FROM usage_logs u
+             LEFT JOIN prompts p ON p.id = u.prompt_id
+             ORDER BY u.created_at DESC
+             LIMIT ?1",
+        )?;
+
+        let rows = stmt.query_map([limit], |row| {
+            Ok(UsageLogEntry {
+                id: row.get(0)?,
+                prompt_id: row.get(1)?,
+                prompt_name: row
+                    .get::<_, Option<String>>(2)?
+                    .unwrap_or_else(|| "未知".to_string()),
+                target_app: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                window_title: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                hotkey_used: row
+                    .get::<_, Option<String>>(5)?
+                    .unwrap_or_else(|| "未知".to_string()),
+                strategy: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                fallback_chain: row.get(12)?,
+                injection_time_ms: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+                success: row.get::<_, Option<i32>>(8)?.unwrap_or(0) == 1,
+                error: row.get(9)?,
+                result: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+                created_at: row.get::<_, Option<i64>>(11)?.unwrap_or(0) * 1000,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    pub fn clear_usage_logs(&self) -> DbResult<()> {
+        self.conn.execute("DELETE FROM usage_logs", [])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Prompt;
+
+    #[test]
+    fn test_selector_and_wheel_queries() {
+        let db = Database::new(":memory:").unwrap();
+        let mut ids = Vec::new();
+        for name in ["A", "B", "C"] {
+            let prompt = Prompt {
+                id: None,
+                name: name.to_string(),
+                tags: Some(vec![format!("{}-tag", name)]),
+                content: format!("{} content", name),
+                content_type: None,
+                variables_json: None,
+                app_scopes_json: None,
+                inject_order: None,
+                version: None,
+                updated_at: None,
+                is_pinned: false,
+                source: None,
+                source_path: None,
+                read_only: false,
+                abbreviation: None,
+            };
+            ids.push(db.create_prompt(&prompt).unwrap());
+        }
+
+        db.log_selector_usage(ids[1], "B", Some("b")).unwrap();
+        db.log_selector_usage(ids[1], "B", None).unwrap();
+        assert!(db.toggle_prompt_pin(ids[2]).unwrap());
+
+        let selector = db.get_prompts_for_selector().unwrap();
+        assert_eq!(selector[1].usage_count, 2);
+        assert_eq!(selector[0].category.as_deref(), Some("A-tag"));
+
+        let stats = db.get_selector_stats(2).unwrap();
+        assert_eq!(stats.top_prompts[0].name, "B");
+
+        // 置顶优先，其次是最近使用
+        let page = db.get_top_prompts_paginated(0, 2).unwrap();
+        assert_eq!(page.total_pages, 2);
+        assert_eq!(
+            page.prompts.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![ids[2], ids[1]]
+        );
+
+        let logs = db.get_usage_logs(100).unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].strategy, "selector");
+        db.clear_usage_logs().unwrap();
+        assert!(db.get_usage_logs(100).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+// Bookkeeping for prompts mirrored from mounted folders (see crate::library)
+
+use super::{Database, PROMPT_COLUMNS, Prompt, prompt_from_row};
+
+type DbResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+impl Database {
+    /// 标记提示词来自某个目录中的文件
+    pub fn set_prompt_source(
+        &self,
+        id: i32,
+        source: &str,
+        source_path: &str,
+        writable: bool,
+    ) -> DbResult<()> {
+        self.conn.execute(
+            "UPDATE prompts SET source = ?1, source_path = ?2, source_writable = ?3 WHERE id = ?4",
+            rusqlite::params![source, source_path, writable as i32, id],
+        )?;
+        Ok(())
+    }
+
+    /// 某个来源目录当前已索引的提示词
+    pub fn get_prompts_by_source(&self, source: &str) -> DbResult<Vec<Prompt>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM prompts WHERE source = ?1 ORDER BY id ASC",
+            PROMPT_COLUMNS
+        ))?;
+        let rows = stmt.query_map([source], prompt_from_row)?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 删除不在 active 列表中的来源（配置中已移除的目录）所索引的提示词，返回删除数量
+    pub fn remove_unmounted_sources(&self, active: &[String]) -> DbResult<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT source FROM prompts WHERE source IS NOT NULL")?;
+        let stale: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|s| !active.contains(s))
+            .collect();
+
+        let mut removed = 0;
+        for source in stale {
+            log::info!("移除已卸载来源 {} 的提示词", source);
+            removed += self
+                .conn
+                .execute("DELETE FROM prompts WHERE source = ?1", [&source])?;
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,102 @@
+// Line-level diff (LCS) used by prompt revision history
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+    /// 旧文本中的行号（从 1 开始），新增行为 None
+    pub old_line: Option<usize>,
+    /// 新文本中的行号（从 1 开始），删除行为 None
+    pub new_line: Option<usize>,
+}
+
+/// 计算两段文本的逐行差异
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = a[i..] 与 b[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0usize, 0usize);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(line(DiffKind::Equal, a[i], Some(i + 1), Some(j + 1)));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] > lcs[i + 1][j]) {
+            out.push(line(DiffKind::Added, b[j], None, Some(j + 1)));
+            j += 1;
+        } else {
+            out.push(line(DiffKind::Removed, a[i], Some(i + 1), None));
+            i += 1;
+        }
+    }
+    out
+}
+
+fn line(kind: DiffKind, text: &str, old_line: Option<usize>, new_line: Option<usize>) -> DiffLine {
+    DiffLine {
+        kind,
+        text: text.to_string(),
+        old_line,
+        new_line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(lines: &[DiffLine]) -> Vec<(DiffKind, &str)> {
+        lines.iter().map(|l| (l.kind, l.text.as_str())).collect()
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc", "a\nc\nd");
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (DiffKind::Equal, "a"),
+                (DiffKind::Removed, "b"),
+                (DiffKind::Equal, "c"),
+                (DiffKind::Added, "d"),
+            ]
+        );
+        assert_eq!(diff[3].new_line, Some(3));
+    }
+
+    #[test]
+    fn test_diff_identical_and_empty() {
+        assert!(
+            diff_lines("x\ny", "x\ny")
+                .iter()
+                .all(|l| l.kind == DiffKind::Equal)
+        );
+        assert_eq!(
+            kinds(&diff_lines("", "new")),
+            vec![(DiffKind::Added, "new")]
+        );
+    }
+}
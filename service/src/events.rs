@@ -0,0 +1,192 @@
+// Service Event Bus
+// 热键、GUI 注入请求、键盘输入、配置变更与定时器都把事件发送到同一个通道，
+// 主循环阻塞等待下一个事件，空闲时不占用 CPU。新的事件源只需持有一个 Sender 或通过 forward 接入。
+
+use crate::expansion::KeyInput;
+use crate::hotkey::HotkeyEvent;
+use crate::ipc::inject_server::InjectRequest;
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 配置文件连续的变更事件在安静这么久之后才通知一次（保存时通常产生多次事件）
+const CONFIG_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceEvent {
+    Hotkey(HotkeyEvent),
+    /// GUI 的点选注入请求
+    Inject(InjectRequest),
+    /// 键盘监听报告的输入，及其进入事件总线的时间（用于忽略注入期间产生的按键）
+    Key(KeyInput, Instant),
+    /// 配置文件已修改
+    ConfigChanged,
+    Timer(Timer),
+}
+
+/// 周期性的定时事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timer {
+    /// 重新读取提示词缩写（GUI 中修改的缩写在一个周期内生效）
+    RefreshAbbreviations,
+}
+
+pub struct EventBus {
+    tx: Sender<ServiceEvent>,
+    rx: Receiver<ServiceEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        EventBus { tx, rx }
+    }
+
+    pub fn sender(&self) -> Sender<ServiceEvent> {
+        self.tx.clone()
+    }
+
+    /// 阻塞等待下一个事件
+    pub fn recv(&self) -> Option<ServiceEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// 把一个已有的事件通道接入总线：在后台线程中逐个转换并转发，
+    /// 任一端关闭后线程退出
+    pub fn forward<T, F>(&self, rx: Receiver<T>, wrap: F)
+    where
+        T: Send + 'static,
+        F: Fn(T) -> ServiceEvent + Send + 'static,
+    {
+        let tx = self.sender();
+        thread::spawn(move || {
+            while let Ok(item) = rx.recv() {
+                if tx.send(wrap(item)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// 每隔 interval 发送一次定时事件
+    pub fn every(&self, interval: Duration, timer: Timer) {
+        let tx = self.sender();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                if tx.send(ServiceEvent::Timer(timer)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// 监听配置文件，修改后发送 ConfigChanged。监听所在目录而非文件本身：
+    /// 编辑器与 GUI 保存时可能以替换文件的方式写入
+    pub fn watch_config(&self, path: &Path) -> notify::Result<ConfigWatcher> {
+        let path = path.to_path_buf();
+        let dir = path
+            .parent()
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        let (events_tx, events_rx) = mpsc::channel::<notify::Result<notify::Event>>();
+        let mut watcher = notify::recommended_watcher(events_tx)?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        let tx = self.sender();
+        thread::spawn(move || {
+            while let Ok(first) = events_rx.recv() {
+                let mut changed = touches(&first, &path);
+                while let Ok(event) = events_rx.recv_timeout(CONFIG_DEBOUNCE) {
+                    changed |= touches(&event, &path);
+                }
+                if changed && tx.send(ServiceEvent::ConfigChanged).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(ConfigWatcher { _watcher: watcher })
+    }
+}
+
+/// 持有配置文件监听器；drop 后停止监听
+pub struct ConfigWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+fn touches(event: &notify::Result<notify::Event>, path: &Path) -> bool {
+    match event {
+        Ok(event) => {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == path.file_name())
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_sources_feed_one_channel() {
+        let bus = EventBus::new();
+        let (hotkey_tx, hotkey_rx) = mpsc::channel();
+        let (inject_tx, inject_rx) = mpsc::channel();
+        bus.forward(hotkey_rx, ServiceEvent::Hotkey);
+        bus.forward(inject_rx, ServiceEvent::Inject);
+
+        hotkey_tx.send(HotkeyEvent::Pressed(2)).unwrap();
+        assert_eq!(
+            bus.recv(),
+            Some(ServiceEvent::Hotkey(HotkeyEvent::Pressed(2)))
+        );
+
+        let request = InjectRequest {
+            prompt_id: 7,
+            variables: HashMap::new(),
+        };
+        inject_tx.send(request.clone()).unwrap();
+        assert_eq!(bus.recv(), Some(ServiceEvent::Inject(request)));
+
+        bus.sender().send(ServiceEvent::ConfigChanged).unwrap();
+        assert_eq!(bus.recv(), Some(ServiceEvent::ConfigChanged));
+        assert!(bus.rx.recv_timeout(Duration::from_millis(20)).is_err());
+    }
+
+    #[test]
+    fn test_timer_and_config_watch() {
+        let bus = EventBus::new();
+        bus.every(Duration::from_millis(10), Timer::RefreshAbbreviations);
+        assert_eq!(
+            bus.recv(),
+            Some(ServiceEvent::Timer(Timer::RefreshAbbreviations))
+        );
+
+        let bus = EventBus::new();
+        let dir = std::env::temp_dir().join(format!("promptkey-events-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        let _watcher = bus.watch_config(&path).unwrap();
+        // 同目录下的其他文件不触发
+        std::fs::write(dir.join("other.yaml"), "a: 1").unwrap();
+        assert!(bus.rx.recv_timeout(Duration::from_millis(500)).is_err());
+        std::fs::write(&path, "a: 1").unwrap();
+        assert_eq!(
+            bus.rx.recv_timeout(Duration::from_secs(5)).ok(),
+            Some(ServiceEvent::ConfigChanged)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
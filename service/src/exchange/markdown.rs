@@ -0,0 +1,174 @@
+// Markdown + YAML front-matter: one prompt per file
+//
+// ---
+// name: Code Review
+// tags: [dev]
+// ---
+// Review this {{lang}} code...
+
+use super::{ExchangeError, ExportedPrompt};
+use std::collections::HashSet;
+use std::path::Path;
+
+const FENCE: &str = "---";
+
+/// 将单个提示词渲染为带 front-matter 的 Markdown 文本
+pub fn to_markdown(prompt: &ExportedPrompt) -> Result<String, ExchangeError> {
+    let meta = ExportedPrompt {
+        content: String::new(),
+        ..prompt.clone()
+    };
+    let yaml = serde_yaml::to_string(&meta).map_err(|e| ExchangeError::Parse(e.to_string()))?;
+    Ok(format!(
+        "{}\n{}{}\n{}\n",
+        FENCE, yaml, FENCE, prompt.content
+    ))
+}
+
+/// 解析 Markdown 文本；没有 front-matter 时整个文件都是正文
+pub fn from_markdown(text: &str, fallback_name: &str) -> Result<ExportedPrompt, ExchangeError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let (meta, body) = match split_front_matter(text) {
+        Some((yaml, body)) => {
+            let meta: ExportedPrompt = serde_yaml::from_str(yaml).map_err(|e| {
+                ExchangeError::Parse(format!("{} 的 front-matter: {}", fallback_name, e))
+            })?;
+            (meta, body)
+        }
+        None => (empty_meta(), text),
+    };
+
+    // 写出时在正文末尾补了一个换行，读回时去掉
+    let body = body
+        .strip_suffix("\r\n")
+        .or_else(|| body.strip_suffix('\n'))
+        .unwrap_or(body);
+
+    Ok(ExportedPrompt {
+        name: if meta.name.trim().is_empty() {
+            fallback_name.to_string()
+        } else {
+            meta.name
+        },
+        content: body.to_string(),
+        ..meta
+    })
+}
+
+fn empty_meta() -> ExportedPrompt {
+    ExportedPrompt {
+        name: String::new(),
+        tags: Vec::new(),
+        content_type: None,
+        variables: None,
+        app_scopes: None,
+        inject_order: None,
+        pinned: false,
+        abbreviation: None,
+        content: String::new(),
+    }
+}
+
+// 返回 (front-matter YAML, 正文)
+fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let rest = text
+        .strip_prefix("---\r\n")
+        .or_else(|| text.strip_prefix("---\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FENCE {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// 写入目录，每个提示词一个文件（文件名由名称生成，重名时追加序号）
+pub fn write_dir(dir: &Path, prompts: &[ExportedPrompt]) -> Result<(), ExchangeError> {
+    std::fs::create_dir_all(dir)?;
+    let mut used = HashSet::new();
+    for prompt in prompts {
+        let stem = file_stem(&prompt.name);
+        let mut file_name = format!("{}.md", stem);
+        let mut n = 2;
+        while !used.insert(file_name.to_lowercase()) {
+            file_name = format!("{}-{}.md", stem, n);
+            n += 1;
+        }
+        std::fs::write(dir.join(file_name), to_markdown(prompt)?)?;
+    }
+    Ok(())
+}
+
+/// 读取目录中所有 .md 文件（按文件名排序）
+pub fn read_dir(dir: &Path) -> Result<Vec<ExportedPrompt>, ExchangeError> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| e.eq_ignore_ascii_case("md"))
+        })
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("prompt");
+            from_markdown(&std::fs::read_to_string(path)?, stem)
+        })
+        .collect()
+}
+
+// 文件名只保留字母数字（含中文）、'-' 与 '_'
+fn file_stem(name: &str) -> String {
+    let mut stem = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' {
+            stem.push(c);
+        } else if !stem.ends_with('-') {
+            stem.push('-');
+        }
+    }
+    let stem = stem.trim_matches('-');
+    if stem.is_empty() {
+        "prompt".to_string()
+    } else {
+        stem.chars().take(64).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let prompt = ExportedPrompt {
+            name: "Code Review".to_string(),
+            tags: vec!["dev".to_string()],
+            variables: Some(serde_json::json!([{"name": "lang"}])),
+            pinned: true,
+            content: "---\nReview {{lang}}\n".to_string(),
+            ..empty_meta()
+        };
+        let text = to_markdown(&prompt).unwrap();
+        assert!(text.starts_with("---\nname: Code Review\n"));
+        assert_eq!(from_markdown(&text, "ignored").unwrap(), prompt);
+    }
+
+    #[test]
+    fn test_plain_markdown_uses_file_name() {
+        let prompt = from_markdown("Just text\n", "翻译 助手").unwrap();
+        assert_eq!(prompt.name, "翻译 助手");
+        assert_eq!(prompt.content, "Just text");
+        assert_eq!(file_stem("翻译 助手 / v2"), "翻译-助手-v2");
+    }
+}
@@ -0,0 +1,552 @@
+// Prompt Library Exchange
+// Exports prompts to a versioned JSON/YAML bundle or a folder of Markdown
+// files with front-matter, and imports them back with a merge strategy.
+
+pub mod markdown;
+
+use crate::db::{Database, Prompt, normalize_abbreviation};
+use crate::library;
+use crate::scope::AppScope;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// 当前导出格式版本；导入时拒绝更高的版本
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeFormat {
+    Json,
+    Yaml,
+    /// 目录，每个提示词一个 .md 文件
+    Markdown,
+}
+
+impl ExchangeFormat {
+    /// 根据路径推断格式：.json / .yaml / .yml，其余（目录）视为 Markdown
+    pub fn from_path(path: &Path) -> ExchangeFormat {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => ExchangeFormat::Json,
+            Some("yaml") | Some("yml") => ExchangeFormat::Yaml,
+            _ => ExchangeFormat::Markdown,
+        }
+    }
+}
+
+/// 名称冲突时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    #[default]
+    Skip,
+    Overwrite,
+    /// 以 "名称 (2)" 的形式另存为新提示词
+    Rename,
+}
+
+/// 导出文件的顶层结构
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptBundle {
+    pub format_version: u32,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    pub prompts: Vec<ExportedPrompt>,
+}
+
+/// 单个提示词的可移植表示（不含 id / version 等本地字段）
+///
+/// variables 与 app_scopes 以结构化数据导出，便于在 YAML / front-matter 中手工编辑。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedPrompt {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_scopes: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inject_order: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abbreviation: Option<String>,
+    /// Markdown 导出时正文写在 front-matter 之后，此字段留空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExchangeError {
+    Io(String),
+    Parse(String),
+    UnsupportedVersion { found: u32, supported: u32 },
+    InvalidPrompt { name: String, message: String },
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeError::Io(e) => write!(f, "读写导出文件失败: {}", e),
+            ExchangeError::Parse(e) => write!(f, "导入文件格式错误: {}", e),
+            ExchangeError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "导入文件版本 {} 高于当前支持的版本 {}，请升级 PromptKey",
+                found, supported
+            ),
+            ExchangeError::InvalidPrompt { name, message } => {
+                write!(f, "提示词 '{}' 无效: {}", name, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExchangeError {}
+
+impl From<std::io::Error> for ExchangeError {
+    fn from(e: std::io::Error) -> Self {
+        ExchangeError::Io(e.to_string())
+    }
+}
+
+impl ExportedPrompt {
+    pub fn from_prompt(prompt: &Prompt) -> Self {
+        ExportedPrompt {
+            name: prompt.name.clone(),
+            tags: prompt.tags.clone().unwrap_or_default(),
+            content_type: prompt.content_type.clone(),
+            variables: json_field(prompt.variables_json.as_deref()),
+            app_scopes: json_field(prompt.app_scopes_json.as_deref()),
+            inject_order: prompt.inject_order.clone(),
+            pinned: prompt.is_pinned,
+            abbreviation: prompt.abbreviation.clone(),
+            content: prompt.content.clone(),
+        }
+    }
+
+    /// 转换为待写入数据库的 Prompt，并校验变量定义与作用域
+    pub fn to_prompt(&self) -> Result<Prompt, ExchangeError> {
+        let invalid = |message: String| ExchangeError::InvalidPrompt {
+            name: self.name.clone(),
+            message,
+        };
+        if self.name.trim().is_empty() {
+            return Err(invalid("名称不能为空".to_string()));
+        }
+
+        let variables_json = self.variables.as_ref().map(json_text);
+        crate::template::parse_schema(variables_json.as_deref())
+            .map_err(|e| invalid(e.to_string()))?;
+        let app_scopes_json = self.app_scopes.as_ref().map(json_text);
+        if let Some(scope) =
+            AppScope::parse(app_scopes_json.as_deref()).map_err(|e| invalid(e.to_string()))?
+        {
+            scope.validate().map_err(|e| invalid(e.to_string()))?;
+        }
+
+        Ok(Prompt {
+            id: None,
+            name: self.name.clone(),
+            tags: (!self.tags.is_empty()).then(|| self.tags.clone()),
+            content: self.content.clone(),
+            content_type: self.content_type.clone(),
+            variables_json,
+            app_scopes_json,
+            inject_order: self.inject_order.clone(),
+            version: None,
+            updated_at: None,
+            is_pinned: self.pinned,
+            source: None,
+            source_path: None,
+            read_only: false,
+            abbreviation: self.abbreviation.clone(),
+        })
+    }
+}
+
+// 库中的 JSON 文本 → 结构化值；无法解析时原样保留为字符串
+fn json_field(raw: Option<&str>) -> Option<serde_json::Value> {
+    let raw = raw
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && *s != "null")?;
+    Some(serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string())))
+}
+
+fn json_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl PromptBundle {
+    pub fn new(prompts: Vec<ExportedPrompt>) -> Self {
+        PromptBundle {
+            format_version: FORMAT_VERSION,
+            exported_at: Some(chrono::Local::now().to_rfc3339()),
+            prompts,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, ExchangeError> {
+        serde_json::to_string_pretty(self).map_err(|e| ExchangeError::Parse(e.to_string()))
+    }
+
+    pub fn to_yaml(&self) -> Result<String, ExchangeError> {
+        serde_yaml::to_string(self).map_err(|e| ExchangeError::Parse(e.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ExchangeError> {
+        let bundle: PromptBundle =
+            serde_json::from_str(text).map_err(|e| ExchangeError::Parse(e.to_string()))?;
+        bundle.check_version()
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self, ExchangeError> {
+        let bundle: PromptBundle =
+            serde_yaml::from_str(text).map_err(|e| ExchangeError::Parse(e.to_string()))?;
+        bundle.check_version()
+    }
+
+    fn check_version(self) -> Result<Self, ExchangeError> {
+        if self.format_version > FORMAT_VERSION {
+            return Err(ExchangeError::UnsupportedVersion {
+                found: self.format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
+        Ok(self)
+    }
+
+    /// 写入文件（JSON/YAML）或目录（Markdown）
+    pub fn write_to(&self, path: &Path, format: ExchangeFormat) -> Result<(), ExchangeError> {
+        match format {
+            ExchangeFormat::Json => std::fs::write(path, self.to_json()?)?,
+            ExchangeFormat::Yaml => std::fs::write(path, self.to_yaml()?)?,
+            ExchangeFormat::Markdown => markdown::write_dir(path, &self.prompts)?,
+        }
+        Ok(())
+    }
+
+    pub fn read_from(path: &Path, format: ExchangeFormat) -> Result<Self, ExchangeError> {
+        match format {
+            ExchangeFormat::Json => Self::from_json(&std::fs::read_to_string(path)?),
+            ExchangeFormat::Yaml => Self::from_yaml(&std::fs::read_to_string(path)?),
+            ExchangeFormat::Markdown => Ok(Self::new(markdown::read_dir(path)?)),
+        }
+    }
+}
+
+/// 导入时对单个提示词采取的动作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ImportAction {
+    Created,
+    Overwritten {
+        id: i32,
+    },
+    Renamed {
+        new_name: String,
+    },
+    Skipped {
+        id: i32,
+    },
+    /// 与库中同名提示词完全一致，无需写入
+    Unchanged {
+        id: i32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportItem {
+    pub name: String,
+    #[serde(flatten)]
+    pub action: ImportAction,
+}
+
+/// 导入结果；dry_run 为 true 时数据库未被修改
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub overwritten: usize,
+    pub renamed: usize,
+    pub skipped: usize,
+    pub unchanged: usize,
+    pub items: Vec<ImportItem>,
+}
+
+impl ImportReport {
+    fn push(&mut self, name: &str, action: ImportAction) {
+        match action {
+            ImportAction::Created => self.created += 1,
+            ImportAction::Overwritten { .. } => self.overwritten += 1,
+            ImportAction::Renamed { .. } => self.renamed += 1,
+            ImportAction::Skipped { .. } => self.skipped += 1,
+            ImportAction::Unchanged { .. } => self.unchanged += 1,
+        }
+        self.items.push(ImportItem {
+            name: name.to_string(),
+            action,
+        });
+    }
+}
+
+/// 导出库中全部提示词
+pub fn export_bundle(db: &Database) -> Result<PromptBundle, Box<dyn std::error::Error>> {
+    let prompts = db.get_all_prompts()?;
+    Ok(PromptBundle::new(
+        prompts.iter().map(ExportedPrompt::from_prompt).collect(),
+    ))
+}
+
+/// 按合并策略导入；dry_run 时只生成报告
+///
+/// 所有条目先整体校验，任何一个无效都不会写入数据库。
+pub fn import_bundle(
+    db: &Database,
+    bundle: &PromptBundle,
+    strategy: MergeStrategy,
+    dry_run: bool,
+) -> Result<ImportReport, Box<dyn std::error::Error>> {
+    let incoming = bundle
+        .prompts
+        .iter()
+        .map(ExportedPrompt::to_prompt)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut existing: HashMap<String, Prompt> = db
+        .get_all_prompts()?
+        .into_iter()
+        .map(|p| (p.name.clone(), p))
+        .collect();
+
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    for mut prompt in incoming {
+        let name = prompt.name.clone();
+        let current = match existing.get(&name) {
+            None => {
+                if !dry_run {
+                    prompt.id = Some(db.create_prompt(&prompt)?);
+                }
+                existing.insert(name.clone(), prompt);
+                report.push(&name, ImportAction::Created);
+                continue;
+            }
+            Some(current) => current,
+        };
+        let id = current.id.unwrap_or_default();
+
+        if same_prompt(current, &prompt) {
+            report.push(&name, ImportAction::Unchanged { id });
+            continue;
+        }
+
+        match strategy {
+            MergeStrategy::Skip => report.push(&name, ImportAction::Skipped { id }),
+            // 只读目录中的提示词以源文件为准
+            MergeStrategy::Overwrite if current.read_only => {
+                log::warn!("提示词 '{}' 来自只读目录，导入时跳过", name);
+                report.push(&name, ImportAction::Skipped { id });
+            }
+            MergeStrategy::Overwrite => {
+                if !dry_run {
+                    // 先更新置顶状态，写回源文件时一并保存
+                    db.set_prompt_pin(id, prompt.is_pinned)?;
+                    prompt.id = Some(id);
+                    library::save_prompt(db, &prompt)?;
+                }
+                report.push(&name, ImportAction::Overwritten { id });
+            }
+            MergeStrategy::Rename => {
+                let new_name = unique_name(&name, |n| existing.contains_key(n));
+                prompt.name = new_name.clone();
+                if !dry_run {
+                    prompt.id = Some(db.create_prompt(&prompt)?);
+                }
+                existing.insert(new_name.clone(), prompt);
+                report.push(&name, ImportAction::Renamed { new_name });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+pub(crate) fn same_prompt(a: &Prompt, b: &Prompt) -> bool {
+    a.content == b.content
+        && a.tags.clone().unwrap_or_default() == b.tags.clone().unwrap_or_default()
+        && a.content_type == b.content_type
+        && json_field(a.variables_json.as_deref()) == json_field(b.variables_json.as_deref())
+        && json_field(a.app_scopes_json.as_deref()) == json_field(b.app_scopes_json.as_deref())
+        && a.inject_order == b.inject_order
+        && a.is_pinned == b.is_pinned
+        && normalize_abbreviation(a.abbreviation.as_deref())
+            == normalize_abbreviation(b.abbreviation.as_deref())
+}
+
+/// "名称 (2)"、"名称 (3)" ... 直到不冲突
+fn unique_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported(name: &str, content: &str) -> ExportedPrompt {
+        ExportedPrompt {
+            name: name.to_string(),
+            tags: vec!["dev".to_string()],
+            content_type: None,
+            variables: Some(serde_json::json!([{"name": "lang", "required": false}])),
+            app_scopes: Some(serde_json::json!(["code.exe"])),
+            inject_order: None,
+            pinned: true,
+            abbreviation: Some(format!(";;{}", name.to_lowercase())),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trip_json_and_yaml() {
+        let bundle = PromptBundle::new(vec![exported("Review", "Review {{lang}}\nplease")]);
+        assert_eq!(
+            PromptBundle::from_json(&bundle.to_json().unwrap()).unwrap(),
+            bundle
+        );
+        assert_eq!(
+            PromptBundle::from_yaml(&bundle.to_yaml().unwrap()).unwrap(),
+            bundle
+        );
+
+        let too_new = r#"{"format_version": 99, "prompts": []}"#;
+        assert!(matches!(
+            PromptBundle::from_json(too_new),
+            Err(ExchangeError::UnsupportedVersion { found: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_import_strategies_and_dry_run() {
+        let db = Database::new(":memory:").unwrap();
+        let bundle = PromptBundle::new(vec![exported("Review", "v1"), exported("Fix", "fix it")]);
+        let report = import_bundle(&db, &bundle, MergeStrategy::Skip, false).unwrap();
+        assert_eq!(report.created, 2);
+
+        // 导出后再导入：内容一致的条目不会重复写入
+        let round_trip = export_bundle(&db).unwrap();
+        let report = import_bundle(&db, &round_trip, MergeStrategy::Rename, false).unwrap();
+        assert_eq!(report.unchanged, 2);
+
+        let changed = PromptBundle::new(vec![exported("Review", "v2")]);
+        let report = import_bundle(&db, &changed, MergeStrategy::Overwrite, true).unwrap();
+        assert_eq!(report.overwritten, 1);
+        assert_eq!(db.get_all_prompts().unwrap()[0].content, "v1");
+
+        let report = import_bundle(&db, &changed, MergeStrategy::Rename, false).unwrap();
+        assert_eq!(
+            report.items[0].action,
+            ImportAction::Renamed {
+                new_name: "Review (2)".to_string()
+            }
+        );
+        assert_eq!(db.get_all_prompts().unwrap().len(), 3);
+
+        let report = import_bundle(&db, &changed, MergeStrategy::Overwrite, false).unwrap();
+        assert_eq!(report.overwritten, 1);
+        assert!(
+            db.get_all_prompts()
+                .unwrap()
+                .iter()
+                .any(|p| p.content == "v2")
+        );
+
+        // 只修改了缩写的条目也会被覆盖
+        let mut renamed = exported("Fix", "fix it");
+        renamed.abbreviation = Some(";;repair".to_string());
+        let bundle = PromptBundle::new(vec![renamed]);
+        let report = import_bundle(&db, &bundle, MergeStrategy::Overwrite, false).unwrap();
+        assert_eq!(report.overwritten, 1);
+        let fix = db.get_all_prompts().unwrap();
+        let fix = fix.iter().find(|p| p.name == "Fix").unwrap();
+        assert_eq!(fix.abbreviation.as_deref(), Some(";;repair"));
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_scope() {
+        let db = Database::new(":memory:").unwrap();
+        let mut bad = exported("Bad", "x");
+        bad.app_scopes = Some(serde_json::json!({"include": [{"title": "("}]}));
+        let mut good = exported("Good", "y");
+        good.pinned = false;
+        let bundle = PromptBundle::new(vec![good, bad]);
+        assert!(import_bundle(&db, &bundle, MergeStrategy::Skip, false).is_err());
+        assert!(db.get_all_prompts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_overwrite_respects_source_folders() {
+        let dir = std::env::temp_dir().join(format!("promptkey-import-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("ro")).unwrap();
+        std::fs::create_dir_all(dir.join("rw")).unwrap();
+        std::fs::write(
+            dir.join("ro/locked.md"),
+            "---\nname: Locked\n---\nfrom file\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("rw/shared.md"), "---\nname: Shared\n---\nold\n").unwrap();
+
+        let db = Database::new(":memory:").unwrap();
+        for (folder, writable) in [("ro", false), ("rw", true)] {
+            let source = library::PromptSource::from_config(&crate::config::PromptSourceConfig {
+                path: dir.join(folder).to_string_lossy().into_owned(),
+                name: Some(folder.to_string()),
+                writable,
+            });
+            library::sync_source(&db, &source).unwrap();
+        }
+
+        let bundle = PromptBundle::new(vec![exported("Locked", "new"), exported("Shared", "new")]);
+        let report = import_bundle(&db, &bundle, MergeStrategy::Overwrite, false).unwrap();
+        assert!(matches!(
+            report.items[0].action,
+            ImportAction::Skipped { .. }
+        ));
+        assert!(matches!(
+            report.items[1].action,
+            ImportAction::Overwritten { .. }
+        ));
+
+        let contents: HashMap<String, String> = db
+            .get_all_prompts()
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.name, p.content))
+            .collect();
+        assert_eq!(contents["Locked"], "from file");
+        assert_eq!(contents["Shared"], "new");
+        // 读写目录中的源文件同步更新
+        let text = std::fs::read_to_string(dir.join("rw/shared.md")).unwrap();
+        assert!(text.ends_with("---\nnew\n"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,288 @@
+// Text Expansion
+// 输入提示词的缩写（如 ";;sig"）再输入分隔符时，用退格删除缩写并注入该提示词。
+// Expander 只处理平台无关的 KeyInput，不依赖系统键盘钩子，可以直接测试；
+// 按键监听由平台后端（Win32 低级键盘钩子 / X11 RECORD 扩展）提供。
+
+#[cfg(windows)]
+mod win32;
+#[cfg(not(windows))]
+mod x11;
+
+use crate::config::ExpansionConfig;
+use std::sync::mpsc::{self, Receiver};
+
+/// 键盘监听后端报告的一次输入
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyInput {
+    /// 输入了一个字符（Enter 为 '\n'，Tab 为 '\t'）
+    Char(char),
+    Backspace,
+    /// 光标可能已移动或焦点已切换（方向键、Ctrl 组合键、鼠标点击、切换窗口等），
+    /// 之前输入的字符不再与光标相邻
+    Reset,
+}
+
+/// 一次匹配：删除 erase 个字符（缩写与其后的分隔符）后注入 prompt_id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub prompt_id: i32,
+    /// 配置中的缩写（用作使用日志中的触发方式）
+    pub abbreviation: String,
+    pub erase: usize,
+}
+
+struct Abbreviation {
+    prompt_id: i32,
+    text: String,
+    /// 用于比较的字符（不区分大小写时为小写）
+    key: Vec<char>,
+}
+
+pub struct Expander {
+    abbreviations: Vec<Abbreviation>,
+    delimiters: Vec<char>,
+    case_sensitive: bool,
+    /// 光标前最近输入的字符，最多保留最长缩写 + 1 个（用于判断缩写前是否为词边界）
+    buffer: Vec<char>,
+}
+
+impl Expander {
+    pub fn new(config: &ExpansionConfig, abbreviations: Vec<(i32, String)>) -> Self {
+        let mut expander = Expander {
+            abbreviations: Vec::new(),
+            delimiters: config.delimiters.chars().collect(),
+            case_sensitive: config.case_sensitive,
+            buffer: Vec::new(),
+        };
+        expander.set_abbreviations(abbreviations);
+        expander
+    }
+
+    /// 替换缩写列表（提示词被修改后调用）。包含分隔符的缩写永远无法输入完整，记录警告后忽略；
+    /// 重复的缩写以先出现者为准
+    pub fn set_abbreviations(&mut self, abbreviations: Vec<(i32, String)>) {
+        self.abbreviations.clear();
+        for (prompt_id, text) in abbreviations {
+            let text = text.trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+            if text.chars().any(|c| self.is_delimiter(c)) {
+                log::warn!(
+                    "缩写 \"{}\" 包含分隔符，已忽略 (提示词 {})",
+                    text,
+                    prompt_id
+                );
+                continue;
+            }
+            let key = self.normalize(&text);
+            if let Some(existing) = self.abbreviations.iter().find(|a| a.key == key) {
+                log::warn!(
+                    "缩写 \"{}\" 已由提示词 {} 使用，忽略提示词 {}",
+                    text,
+                    existing.prompt_id,
+                    prompt_id
+                );
+                continue;
+            }
+            self.abbreviations.push(Abbreviation {
+                prompt_id,
+                text,
+                key,
+            });
+        }
+        self.trim_buffer();
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// 处理一次输入；输入分隔符时若光标前恰好是一个完整的缩写则返回匹配
+    pub fn feed(&mut self, input: KeyInput) -> Option<Expansion> {
+        match input {
+            KeyInput::Char(c) if self.is_delimiter(c) => {
+                let expansion = self.match_abbreviation();
+                if expansion.is_some() {
+                    self.buffer.clear();
+                } else {
+                    self.push(c);
+                }
+                expansion
+            }
+            KeyInput::Char(c) => {
+                self.push(c);
+                None
+            }
+            KeyInput::Backspace => {
+                self.buffer.pop();
+                None
+            }
+            KeyInput::Reset => {
+                self.reset();
+                None
+            }
+        }
+    }
+
+    /// 缓冲区末尾的最长缩写，且缩写之前是分隔符、空白或输入的开头
+    fn match_abbreviation(&self) -> Option<Expansion> {
+        self.abbreviations
+            .iter()
+            .filter(|a| self.buffer.ends_with(&a.key))
+            .filter(|a| {
+                let start = self.buffer.len() - a.key.len();
+                start == 0 || {
+                    let before = self.buffer[start - 1];
+                    before.is_whitespace() || self.is_delimiter(before)
+                }
+            })
+            .max_by_key(|a| a.key.len())
+            .map(|a| Expansion {
+                prompt_id: a.prompt_id,
+                abbreviation: a.text.clone(),
+                erase: a.key.len() + 1,
+            })
+    }
+
+    fn push(&mut self, c: char) {
+        let c = if self.case_sensitive { c } else { fold_case(c) };
+        self.buffer.push(c);
+        self.trim_buffer();
+    }
+
+    fn trim_buffer(&mut self) {
+        let capacity = self
+            .abbreviations
+            .iter()
+            .map(|a| a.key.len() + 1)
+            .max()
+            .unwrap_or(0);
+        if self.buffer.len() > capacity {
+            self.buffer.drain(..self.buffer.len() - capacity);
+        }
+    }
+
+    fn normalize(&self, text: &str) -> Vec<char> {
+        if self.case_sensitive {
+            text.chars().collect()
+        } else {
+            text.chars().map(fold_case).collect()
+        }
+    }
+
+    fn is_delimiter(&self, c: char) -> bool {
+        self.delimiters.contains(&c)
+    }
+}
+
+/// 小写形式；个别字符的小写不止一个字符，这种情况下保留原字符
+fn fold_case(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+/// 启动系统键盘监听，输入以 KeyInput 发送到返回的通道。
+/// 程序自身注入的按键不会被报告（X11 下无法区分，由调用方在注入后清空通道）
+pub fn start_monitor() -> Result<Receiver<KeyInput>, String> {
+    let (tx, rx) = mpsc::channel();
+    #[cfg(windows)]
+    win32::start(tx)?;
+    #[cfg(not(windows))]
+    x11::start(tx)?;
+    Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expander(case_sensitive: bool) -> Expander {
+        let config = ExpansionConfig {
+            enabled: true,
+            delimiters: " \t".to_string(),
+            case_sensitive,
+            apps: Vec::new(),
+        };
+        Expander::new(
+            &config,
+            vec![
+                (1, ";;sig".to_string()),
+                (2, "sig".to_string()),
+                (3, " ;;addr ".to_string()),
+                (4, "a b".to_string()),
+                (5, ";;SIG".to_string()),
+            ],
+        )
+    }
+
+    fn type_text(expander: &mut Expander, text: &str) -> Vec<Expansion> {
+        text.chars()
+            .filter_map(|c| expander.feed(KeyInput::Char(c)))
+            .collect()
+    }
+
+    fn expansion(prompt_id: i32, abbreviation: &str) -> Expansion {
+        Expansion {
+            prompt_id,
+            abbreviation: abbreviation.to_string(),
+            erase: abbreviation.chars().count() + 1,
+        }
+    }
+
+    #[test]
+    fn test_abbreviation_expands_on_delimiter() {
+        let mut e = expander(false);
+        // 最长匹配优先；缩写两端的空白被忽略
+        assert_eq!(
+            type_text(&mut e, "hi ;;sig ;;addr\t"),
+            vec![expansion(1, ";;sig"), expansion(3, ";;addr")]
+        );
+        assert_eq!(type_text(&mut e, "sig "), vec![expansion(2, "sig")]);
+        // 没有分隔符时不触发
+        assert!(type_text(&mut e, ";;sig.").is_empty());
+    }
+
+    #[test]
+    fn test_word_boundary_backspace_and_reset() {
+        let mut e = expander(false);
+        // 缩写前必须是词边界
+        assert!(type_text(&mut e, "design ").is_empty());
+
+        // 退格修正后的输入仍能匹配
+        type_text(&mut e, ";;six");
+        e.feed(KeyInput::Backspace);
+        e.feed(KeyInput::Backspace);
+        assert_eq!(type_text(&mut e, "ig "), vec![expansion(1, ";;sig")]);
+
+        // 光标移动后之前的输入作废，但之后新输入的缩写仍从头匹配
+        type_text(&mut e, ";;s");
+        e.feed(KeyInput::Reset);
+        assert!(type_text(&mut e, "ig ").is_empty());
+        assert_eq!(type_text(&mut e, "sig "), vec![expansion(2, "sig")]);
+    }
+
+    #[test]
+    fn test_case_handling() {
+        // 不区分大小写时 ";;SIG" 与 ";;sig" 重复，保留先出现的
+        let mut e = expander(false);
+        assert_eq!(type_text(&mut e, ";;SiG "), vec![expansion(1, ";;sig")]);
+
+        let mut e = expander(true);
+        assert_eq!(type_text(&mut e, ";;SIG "), vec![expansion(5, ";;SIG")]);
+        assert!(type_text(&mut e, ";;Sig ").is_empty());
+    }
+
+    #[test]
+    fn test_abbreviations_containing_delimiters_are_ignored() {
+        let mut e = expander(false);
+        assert!(type_text(&mut e, "a b ").is_empty());
+        // 缓冲区只保留最长缩写 + 1 个字符
+        type_text(&mut e, &"x".repeat(100));
+        assert_eq!(e.buffer.len(), ";;addr".len() + 1);
+        assert_eq!(type_text(&mut e, " sig "), vec![expansion(2, "sig")]);
+    }
+}
@@ -0,0 +1,156 @@
+// Win32 keyboard monitor: WH_KEYBOARD_LL low-level hook on a dedicated message-loop thread,
+// key codes translated to characters with ToUnicodeEx in the foreground window's keyboard layout
+
+use super::KeyInput;
+use std::cell::RefCell;
+use std::sync::mpsc::{self, Sender};
+use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Threading::{AttachThreadInput, GetCurrentThreadId};
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+/// ToUnicodeEx 标志：不修改内核中的键盘状态，避免打断目标应用的死键组合（Windows 10 1607+）
+const TO_UNICODE_NO_STATE_CHANGE: u32 = 0x4;
+
+struct HookState {
+    tx: Sender<KeyInput>,
+    /// 上一次按键时的前台窗口；切换窗口后之前的输入作废
+    foreground: HWND,
+}
+
+thread_local! {
+    // 低级钩子回调在安装钩子的线程上执行
+    static HOOK_STATE: RefCell<Option<HookState>> = const { RefCell::new(None) };
+}
+
+pub fn start(tx: Sender<KeyInput>) -> Result<(), String> {
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        HOOK_STATE.with(|state| {
+            *state.borrow_mut() = Some(HookState {
+                tx,
+                foreground: HWND::default(),
+            })
+        });
+        let hook = match unsafe {
+            SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_proc), HINSTANCE::default(), 0)
+        } {
+            Ok(hook) => hook,
+            Err(e) => {
+                let _ = ready_tx.send(Err(format!("无法安装键盘钩子: {}", e)));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+
+        // 钩子回调由消息循环驱动
+        let mut msg = MSG::default();
+        while unsafe { GetMessageW(&mut msg, HWND::default(), 0, 0) }.0 > 0 {}
+        let _ = unsafe { UnhookWindowsHookEx(hook) };
+    });
+    ready_rx
+        .recv()
+        .map_err(|_| "键盘监听线程意外退出".to_string())?
+}
+
+unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32 && matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN) {
+        let info = unsafe { &*(lparam.0 as *const KBDLLHOOKSTRUCT) };
+        // 忽略 SendInput 产生的按键（包括本程序注入的文本与退格）
+        if info.flags.0 & LLKHF_INJECTED.0 == 0 {
+            HOOK_STATE.with(|state| {
+                if let Some(state) = state.borrow_mut().as_mut() {
+                    state.handle(info);
+                }
+            });
+        }
+    }
+    unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+}
+
+impl HookState {
+    fn handle(&mut self, info: &KBDLLHOOKSTRUCT) {
+        let foreground = unsafe { GetForegroundWindow() };
+        if foreground != self.foreground {
+            self.foreground = foreground;
+            let _ = self.tx.send(KeyInput::Reset);
+        }
+        if let Some(input) = translate(VIRTUAL_KEY(info.vkCode as u16), info.scanCode, foreground) {
+            let _ = self.tx.send(input);
+        }
+    }
+}
+
+fn is_down(key: VIRTUAL_KEY) -> bool {
+    unsafe { GetAsyncKeyState(key.0 as i32) as u16 & 0x8000 != 0 }
+}
+
+/// 前台线程的 CapsLock 状态：GetKeyState 只反映调用线程的输入状态，
+/// 临时附加到前台线程的输入队列后读取
+fn caps_lock_on(thread_id: u32) -> bool {
+    let current = unsafe { GetCurrentThreadId() };
+    let attached = thread_id != 0
+        && thread_id != current
+        && unsafe { AttachThreadInput(current, thread_id, true) }.as_bool();
+    let on = unsafe { GetKeyState(VK_CAPITAL.0 as i32) } & 1 != 0;
+    if attached {
+        let _ = unsafe { AttachThreadInput(current, thread_id, false) };
+    }
+    on
+}
+
+/// 把按键转换为输入；单独的修饰键与死键返回 None
+fn translate(vk: VIRTUAL_KEY, scan_code: u32, foreground: HWND) -> Option<KeyInput> {
+    match vk {
+        VK_SHIFT | VK_LSHIFT | VK_RSHIFT | VK_CONTROL | VK_LCONTROL | VK_RCONTROL | VK_MENU
+        | VK_LMENU | VK_RMENU | VK_CAPITAL | VK_LWIN | VK_RWIN => return None,
+        VK_BACK => return Some(KeyInput::Backspace),
+        VK_RETURN => return Some(KeyInput::Char('\n')),
+        VK_TAB => return Some(KeyInput::Char('\t')),
+        _ => {}
+    }
+
+    let ctrl = is_down(VK_CONTROL);
+    let alt = is_down(VK_MENU);
+    // Ctrl / Alt 组合键是快捷键（可能移动光标或修改文本）；Ctrl+Alt 是 AltGr，仍可输入字符
+    if ctrl != alt || is_down(VK_LWIN) || is_down(VK_RWIN) {
+        return Some(KeyInput::Reset);
+    }
+
+    // 钩子线程的键盘状态不反映其他线程的按键，按当前物理状态构造
+    let mut state = [0u8; 256];
+    for key in [VK_SHIFT, VK_CONTROL, VK_MENU] {
+        if is_down(key) {
+            state[key.0 as usize] = 0x80;
+        }
+    }
+    // 按前台窗口所在线程的键盘布局转换：钩子线程的布局不随用户在目标应用中切换布局而改变
+    let thread_id = unsafe { GetWindowThreadProcessId(foreground, None) };
+    if caps_lock_on(thread_id) {
+        state[VK_CAPITAL.0 as usize] = 0x01;
+    }
+    let layout = unsafe { GetKeyboardLayout(thread_id) };
+
+    let mut buffer = [0u16; 8];
+    let written = unsafe {
+        ToUnicodeEx(
+            vk.0 as u32,
+            scan_code,
+            &state,
+            &mut buffer,
+            TO_UNICODE_NO_STATE_CHANGE,
+            layout,
+        )
+    };
+    match written {
+        // 死键：等待与下一个按键组合
+        n if n < 0 => None,
+        // 不产生字符的按键（方向键、Home、Delete、功能键等）
+        0 => Some(KeyInput::Reset),
+        n => char::decode_utf16(buffer[..n as usize].iter().copied())
+            .next()
+            .and_then(Result::ok)
+            .filter(|c| !c.is_control())
+            .map_or(Some(KeyInput::Reset), |c| Some(KeyInput::Char(c))),
+    }
+}
@@ -0,0 +1,245 @@
+// X11 keyboard monitor: RECORD extension device events on a dedicated connection,
+// keycodes translated with the keyboard mapping fetched at startup
+
+use super::KeyInput;
+use std::sync::mpsc::{self, Sender};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::record::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{self, ConnectionExt as _, Keycode};
+use x11rb::x11_utils::TryParse;
+
+// keysym 取值见 X11/keysymdef.h
+const XK_BACKSPACE: u32 = 0xff08;
+const XK_TAB: u32 = 0xff09;
+const XK_RETURN: u32 = 0xff0d;
+const XK_KP_ENTER: u32 = 0xff8d;
+const XK_KP_0: u32 = 0xffb0;
+const XK_KP_9: u32 = 0xffb9;
+const XK_SHIFT_L: u32 = 0xffe1;
+const XK_SHIFT_R: u32 = 0xffe2;
+const XK_CONTROL_L: u32 = 0xffe3;
+const XK_CONTROL_R: u32 = 0xffe4;
+const XK_CAPS_LOCK: u32 = 0xffe5;
+const XK_ALT_L: u32 = 0xffe9;
+const XK_ALT_R: u32 = 0xffea;
+const XK_SUPER_L: u32 = 0xffeb;
+const XK_SUPER_R: u32 = 0xffec;
+const XK_ISO_LEVEL3_SHIFT: u32 = 0xfe03;
+
+/// RECORD 回复的 category（recordproto）
+const RECORD_FROM_SERVER: u8 = 0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Modifier {
+    Shift,
+    /// Ctrl、Alt 与 Super：按住时的按键是快捷键
+    Shortcut,
+    /// AltGr 等第三层切换键，按住时仍在输入字符
+    Other,
+}
+
+/// 键盘映射与当前按住的修饰键
+struct Keyboard {
+    min_keycode: Keycode,
+    keysyms_per_keycode: usize,
+    keysyms: Vec<u32>,
+    held: Vec<(Keycode, Modifier)>,
+    caps_lock: bool,
+}
+
+pub fn start(tx: Sender<KeyInput>) -> Result<(), String> {
+    // recordproto 建议使用两个连接：一个创建上下文，另一个接收数据
+    let (ctrl, _) = x11rb::connect(None).map_err(|e| format!("无法连接 X server: {}", e))?;
+    let (data, _) = x11rb::connect(None).map_err(|e| format!("无法连接 X server: {}", e))?;
+    if ctrl
+        .extension_information(record::X11_EXTENSION_NAME)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err("X server 不支持 RECORD 扩展".to_string());
+    }
+    let mut keyboard = Keyboard::load(&ctrl)?;
+
+    let context = ctrl.generate_id().map_err(|e| e.to_string())?;
+    let empty = record::Range8 { first: 0, last: 0 };
+    let empty_ext = record::ExtRange {
+        major: empty,
+        minor: record::Range16 { first: 0, last: 0 },
+    };
+    let range = record::Range {
+        core_requests: empty,
+        core_replies: empty,
+        ext_requests: empty_ext,
+        ext_replies: empty_ext,
+        delivered_events: empty,
+        device_events: record::Range8 {
+            first: xproto::KEY_PRESS_EVENT,
+            last: xproto::BUTTON_PRESS_EVENT,
+        },
+        errors: empty,
+        client_started: false,
+        client_died: false,
+    };
+    ctrl.record_create_context(context, 0, &[record::CS::ALL_CLIENTS.into()], &[range])
+        .map_err(|e| e.to_string())?
+        .check()
+        .map_err(|e| format!("无法创建 RECORD 上下文: {}", e))?;
+
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        // 控制连接需要与上下文同生命周期
+        let _ctrl = ctrl;
+        let replies = match data.record_enable_context(context) {
+            Ok(replies) => {
+                let _ = ready_tx.send(Ok(()));
+                replies
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(format!("无法启用 RECORD 上下文: {}", e)));
+                return;
+            }
+        };
+        for reply in replies {
+            let reply = match reply {
+                Ok(reply) => reply,
+                Err(e) => {
+                    log::error!("键盘监听中断: {}", e);
+                    return;
+                }
+            };
+            if reply.category != RECORD_FROM_SERVER || reply.client_swapped {
+                continue;
+            }
+            for input in keyboard.parse(&reply.data) {
+                if tx.send(input).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    ready_rx
+        .recv()
+        .map_err(|_| "键盘监听线程意外退出".to_string())?
+}
+
+impl Keyboard {
+    fn load(conn: &impl Connection) -> Result<Self, String> {
+        let setup = conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        // 监听开始前 CapsLock 可能已经打开：以 Lock 修饰键的当前状态为准
+        let caps_lock = match setup.roots.first() {
+            Some(screen) => conn
+                .query_pointer(screen.root)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?
+                .mask
+                .contains(xproto::KeyButMask::LOCK),
+            None => false,
+        };
+        Ok(Keyboard {
+            min_keycode: min,
+            keysyms_per_keycode: mapping.keysyms_per_keycode.max(1) as usize,
+            keysyms: mapping.keysyms,
+            held: Vec::new(),
+            caps_lock,
+        })
+    }
+
+    /// 解析一段 RECORD 数据（可能包含多个 32 字节的事件）
+    fn parse(&mut self, mut data: &[u8]) -> Vec<KeyInput> {
+        let mut inputs = Vec::new();
+        while data.len() >= 32 {
+            match data[0] {
+                xproto::KEY_PRESS_EVENT => {
+                    if let Ok((event, _)) = xproto::KeyPressEvent::try_parse(data) {
+                        inputs.extend(self.press(event.detail));
+                    }
+                }
+                xproto::KEY_RELEASE_EVENT => {
+                    if let Ok((event, _)) = xproto::KeyReleaseEvent::try_parse(data) {
+                        self.held.retain(|(code, _)| *code != event.detail);
+                    }
+                }
+                // 鼠标点击可能移动了光标
+                xproto::BUTTON_PRESS_EVENT => inputs.push(KeyInput::Reset),
+                _ => {}
+            }
+            data = &data[32..];
+        }
+        inputs
+    }
+
+    fn keysym(&self, keycode: Keycode, column: usize) -> u32 {
+        let index = (keycode.saturating_sub(self.min_keycode)) as usize * self.keysyms_per_keycode;
+        let syms = self
+            .keysyms
+            .get(index..index + self.keysyms_per_keycode)
+            .unwrap_or(&[]);
+        match syms.get(column).copied().unwrap_or(0) {
+            // 没有第二列时使用第一列
+            0 => syms.first().copied().unwrap_or(0),
+            sym => sym,
+        }
+    }
+
+    fn press(&mut self, keycode: Keycode) -> Option<KeyInput> {
+        let base = self.keysym(keycode, 0);
+        let modifier = match base {
+            XK_SHIFT_L | XK_SHIFT_R => Some(Modifier::Shift),
+            XK_CONTROL_L | XK_CONTROL_R | XK_ALT_L | XK_ALT_R | XK_SUPER_L | XK_SUPER_R => {
+                Some(Modifier::Shortcut)
+            }
+            XK_ISO_LEVEL3_SHIFT => Some(Modifier::Other),
+            XK_CAPS_LOCK => {
+                self.caps_lock = !self.caps_lock;
+                return None;
+            }
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            if !self.held.iter().any(|(code, _)| *code == keycode) {
+                self.held.push((keycode, modifier));
+            }
+            return None;
+        }
+        if self.held.iter().any(|(_, m)| *m == Modifier::Shortcut) {
+            return Some(KeyInput::Reset);
+        }
+
+        let shift = self.held.iter().any(|(_, m)| *m == Modifier::Shift);
+        let input = match base {
+            XK_BACKSPACE => KeyInput::Backspace,
+            XK_RETURN | XK_KP_ENTER => KeyInput::Char('\n'),
+            XK_TAB => KeyInput::Char('\t'),
+            XK_KP_0..=XK_KP_9 => KeyInput::Char((b'0' + (base - XK_KP_0) as u8) as char),
+            _ => match keysym_char(self.keysym(keycode, shift as usize)) {
+                Some(c) if self.caps_lock && c.is_alphabetic() => {
+                    let c = if shift {
+                        c.to_lowercase().next().unwrap_or(c)
+                    } else {
+                        c.to_uppercase().next().unwrap_or(c)
+                    };
+                    KeyInput::Char(c)
+                }
+                Some(c) => KeyInput::Char(c),
+                None => KeyInput::Reset,
+            },
+        };
+        Some(input)
+    }
+}
+
+/// Latin-1 keysym 与字符码相同；Unicode keysym 为 0x01000000 + 码位
+fn keysym_char(keysym: u32) -> Option<char> {
+    match keysym {
+        0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+        0x0100_0000..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+        _ => None,
+    }
+}
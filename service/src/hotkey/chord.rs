@@ -0,0 +1,480 @@
+// Chord
+// 平台无关的热键组合，例如 "Ctrl+Alt+F13"。解析不区分大小写、允许空格与常见别名，
+// 显示时输出规范形式（修饰键固定为 Ctrl+Alt+Shift+Win 的顺序），两者可以往返。
+// 平台后端只需把 Key 映射为各自的键码（Win32 虚拟键码 / X11 keysym）。
+
+use std::fmt;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Win / Super
+    pub meta: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// 字母 A–Z（大写）
+    Letter(char),
+    /// 主键盘数字 0–9
+    Digit(u8),
+    /// 功能键 F1–F24
+    F(u8),
+    /// 小键盘数字 0–9
+    Numpad(u8),
+    Space,
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    PrintScreen,
+    Pause,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Semicolon,
+    Quote,
+    Comma,
+    Period,
+    Slash,
+    Backquote,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    MediaPlayPause,
+    MediaNext,
+    MediaPrevious,
+    MediaStop,
+}
+
+/// 具名按键：(按键, 规范名称, 其他可接受的写法)。
+/// 别名包含浏览器 KeyboardEvent.code 的名称，GUI 录制到的按键可以直接解析。
+const NAMED_KEYS: &[(Key, &str, &[&str])] = &[
+    (Key::Space, "Space", &["spacebar"]),
+    (Key::Enter, "Enter", &["return"]),
+    (Key::Tab, "Tab", &[]),
+    (Key::Escape, "Esc", &["escape"]),
+    (Key::Backspace, "Backspace", &["back"]),
+    (Key::Delete, "Delete", &["del"]),
+    (Key::Insert, "Insert", &["ins"]),
+    (Key::Home, "Home", &[]),
+    (Key::End, "End", &[]),
+    (Key::PageUp, "PageUp", &["pgup", "prior"]),
+    (Key::PageDown, "PageDown", &["pgdn", "pgdown"]),
+    (Key::Up, "Up", &["arrowup"]),
+    (Key::Down, "Down", &["arrowdown"]),
+    (Key::Left, "Left", &["arrowleft"]),
+    (Key::Right, "Right", &["arrowright"]),
+    (
+        Key::PrintScreen,
+        "PrintScreen",
+        &["prtsc", "prtscr", "print"],
+    ),
+    (Key::Pause, "Pause", &["break"]),
+    (Key::NumpadAdd, "NumpadAdd", &["numpadplus"]),
+    (Key::NumpadSubtract, "NumpadSubtract", &["numpadminus"]),
+    (Key::NumpadMultiply, "NumpadMultiply", &[]),
+    (Key::NumpadDivide, "NumpadDivide", &[]),
+    (Key::NumpadDecimal, "NumpadDecimal", &["numpaddot"]),
+    (Key::Minus, "-", &["minus"]),
+    (Key::Equal, "=", &["equal", "equals"]),
+    (Key::BracketLeft, "[", &["bracketleft"]),
+    (Key::BracketRight, "]", &["bracketright"]),
+    (Key::Backslash, "\\", &["backslash"]),
+    (Key::Semicolon, ";", &["semicolon"]),
+    (Key::Quote, "'", &["quote", "apostrophe"]),
+    (Key::Comma, ",", &["comma"]),
+    (Key::Period, ".", &["period", "dot"]),
+    (Key::Slash, "/", &["slash"]),
+    (Key::Backquote, "`", &["backquote", "grave"]),
+    (Key::VolumeUp, "VolumeUp", &["audiovolumeup"]),
+    (Key::VolumeDown, "VolumeDown", &["audiovolumedown"]),
+    (Key::VolumeMute, "VolumeMute", &["audiovolumemute", "mute"]),
+    (Key::MediaPlayPause, "MediaPlayPause", &["playpause"]),
+    (Key::MediaNext, "MediaNext", &["mediatracknext"]),
+    (
+        Key::MediaPrevious,
+        "MediaPrevious",
+        &["mediatrackprevious", "mediaprev"],
+    ),
+    (Key::MediaStop, "MediaStop", &[]),
+];
+
+impl Key {
+    /// 所有支持的按键
+    pub fn all() -> Vec<Key> {
+        let mut keys: Vec<Key> = ('A'..='Z').map(Key::Letter).collect();
+        keys.extend((0..=9).map(Key::Digit));
+        keys.extend((1..=24).map(Key::F));
+        keys.extend((0..=9).map(Key::Numpad));
+        keys.extend(NAMED_KEYS.iter().map(|(key, _, _)| *key));
+        keys
+    }
+
+    /// 规范名称（Chord 的显示形式中使用）
+    pub fn name(&self) -> String {
+        match self {
+            Key::Letter(c) => c.to_string(),
+            Key::Digit(d) => d.to_string(),
+            Key::F(n) => format!("F{}", n),
+            Key::Numpad(d) => format!("Numpad{}", d),
+            key => NAMED_KEYS
+                .iter()
+                .find(|(k, _, _)| k == key)
+                .map(|(_, name, _)| name.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 按名称查找按键（不区分大小写）
+    pub fn from_name(name: &str) -> Option<Key> {
+        let lower = name.trim().to_ascii_lowercase();
+        // KeyboardEvent.code 形式："KeyA"、"Digit1"
+        let name = match (lower.strip_prefix("key"), lower.strip_prefix("digit")) {
+            (Some(rest), _) if rest.len() == 1 && rest.as_bytes()[0].is_ascii_lowercase() => rest,
+            (_, Some(rest)) if rest.len() == 1 && rest.as_bytes()[0].is_ascii_digit() => rest,
+            _ => &lower,
+        };
+
+        if let [c] = name.as_bytes() {
+            if c.is_ascii_lowercase() {
+                return Some(Key::Letter(c.to_ascii_uppercase() as char));
+            }
+            if c.is_ascii_digit() {
+                return Some(Key::Digit(c - b'0'));
+            }
+        }
+        if let Some(n) = name.strip_prefix('f').and_then(parse_number) {
+            return (1..=24).contains(&n).then_some(Key::F(n));
+        }
+        if let Some(n) = name.strip_prefix("numpad").and_then(parse_number) {
+            return (n <= 9).then_some(Key::Numpad(n));
+        }
+        NAMED_KEYS
+            .iter()
+            .find(|(_, canonical, aliases)| {
+                canonical.eq_ignore_ascii_case(name) || aliases.contains(&name)
+            })
+            .map(|(key, _, _)| *key)
+    }
+
+    /// 可以不带修饰键单独使用的按键；其余按键单独注册会拦截正常输入
+    pub fn is_standalone(&self) -> bool {
+        matches!(
+            self,
+            Key::F(_)
+                | Key::PrintScreen
+                | Key::Pause
+                | Key::VolumeUp
+                | Key::VolumeDown
+                | Key::VolumeMute
+                | Key::MediaPlayPause
+                | Key::MediaNext
+                | Key::MediaPrevious
+                | Key::MediaStop
+        )
+    }
+}
+
+/// 十进制数字（不允许前导 0，避免 "F01" 之类的多种写法）
+fn parse_number(s: &str) -> Option<u8> {
+    if s.is_empty() || s.len() > 2 || (s.len() > 1 && s.starts_with('0')) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordError {
+    Empty,
+    /// "Ctrl++" 之类的空片段
+    EmptyPart(String),
+    UnknownKey(String),
+    DuplicateModifier(&'static str),
+    MultipleKeys(String, String),
+    MissingKey(String),
+    NeedsModifier(String),
+}
+
+impl fmt::Display for ChordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChordError::Empty => write!(f, "热键为空"),
+            ChordError::EmptyPart(chord) => write!(
+                f,
+                "热键 \"{}\" 中有空的按键；'+' 是分隔符，加号请写作 \"=\" 或 \"NumpadAdd\"",
+                chord
+            ),
+            ChordError::UnknownKey(key) => write!(
+                f,
+                "无法识别的按键 \"{}\"（支持 A–Z、0–9、F1–F24、方向键、小键盘、标点与媒体键）",
+                key
+            ),
+            ChordError::DuplicateModifier(modifier) => write!(f, "修饰键 {} 重复", modifier),
+            ChordError::MultipleKeys(first, second) => {
+                write!(f, "热键只能有一个主键，但同时包含 {} 与 {}", first, second)
+            }
+            ChordError::MissingKey(chord) => {
+                write!(f, "热键 \"{}\" 缺少主键（例如 Ctrl+Alt+Space）", chord)
+            }
+            ChordError::NeedsModifier(key) => write!(
+                f,
+                "{} 需要与 Ctrl、Alt 或 Win 组合使用，否则会拦截正常输入",
+                key
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChordError {}
+
+/// 修饰键：(规范名称, 可接受的写法)
+const MODIFIER_NAMES: [(&str, &[&str]); 4] = [
+    ("Ctrl", &["ctrl", "control", "ctl"]),
+    ("Alt", &["alt", "option", "opt"]),
+    ("Shift", &["shift"]),
+    (
+        "Win",
+        &["win", "windows", "super", "meta", "cmd", "command"],
+    ),
+];
+
+/// 平台无关的热键组合：若干修饰键 + 一个主键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Chord {
+    /// 解析热键字符串，例如 "ctrl + alt + f13"、"Win+Shift+ArrowUp"
+    pub fn parse(chord: &str) -> StdResult<Self, ChordError> {
+        let chord = chord.trim();
+        if chord.is_empty() {
+            return Err(ChordError::Empty);
+        }
+
+        let mut modifiers = Modifiers::default();
+        let mut key: Option<Key> = None;
+        for part in chord.split('+').map(str::trim) {
+            if part.is_empty() {
+                return Err(ChordError::EmptyPart(chord.to_string()));
+            }
+            let lower = part.to_ascii_lowercase();
+            if let Some(index) = MODIFIER_NAMES
+                .iter()
+                .position(|(_, names)| names.contains(&lower.as_str()))
+            {
+                let flag = match index {
+                    0 => &mut modifiers.ctrl,
+                    1 => &mut modifiers.alt,
+                    2 => &mut modifiers.shift,
+                    _ => &mut modifiers.meta,
+                };
+                if *flag {
+                    return Err(ChordError::DuplicateModifier(MODIFIER_NAMES[index].0));
+                }
+                *flag = true;
+                continue;
+            }
+
+            let parsed =
+                Key::from_name(part).ok_or_else(|| ChordError::UnknownKey(part.to_string()))?;
+            if let Some(previous) = key {
+                return Err(ChordError::MultipleKeys(previous.name(), parsed.name()));
+            }
+            key = Some(parsed);
+        }
+
+        let key = key.ok_or_else(|| ChordError::MissingKey(chord.to_string()))?;
+        Chord { modifiers, key }.check()
+    }
+
+    /// 检查组合是否可以注册为全局热键：除功能键、媒体键等之外，必须包含 Ctrl、Alt 或 Win
+    pub fn check(self) -> StdResult<Self, ChordError> {
+        let m = self.modifiers;
+        if !(m.ctrl || m.alt || m.meta || self.key.is_standalone()) {
+            return Err(ChordError::NeedsModifier(self.to_string()));
+        }
+        Ok(self)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let m = self.modifiers;
+        for ((name, _), on) in MODIFIER_NAMES.iter().zip([m.ctrl, m.alt, m.shift, m.meta]) {
+            if on {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key.name())
+    }
+}
+
+impl FromStr for Chord {
+    type Err = ChordError;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Chord::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_chords() {
+        let chord = Chord::parse("Ctrl + Alt+space").unwrap();
+        assert!(chord.modifiers.ctrl && chord.modifiers.alt);
+        assert!(!chord.modifiers.shift && !chord.modifiers.meta);
+        assert_eq!(chord.key, Key::Space);
+
+        // 小写字母映射到对应的大写字母键
+        let chord = Chord::parse("Super+Shift+q").unwrap();
+        assert!(chord.modifiers.meta && chord.modifiers.shift);
+        assert_eq!(chord.key, Key::Letter('Q'));
+
+        assert_eq!(Chord::parse("F13").unwrap().key, Key::F(13));
+        assert_eq!(Chord::parse("Ctrl+F24").unwrap().key, Key::F(24));
+        assert_eq!(Chord::parse("alt+ArrowUp").unwrap().key, Key::Up);
+        assert_eq!(Chord::parse("Ctrl+KeyA").unwrap().key, Key::Letter('A'));
+        assert_eq!(Chord::parse("Ctrl+Digit7").unwrap().key, Key::Digit(7));
+        assert_eq!(Chord::parse("Ctrl+Numpad5").unwrap().key, Key::Numpad(5));
+        assert_eq!(Chord::parse("Ctrl+/").unwrap().key, Key::Slash);
+        assert_eq!(
+            Chord::parse("Win+BracketLeft").unwrap().key,
+            Key::BracketLeft
+        );
+        assert_eq!(
+            Chord::parse("MediaPlayPause").unwrap().key,
+            Key::MediaPlayPause
+        );
+
+        assert_eq!(
+            Chord::parse("control+option+shift+cmd+pgdn")
+                .unwrap()
+                .to_string(),
+            "Ctrl+Alt+Shift+Win+PageDown"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Chord::parse("  "), Err(ChordError::Empty));
+        assert_eq!(
+            Chord::parse("Ctrl+Alt"),
+            Err(ChordError::MissingKey("Ctrl+Alt".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("Ctrl++"),
+            Err(ChordError::EmptyPart("Ctrl++".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("Ctrl+Hyper"),
+            Err(ChordError::UnknownKey("Hyper".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("Ctrl+F25").unwrap_err(),
+            ChordError::UnknownKey("F25".into())
+        );
+        assert_eq!(
+            Chord::parse("Ctrl+Control+A"),
+            Err(ChordError::DuplicateModifier("Ctrl"))
+        );
+        assert_eq!(
+            Chord::parse("Ctrl+A+B"),
+            Err(ChordError::MultipleKeys("A".to_string(), "B".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("Shift+a"),
+            Err(ChordError::NeedsModifier("Shift+A".to_string()))
+        );
+        assert!(
+            Chord::parse("Shift+a")
+                .unwrap_err()
+                .to_string()
+                .contains("Ctrl、Alt 或 Win")
+        );
+    }
+
+    #[test]
+    fn test_key_names_are_unique() {
+        let keys = Key::all();
+        for key in &keys {
+            assert_eq!(Key::from_name(&key.name()), Some(*key), "{:?}", key);
+        }
+        let mut names: Vec<String> = keys.iter().map(|k| k.name().to_lowercase()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), keys.len());
+    }
+
+    fn any_chord() -> impl Strategy<Value = Chord> {
+        (any::<[bool; 4]>(), proptest::sample::select(Key::all()))
+            .prop_map(|([ctrl, alt, shift, meta], key)| Chord {
+                modifiers: Modifiers {
+                    ctrl,
+                    alt,
+                    shift,
+                    meta,
+                },
+                key,
+            })
+            .prop_filter("not registrable", |chord| chord.check().is_ok())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_display_parse_round_trip(chord in any_chord()) {
+            prop_assert_eq!(Chord::parse(&chord.to_string()), Ok(chord));
+        }
+
+        #[test]
+        fn prop_parse_ignores_case_spacing_and_order(
+            chord in any_chord(),
+            upper in any::<bool>(),
+            spaces in 0usize..3,
+            rotate in 0usize..5,
+        ) {
+            let mut parts: Vec<String> =
+                chord.to_string().split('+').map(str::to_string).collect();
+            let len = parts.len();
+            parts.rotate_left(rotate % len);
+            let sep = format!("{}+{}", " ".repeat(spaces), " ".repeat(spaces));
+            let text = parts.join(&sep);
+            let text = if upper { text.to_uppercase() } else { text.to_lowercase() };
+            prop_assert_eq!(Chord::parse(&text), Ok(chord));
+        }
+
+        #[test]
+        fn prop_parsed_chords_display_canonically(text in "[A-Za-z0-9+ /=\\-]{0,24}") {
+            if let Ok(chord) = Chord::parse(&text) {
+                prop_assert_eq!(Chord::parse(&chord.to_string()), Ok(chord));
+            }
+        }
+    }
+}
@@ -1,148 +1,328 @@
+// Global Hotkeys
+// HotkeyService 在独立线程中驱动平台后端（Win32 RegisterHotKey / X11 XGrabKey），
+// 触发的热键与前导键序列的进展通过 channel 交给主循环。
+
+mod chord;
+mod sequence;
+#[cfg(windows)]
+mod win32;
+#[cfg(not(windows))]
+mod x11;
+
+pub use chord::{Chord, ChordError, Key, Modifiers};
+pub use sequence::{Continuation, Sequence, SequenceMatcher, SequenceStep};
 use std::result::Result as StdResult;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::thread::JoinHandle;
-use windows::{Win32::UI::Input::KeyboardAndMouse::*, Win32::UI::WindowsAndMessaging::*};
+use std::time::{Duration, Instant};
 
-/// 热键管理器
-pub struct HotkeyManager {
-    pub tx: mpsc::Sender<u32>,
-    pub rx: mpsc::Receiver<u32>,
+/// 前导热键的 ID；序列进行中临时捕获的后续按键从 SEQUENCE_KEY_ID 开始编号
+const LEADER_ID: u32 = 0x8000;
+const SEQUENCE_KEY_ID: u32 = 0x8001;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyEvent {
+    /// 第 n 个热键被按下
+    Pressed(usize),
+    /// 前导键序列的进展
+    Sequence(SequenceStep),
 }
 
-impl HotkeyManager {
-    pub fn new() -> Self {
-        let (tx, rx) = mpsc::channel();
-        HotkeyManager { tx, rx }
+/// 热键的平台后端；在热键线程中创建并使用
+pub trait HotkeyBackend {
+    fn register(&mut self, id: u32, chord: &Chord) -> StdResult<(), String>;
+    fn unregister(&mut self, id: u32);
+    /// 非阻塞地取出自上次调用以来触发的热键 ID
+    fn poll(&mut self) -> Vec<u32>;
+
+    /// 阻塞直到有热键触发、被 waker 唤醒或超过 timeout（None 表示不限时），返回触发的热键 ID。
+    /// 默认实现以 POLL_INTERVAL 为间隔调用 poll；平台后端使用系统的阻塞等待
+    fn wait(&mut self, timeout: Option<Duration>) -> Vec<u32> {
+        let fired = self.poll();
+        if fired.is_empty() {
+            std::thread::sleep(timeout.map_or(POLL_INTERVAL, |t| t.min(POLL_INTERVAL)));
+        }
+        fired
     }
 
-    /// 等待热键事件（阻塞模式）
-    pub fn wait_for_hotkey(&self) -> Option<u32> {
-        self.rx.recv().ok()
-    }
-
-    /// 非阻塞检查热键事件
-    pub fn try_wait_for_hotkey(&self) -> Option<u32> {
-        match self.rx.try_recv() {
-            Ok(id) => Some(id),
-            _ => None,
-        }
-    }
-
-    /// 注册快捷键
-    pub fn register(&self, id: u32, hotkey_str: &str) -> StdResult<(), String> {
-        let (vk, modifiers) = self.parse_hotkey(hotkey_str)?;
-        unsafe {
-            RegisterHotKey(None, id as i32, modifiers, vk.0 as u32)
-                .map_err(|e| format!("无法注册热键 {}: {}", hotkey_str, e))?;
-        }
-        Ok(())
-    }
-
-    /// 解析热键字符串
-    fn parse_hotkey(
-        &self,
-        hotkey_str: &str,
-    ) -> StdResult<(VIRTUAL_KEY, HOT_KEY_MODIFIERS), String> {
-        let parts: Vec<&str> = hotkey_str.split('+').map(|s| s.trim()).collect();
-        let mut modifiers = HOT_KEY_MODIFIERS(0);
-        let mut vk = VIRTUAL_KEY(0);
-
-        for part in parts {
-            match part.to_uppercase().as_str() {
-                "CTRL" => modifiers |= MOD_CONTROL,
-                "ALT" => modifiers |= MOD_ALT,
-                "SHIFT" => modifiers |= MOD_SHIFT,
-                "WIN" => modifiers |= MOD_WIN,
-                "SPACE" => vk = VK_SPACE,
-                "ENTER" => vk = VK_RETURN,
-                "Q" => vk = VIRTUAL_KEY(b'Q' as u16),
-                "W" => vk = VIRTUAL_KEY(b'W' as u16),
-                "H" => vk = VIRTUAL_KEY(b'H' as u16),
-                s if s.len() == 1 => {
-                    vk = VIRTUAL_KEY(s.as_bytes()[0] as u16);
-                }
-                _ => return Err(format!("不受支持的按键零件: {}", part)),
+    /// 可在其他线程中唤醒 wait 的句柄（用于停止热键线程）
+    fn waker(&self) -> Waker {
+        Box::new(|| {})
+    }
+}
+
+/// 唤醒阻塞在 HotkeyBackend::wait 中的热键线程
+pub type Waker = Box<dyn Fn() + Send>;
+
+/// 默认 wait 实现的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 后端构造函数：Win32 热键绑定到注册线程，因此后端必须在热键线程内创建
+pub type BackendFactory = fn() -> StdResult<Box<dyn HotkeyBackend>, String>;
+
+fn default_backend() -> StdResult<Box<dyn HotkeyBackend>, String> {
+    #[cfg(windows)]
+    return win32::Win32Hotkeys::create();
+    #[cfg(not(windows))]
+    return x11::X11Hotkeys::create();
+}
+
+/// 一个热键的注册结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registration {
+    /// 生效的组合（规范形式）；所有候选都失败时为 None
+    pub active: Option<String>,
+    /// 注册失败的候选及原因，按尝试顺序排列
+    pub failures: Vec<(String, String)>,
+}
+
+/// 依次尝试候选组合，注册第一个成功的
+fn register_first(backend: &mut dyn HotkeyBackend, id: u32, candidates: &[String]) -> Registration {
+    let mut registration = Registration::default();
+    for candidate in candidates {
+        let result = Chord::parse(candidate)
+            .map_err(|e| e.to_string())
+            .and_then(|chord| backend.register(id, &chord).map(|()| chord));
+        match result {
+            Ok(chord) => {
+                registration.active = Some(chord.to_string());
+                break;
             }
+            Err(e) => registration.failures.push((candidate.clone(), e)),
         }
-        Ok((vk, modifiers))
     }
+    registration
 }
 
-/// 热键服务，负责在一个独立的 Windows 消息循环中处理热键
+/// 检查组合能否注册为全局热键（例如是否已被其他程序占用）：在独立线程中临时注册后立即注销。
+/// 成功时返回规范化的组合
+pub fn test_chord(chord: &str) -> StdResult<Chord, String> {
+    test_chord_with(default_backend, chord)
+}
+
+fn test_chord_with(factory: BackendFactory, chord: &str) -> StdResult<Chord, String> {
+    // 与服务使用的 ID（下标 + 1）错开
+    const TEST_ID: u32 = 0xBFFF;
+    let chord = Chord::parse(chord).map_err(|e| e.to_string())?;
+    std::thread::spawn(move || {
+        let mut backend = factory()?;
+        backend.register(TEST_ID, &chord)?;
+        backend.unregister(TEST_ID);
+        Ok(chord)
+    })
+    .join()
+    .map_err(|_| "热键检测线程异常退出".to_string())?
+}
+
+/// 热键线程中的序列状态：序列进行中把可用的后续按键临时注册为不带修饰键的热键
+struct SequenceCapture {
+    matcher: SequenceMatcher,
+    captured: Vec<(u32, Key)>,
+}
+
+impl SequenceCapture {
+    fn key_for(&self, id: u32) -> Option<Key> {
+        self.captured
+            .iter()
+            .find(|(c, _)| *c == id)
+            .map(|(_, key)| *key)
+    }
+
+    /// 按状态机的结果更新捕获的按键，并把进展交给主循环
+    fn apply(
+        &mut self,
+        backend: &mut dyn HotkeyBackend,
+        step: SequenceStep,
+        tx: &mpsc::Sender<HotkeyEvent>,
+    ) {
+        for (id, _) in self.captured.drain(..) {
+            backend.unregister(id);
+        }
+        for (i, key) in self.matcher.keys_to_capture().into_iter().enumerate() {
+            let id = SEQUENCE_KEY_ID + i as u32;
+            let chord = Chord {
+                modifiers: Modifiers::default(),
+                key,
+            };
+            match backend.register(id, &chord) {
+                Ok(()) => self.captured.push((id, key)),
+                Err(e) => log::warn!("无法捕获序列按键 {}: {}", key.name(), e),
+            }
+        }
+        let _ = tx.send(HotkeyEvent::Sequence(step));
+    }
+}
+
+/// 热键服务，负责在一个独立线程中处理热键
+///
+/// 按顺序注册一组热键（每个热键可带若干备选组合），触发时报告该热键在列表中的下标；
+/// 可选地注册一个前导热键，驱动前导键序列
 pub struct HotkeyService {
-    hotkey_manager: HotkeyManager,
+    tx: mpsc::Sender<HotkeyEvent>,
+    rx: Option<mpsc::Receiver<HotkeyEvent>>,
     should_quit: Arc<AtomicBool>,
-    hotkey: String,
+    waker: Option<Waker>,
+    hotkeys: Vec<Vec<String>>,
+    sequences: Option<(String, SequenceMatcher)>,
+    backend_factory: BackendFactory,
     thread_handle: Option<JoinHandle<StdResult<(), Box<dyn std::error::Error + Send + 'static>>>>,
 }
 
 impl HotkeyService {
-    pub fn new(hotkey: String) -> Self {
+    /// hotkeys 中每一项是一个热键的候选组合，按顺序尝试
+    pub fn new(hotkeys: Vec<Vec<String>>) -> Self {
+        let (tx, rx) = mpsc::channel();
         HotkeyService {
-            hotkey_manager: HotkeyManager::new(),
+            tx,
+            rx: Some(rx),
             should_quit: Arc::new(AtomicBool::new(false)),
-            hotkey,
+            waker: None,
+            hotkeys,
+            sequences: None,
+            backend_factory: default_backend,
             thread_handle: None,
         }
     }
 
-    pub fn start(&mut self) -> StdResult<(), Box<dyn std::error::Error + Send + 'static>> {
+    /// 启用前导键序列：按下 leader 后由 matcher 匹配后续按键
+    pub fn with_sequences(mut self, leader: String, matcher: SequenceMatcher) -> Self {
+        self.sequences = Some((leader, matcher));
+        self
+    }
+
+    /// 替换平台后端（测试用）
+    pub fn with_backend(mut self, factory: BackendFactory) -> Self {
+        self.backend_factory = factory;
+        self
+    }
+
+    /// 启动热键线程，等待注册完成后返回每个热键的注册结果
+    /// （启用了前导键序列时，最后一项为前导热键的结果）
+    pub fn start(
+        &mut self,
+    ) -> StdResult<Vec<Registration>, Box<dyn std::error::Error + Send + 'static>> {
         let should_quit = self.should_quit.clone();
-        let hotkey_str = self.hotkey.clone();
-        let tx = self.hotkey_manager.tx.clone();
+        let hotkeys = self.hotkeys.clone();
+        let sequences = self.sequences.take();
+        let hotkey_count = hotkeys.len();
+        let factory = self.backend_factory;
+        let tx = self.tx.clone();
+        let (registered_tx, registered_rx) = mpsc::channel();
 
         let handle = std::thread::spawn(
             move || -> StdResult<(), Box<dyn std::error::Error + Send + 'static>> {
-                let manager = HotkeyManager {
-                    tx: tx.clone(),
-                    rx: mpsc::channel().1,
-                }; // dummy rx
-
-                // ID 1 (Inject) and ID 3 (Selector) removed.
-
-                // 注册轮盘热键 (From Config)
-                if let Err(e) = manager.register(4, &hotkey_str) {
-                    log::error!("注册轮盘热键失败: {}", e);
-                } else {
-                    println!("✅ [HOTKEY] 轮盘触发热键已注册: {}", hotkey_str);
+                let mut backend = match factory() {
+                    Ok(b) => b,
+                    Err(e) => {
+                        log::error!("无法初始化热键后端: {}", e);
+                        let _ = registered_tx.send(Err(e.clone()));
+                        return Err(Box::new(std::io::Error::other(e)));
+                    }
+                };
+
+                // 后端 ID 为下标 + 1（Win32 热键 ID 不使用 0）
+                let mut registrations: Vec<Registration> = hotkeys
+                    .iter()
+                    .enumerate()
+                    .map(|(index, candidates)| {
+                        let registration =
+                            register_first(backend.as_mut(), index as u32 + 1, candidates);
+                        for (chord, e) in &registration.failures {
+                            log::error!("注册热键 {} 失败: {}", chord, e);
+                        }
+                        if let Some(active) = &registration.active {
+                            println!("✅ [HOTKEY] 热键已注册: {}", active);
+                        }
+                        registration
+                    })
+                    .collect();
+
+                let mut capture = None;
+                if let Some((leader, matcher)) = sequences {
+                    let registration =
+                        register_first(backend.as_mut(), LEADER_ID, std::slice::from_ref(&leader));
+                    match &registration.active {
+                        Some(active) => println!("✅ [HOTKEY] 序列前导热键已注册: {}", active),
+                        None => log::error!("注册序列前导热键 {} 失败", leader),
+                    }
+                    registrations.push(registration);
+                    capture = Some(SequenceCapture {
+                        matcher,
+                        captured: Vec::new(),
+                    });
                 }
+                let _ = registered_tx.send(Ok((registrations, backend.waker())));
 
-                let mut msg = MSG::default();
                 while !should_quit.load(Ordering::Relaxed) {
-                    unsafe {
-                        if PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
-                            if msg.message == WM_HOTKEY {
-                                let _ = tx.send(msg.wParam.0 as u32);
+                    // 序列进行中最多等到超时时刻（稍晚一点，确保 expire 认为已超时）
+                    let timeout =
+                        capture
+                            .as_ref()
+                            .and_then(|c| c.matcher.deadline())
+                            .map(|deadline| {
+                                deadline.saturating_duration_since(Instant::now())
+                                    + Duration::from_millis(1)
+                            });
+                    for id in backend.wait(timeout) {
+                        let now = Instant::now();
+                        let key = capture.as_ref().and_then(|c| c.key_for(id));
+                        match (capture.as_mut(), key) {
+                            (Some(c), _) if id == LEADER_ID => {
+                                let step = c.matcher.start(now);
+                                c.apply(backend.as_mut(), step, &tx);
+                            }
+                            (Some(c), Some(key)) => {
+                                let step = c.matcher.press(key, now);
+                                c.apply(backend.as_mut(), step, &tx);
                             }
-                            let _ = TranslateMessage(&msg);
-                            DispatchMessageW(&msg);
+                            // 后端 ID 为下标 + 1
+                            _ if (1..=hotkey_count).contains(&(id as usize)) => {
+                                let _ = tx.send(HotkeyEvent::Pressed(id as usize - 1));
+                            }
+                            _ => {}
                         }
                     }
-                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    if let Some(c) = capture.as_mut()
+                        && let Some(step) = c.matcher.expire(Instant::now())
+                    {
+                        c.apply(backend.as_mut(), step, &tx);
+                    }
                 }
                 Ok(())
             },
         );
 
         self.thread_handle = Some(handle);
-        Ok(())
+        match registered_rx.recv() {
+            Ok(Ok((registrations, waker))) => {
+                self.waker = Some(waker);
+                Ok(registrations)
+            }
+            Ok(Err(e)) => Err(Box::new(std::io::Error::other(e))),
+            Err(_) => Err(Box::new(std::io::Error::other("热键线程异常退出"))),
+        }
     }
 
     pub fn stop(&mut self) {
         self.should_quit.store(true, Ordering::Relaxed);
+        if let Some(wake) = self.waker.take() {
+            wake();
+        }
         if let Some(handle) = self.thread_handle.take() {
             let _ = handle.join();
         }
     }
 
-    pub fn wait_for_hotkey(&self) -> Option<u32> {
-        self.hotkey_manager.wait_for_hotkey()
+    /// 等待热键事件（阻塞模式）
+    pub fn wait_for_hotkey(&self) -> Option<HotkeyEvent> {
+        self.rx.as_ref()?.recv().ok()
     }
 
-    pub fn try_wait_for_hotkey(&self) -> Option<u32> {
-        self.hotkey_manager.try_wait_for_hotkey()
+    /// 取走事件通道（例如转发到服务的事件总线）；之后 wait_for_hotkey 返回 None
+    pub fn take_events(&mut self) -> Option<mpsc::Receiver<HotkeyEvent>> {
+        self.rx.take()
     }
 }
 
@@ -151,3 +331,195 @@ impl Drop for HotkeyService {
         self.stop();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每次 poll 都报告所有已注册的热键；带 F1 的组合视为已被其他程序占用
+    struct EchoBackend(Vec<u32>);
+
+    impl HotkeyBackend for EchoBackend {
+        fn register(&mut self, id: u32, chord: &Chord) -> StdResult<(), String> {
+            if chord.key == Key::F(1) {
+                return Err(format!("{} 已被占用", chord));
+            }
+            self.0.push(id);
+            Ok(())
+        }
+
+        fn unregister(&mut self, id: u32) {
+            self.0.retain(|&registered| registered != id);
+        }
+
+        fn poll(&mut self) -> Vec<u32> {
+            self.0.clone()
+        }
+    }
+
+    fn echo_backend() -> StdResult<Box<dyn HotkeyBackend>, String> {
+        Ok(Box::new(EchoBackend(Vec::new())))
+    }
+
+    fn candidates(chords: &[&str]) -> Vec<String> {
+        chords.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_service_forwards_backend_events() {
+        let mut service =
+            HotkeyService::new(vec![candidates(&["Ctrl+Q"]), candidates(&["Ctrl+Space"])])
+                .with_backend(echo_backend);
+        service.start().unwrap();
+
+        assert_eq!(service.wait_for_hotkey(), Some(HotkeyEvent::Pressed(0)));
+        assert_eq!(service.wait_for_hotkey(), Some(HotkeyEvent::Pressed(1)));
+        service.stop();
+    }
+
+    // 依次按下 SCRIPT 中的组合（只触发已注册的组合）
+    const SCRIPT: [&str; 3] = ["Ctrl+Alt+P", "R", "V"];
+
+    struct ScriptBackend {
+        registered: Vec<(u32, String)>,
+        next: usize,
+    }
+
+    impl HotkeyBackend for ScriptBackend {
+        fn register(&mut self, id: u32, chord: &Chord) -> StdResult<(), String> {
+            self.registered.push((id, chord.to_string()));
+            Ok(())
+        }
+
+        fn unregister(&mut self, id: u32) {
+            self.registered.retain(|(registered, _)| *registered != id);
+        }
+
+        fn poll(&mut self) -> Vec<u32> {
+            let Some(chord) = SCRIPT.get(self.next) else {
+                return Vec::new();
+            };
+            self.next += 1;
+            self.registered
+                .iter()
+                .filter(|(_, registered)| registered == chord)
+                .map(|(id, _)| *id)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_leader_sequence_captures_continuations() {
+        let matcher = SequenceMatcher::new(
+            vec![
+                Sequence::parse("r v", "review").unwrap(),
+                Sequence::parse("s", "summary").unwrap(),
+            ],
+            std::time::Duration::from_secs(5),
+        );
+        let mut service = HotkeyService::new(vec![candidates(&["Ctrl+Q"])])
+            .with_sequences("Ctrl+Alt+P".to_string(), matcher)
+            .with_backend(|| {
+                Ok(Box::new(ScriptBackend {
+                    registered: Vec::new(),
+                    next: 0,
+                }))
+            });
+        let registrations = service.start().unwrap();
+        assert_eq!(registrations[1].active.as_deref(), Some("Ctrl+Alt+P"));
+
+        let pressed = |event| match event {
+            Some(HotkeyEvent::Sequence(SequenceStep::Pending { pressed, .. })) => pressed,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert!(pressed(service.wait_for_hotkey()).is_empty());
+        assert_eq!(pressed(service.wait_for_hotkey()), vec![Key::Letter('R')]);
+        assert_eq!(
+            service.wait_for_hotkey(),
+            Some(HotkeyEvent::Sequence(SequenceStep::Matched(0)))
+        );
+        service.stop();
+    }
+
+    #[test]
+    fn test_fallback_chords_tried_in_order() {
+        let mut service = HotkeyService::new(vec![
+            candidates(&["Ctrl+F1", "Ctrl+Hyper", "ctrl+alt+w", "Ctrl+Alt+E"]),
+            candidates(&["F1"]),
+        ])
+        .with_backend(echo_backend);
+        let registrations = service.start().unwrap();
+
+        assert_eq!(registrations[0].active.as_deref(), Some("Ctrl+Alt+W"));
+        let failed: Vec<&str> = registrations[0]
+            .failures
+            .iter()
+            .map(|(chord, _)| chord.as_str())
+            .collect();
+        assert_eq!(failed, vec!["Ctrl+F1", "Ctrl+Hyper"]);
+        assert!(registrations[0].failures[1].1.contains("Hyper"));
+
+        assert_eq!(registrations[1].active, None);
+        assert_eq!(registrations[1].failures[0].1, "F1 已被占用");
+        service.stop();
+    }
+
+    // wait 不限时阻塞，只有 waker 能使其返回
+    struct BlockingBackend {
+        wake_tx: mpsc::Sender<()>,
+        wake_rx: mpsc::Receiver<()>,
+    }
+
+    impl HotkeyBackend for BlockingBackend {
+        fn register(&mut self, _id: u32, _chord: &Chord) -> StdResult<(), String> {
+            Ok(())
+        }
+
+        fn unregister(&mut self, _id: u32) {}
+
+        fn poll(&mut self) -> Vec<u32> {
+            Vec::new()
+        }
+
+        fn wait(&mut self, _timeout: Option<Duration>) -> Vec<u32> {
+            let _ = self.wake_rx.recv();
+            Vec::new()
+        }
+
+        fn waker(&self) -> Waker {
+            let tx = self.wake_tx.clone();
+            Box::new(move || {
+                let _ = tx.send(());
+            })
+        }
+    }
+
+    #[test]
+    fn test_stop_wakes_blocked_backend() {
+        let mut service = HotkeyService::new(vec![candidates(&["Ctrl+Q"])]).with_backend(|| {
+            let (wake_tx, wake_rx) = mpsc::channel();
+            Ok(Box::new(BlockingBackend { wake_tx, wake_rx }))
+        });
+        let events = service.take_events().unwrap();
+        service.start().unwrap();
+        assert_eq!(service.wait_for_hotkey(), None);
+        // 没有 waker 时 stop 会一直等待热键线程
+        service.stop();
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_chord_reports_conflicts() {
+        assert_eq!(
+            test_chord_with(echo_backend, "alt + f2")
+                .unwrap()
+                .to_string(),
+            "Alt+F2"
+        );
+        assert_eq!(
+            test_chord_with(echo_backend, "Alt+F1").unwrap_err(),
+            "Alt+F1 已被占用"
+        );
+        assert!(test_chord_with(echo_backend, "Alt+").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+// Leader-key sequences
+// 按下前导热键后依次输入按键（如 Ctrl+Alt+P 然后 r、v），完整匹配某个序列时触发。
+// 状态机不依赖真实按键事件与时钟：调用方传入按键与当前时间，可直接测试。
+
+use super::{ChordError, Key};
+use std::time::{Duration, Instant};
+
+/// 一个按键序列，例如 "r v"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    pub keys: Vec<Key>,
+    /// 提示中显示的名称（通常为提示词名称）
+    pub label: String,
+}
+
+impl Sequence {
+    /// 解析以空格分隔的按键名称，例如 "r v"、"1 F2"
+    pub fn parse(keys: &str, label: &str) -> Result<Self, ChordError> {
+        let keys = keys
+            .split_whitespace()
+            .map(|name| Key::from_name(name).ok_or_else(|| ChordError::UnknownKey(name.into())))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(ChordError::Empty);
+        }
+        Ok(Sequence {
+            keys,
+            label: label.to_string(),
+        })
+    }
+}
+
+/// 当前前缀之后可以输入的一个按键
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    pub key: Key,
+    /// 输入该键即完成的序列名称
+    pub label: Option<String>,
+    /// 还有更长的序列以该键开头
+    pub more: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceStep {
+    /// 等待下一个按键
+    Pending {
+        pressed: Vec<Key>,
+        continuations: Vec<Continuation>,
+    },
+    /// 完整匹配第 n 个序列
+    Matched(usize),
+    /// 输入了不属于任何序列的按键、按下 Esc 或超时，序列取消
+    Cancelled,
+}
+
+struct Pending {
+    pressed: Vec<Key>,
+    deadline: Instant,
+}
+
+pub struct SequenceMatcher {
+    sequences: Vec<Sequence>,
+    /// 每一步的等待时间
+    timeout: Duration,
+    pending: Option<Pending>,
+}
+
+impl SequenceMatcher {
+    pub fn new(sequences: Vec<Sequence>, timeout: Duration) -> Self {
+        SequenceMatcher {
+            sequences,
+            timeout,
+            pending: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// 当前一步的超时时刻；没有进行中的序列时为 None
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|p| p.deadline)
+    }
+
+    /// 前导热键被按下：开始（或重新开始）一个序列
+    pub fn start(&mut self, now: Instant) -> SequenceStep {
+        self.pending = Some(Pending {
+            pressed: Vec::new(),
+            deadline: now + self.timeout,
+        });
+        self.step()
+    }
+
+    /// 输入一个按键
+    pub fn press(&mut self, key: Key, now: Instant) -> SequenceStep {
+        let Some(pending) = self.pending.as_mut() else {
+            return SequenceStep::Cancelled;
+        };
+        if now > pending.deadline {
+            // 超时后才处理到的按键不再计入序列
+            return self.expire(now).unwrap_or(SequenceStep::Cancelled);
+        }
+        if key == Key::Escape {
+            self.pending = None;
+            return SequenceStep::Cancelled;
+        }
+        pending.pressed.push(key);
+        pending.deadline = now + self.timeout;
+        self.step()
+    }
+
+    /// 超时检查：等待超时时结束序列。若已输入的按键恰好是某个序列（只是还有更长的序列），
+    /// 则触发该序列，否则取消
+    pub fn expire(&mut self, now: Instant) -> Option<SequenceStep> {
+        let pending = self.pending.as_ref()?;
+        if now <= pending.deadline {
+            return None;
+        }
+        let exact = self.exact_match(&pending.pressed);
+        self.pending = None;
+        Some(exact.map_or(SequenceStep::Cancelled, SequenceStep::Matched))
+    }
+
+    /// 当前前缀之后需要捕获的按键（含用于取消的 Esc）
+    pub fn keys_to_capture(&self) -> Vec<Key> {
+        let mut keys: Vec<Key> = self.continuations().into_iter().map(|c| c.key).collect();
+        if self.pending.is_some() {
+            keys.push(Key::Escape);
+        }
+        keys
+    }
+
+    fn step(&mut self) -> SequenceStep {
+        let Some(pending) = &self.pending else {
+            return SequenceStep::Cancelled;
+        };
+        let continuations = self.continuations();
+        if continuations.is_empty() {
+            // 没有更长的序列：精确匹配则触发，否则是无效按键
+            let exact = self.exact_match(&pending.pressed);
+            self.pending = None;
+            return exact.map_or(SequenceStep::Cancelled, SequenceStep::Matched);
+        }
+        SequenceStep::Pending {
+            pressed: pending.pressed.clone(),
+            continuations,
+        }
+    }
+
+    fn exact_match(&self, pressed: &[Key]) -> Option<usize> {
+        if pressed.is_empty() {
+            return None;
+        }
+        self.sequences.iter().position(|s| s.keys == pressed)
+    }
+
+    fn continuations(&self) -> Vec<Continuation> {
+        let Some(pending) = &self.pending else {
+            return Vec::new();
+        };
+        let depth = pending.pressed.len();
+        let mut continuations: Vec<Continuation> = Vec::new();
+        for sequence in &self.sequences {
+            if sequence.keys.len() <= depth || !sequence.keys.starts_with(&pending.pressed) {
+                continue;
+            }
+            let key = sequence.keys[depth];
+            let completes = sequence.keys.len() == depth + 1;
+            let index = match continuations.iter().position(|c| c.key == key) {
+                Some(index) => index,
+                None => {
+                    continuations.push(Continuation {
+                        key,
+                        label: None,
+                        more: false,
+                    });
+                    continuations.len() - 1
+                }
+            };
+            let continuation = &mut continuations[index];
+            if completes {
+                continuation
+                    .label
+                    .get_or_insert_with(|| sequence.label.clone());
+            } else {
+                continuation.more = true;
+            }
+        }
+        continuations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher() -> SequenceMatcher {
+        SequenceMatcher::new(
+            vec![
+                Sequence::parse("r v", "review").unwrap(),
+                Sequence::parse("r s", "summary").unwrap(),
+                Sequence::parse("t", "translate").unwrap(),
+                Sequence::parse("t e", "translate to English").unwrap(),
+            ],
+            Duration::from_millis(1000),
+        )
+    }
+
+    fn key(name: &str) -> Key {
+        Key::from_name(name).unwrap()
+    }
+
+    #[test]
+    fn test_sequence_matches_and_lists_continuations() {
+        let mut m = matcher();
+        let t0 = Instant::now();
+        assert!(matches!(
+            m.start(t0),
+            SequenceStep::Pending { ref continuations, .. } if continuations.len() == 2
+        ));
+        assert_eq!(m.keys_to_capture(), vec![key("r"), key("t"), Key::Escape]);
+
+        match m.press(key("r"), t0 + Duration::from_millis(500)) {
+            SequenceStep::Pending {
+                pressed,
+                continuations,
+            } => {
+                assert_eq!(pressed, vec![key("r")]);
+                assert_eq!(
+                    continuations,
+                    vec![
+                        Continuation {
+                            key: key("v"),
+                            label: Some("review".to_string()),
+                            more: false,
+                        },
+                        Continuation {
+                            key: key("s"),
+                            label: Some("summary".to_string()),
+                            more: false,
+                        },
+                    ]
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        // 每一步重新计时
+        assert_eq!(
+            m.press(key("v"), t0 + Duration::from_millis(1400)),
+            SequenceStep::Matched(0)
+        );
+        assert!(!m.is_pending());
+    }
+
+    #[test]
+    fn test_sequence_cancel_and_timeout() {
+        let t0 = Instant::now();
+        let mut m = matcher();
+        m.start(t0);
+        assert_eq!(m.press(key("x"), t0), SequenceStep::Cancelled);
+        assert_eq!(m.press(key("t"), t0), SequenceStep::Cancelled);
+
+        m.start(t0);
+        m.press(key("r"), t0);
+        assert_eq!(m.press(Key::Escape, t0), SequenceStep::Cancelled);
+        assert!(m.keys_to_capture().is_empty());
+
+        // 超时时前缀不完整则取消
+        m.start(t0);
+        m.press(key("r"), t0);
+        assert_eq!(m.deadline(), Some(t0 + Duration::from_millis(1000)));
+        assert_eq!(m.expire(t0 + Duration::from_millis(999)), None);
+        assert_eq!(
+            m.expire(t0 + Duration::from_millis(1001)),
+            Some(SequenceStep::Cancelled)
+        );
+        assert_eq!(m.deadline(), None);
+        assert_eq!(m.expire(t0 + Duration::from_millis(2000)), None);
+    }
+
+    #[test]
+    fn test_prefix_sequence_fires_on_timeout() {
+        let t0 = Instant::now();
+        let mut m = matcher();
+        m.start(t0);
+        // "t" 本身是序列，但还有 "t e"：先等待
+        match m.press(key("t"), t0) {
+            SequenceStep::Pending { continuations, .. } => {
+                assert_eq!(
+                    continuations[0].label.as_deref(),
+                    Some("translate to English")
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            m.expire(t0 + Duration::from_millis(1001)),
+            Some(SequenceStep::Matched(2))
+        );
+
+        m.start(t0);
+        m.press(key("t"), t0);
+        assert_eq!(m.press(key("e"), t0), SequenceStep::Matched(3));
+    }
+
+    #[test]
+    fn test_parse_sequence_keys() {
+        assert_eq!(
+            Sequence::parse(" 1  F2 ", "x").unwrap().keys,
+            vec![Key::Digit(1), Key::F(2)]
+        );
+        assert_eq!(
+            Sequence::parse("r Hyper", "x"),
+            Err(ChordError::UnknownKey("Hyper".to_string()))
+        );
+        assert_eq!(Sequence::parse("  ", "x"), Err(ChordError::Empty));
+    }
+}
@@ -0,0 +1,153 @@
+// Win32 hotkeys: RegisterHotKey + WM_HOTKEY from the thread message queue,
+// waiting with MsgWaitForMultipleObjectsEx and woken by a posted WM_NULL
+
+use super::{Chord, HotkeyBackend, Key, Waker};
+use std::result::Result as StdResult;
+use std::time::Duration;
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::System::Threading::{GetCurrentThreadId, INFINITE};
+use windows::{Win32::UI::Input::KeyboardAndMouse::*, Win32::UI::WindowsAndMessaging::*};
+
+pub struct Win32Hotkeys {
+    /// 已注册的热键 ID，销毁时注销
+    registered: Vec<u32>,
+}
+
+impl Win32Hotkeys {
+    pub fn create() -> StdResult<Box<dyn HotkeyBackend>, String> {
+        Ok(Box::new(Win32Hotkeys {
+            registered: Vec::new(),
+        }))
+    }
+}
+
+impl HotkeyBackend for Win32Hotkeys {
+    fn register(&mut self, id: u32, chord: &Chord) -> StdResult<(), String> {
+        let mut modifiers = HOT_KEY_MODIFIERS(0);
+        if chord.modifiers.ctrl {
+            modifiers |= MOD_CONTROL;
+        }
+        if chord.modifiers.alt {
+            modifiers |= MOD_ALT;
+        }
+        if chord.modifiers.shift {
+            modifiers |= MOD_SHIFT;
+        }
+        if chord.modifiers.meta {
+            modifiers |= MOD_WIN;
+        }
+        let vk = virtual_key(chord.key);
+        unsafe {
+            RegisterHotKey(None, id as i32, modifiers, vk.0 as u32)
+                .map_err(|e| format!("无法注册热键 {}（可能已被其他程序占用）: {}", chord, e))?;
+        }
+        self.registered.push(id);
+        Ok(())
+    }
+
+    fn unregister(&mut self, id: u32) {
+        if let Some(pos) = self.registered.iter().position(|&r| r == id) {
+            self.registered.swap_remove(pos);
+            unsafe {
+                let _ = UnregisterHotKey(None, id as i32);
+            }
+        }
+    }
+
+    fn poll(&mut self) -> Vec<u32> {
+        let mut fired = Vec::new();
+        let mut msg = MSG::default();
+        unsafe {
+            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                if msg.message == WM_HOTKEY {
+                    fired.push(msg.wParam.0 as u32);
+                }
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+        fired
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> Vec<u32> {
+        let fired = self.poll();
+        if !fired.is_empty() {
+            return fired;
+        }
+        let millis = timeout.map_or(INFINITE, |t| t.as_millis().min(INFINITE as u128 - 1) as u32);
+        unsafe {
+            // MWMO_INPUTAVAILABLE：队列中已有（poll 之后新到的）消息时立即返回
+            let _ = MsgWaitForMultipleObjectsEx(None, millis, QS_ALLINPUT, MWMO_INPUTAVAILABLE);
+        }
+        self.poll()
+    }
+
+    fn waker(&self) -> Waker {
+        // 热键绑定到创建后端的线程，后端只在该线程中使用
+        let thread_id = unsafe { GetCurrentThreadId() };
+        Box::new(move || unsafe {
+            let _ = PostThreadMessageW(thread_id, WM_NULL, WPARAM(0), LPARAM(0));
+        })
+    }
+}
+
+impl Drop for Win32Hotkeys {
+    fn drop(&mut self) {
+        for id in std::mem::take(&mut self.registered) {
+            unsafe {
+                let _ = UnregisterHotKey(None, id as i32);
+            }
+        }
+    }
+}
+
+fn virtual_key(key: Key) -> VIRTUAL_KEY {
+    match key {
+        // 字母与数字的虚拟键码等于其 ASCII 大写值
+        Key::Letter(c) => VIRTUAL_KEY(c as u16),
+        Key::Digit(d) => VIRTUAL_KEY(b'0' as u16 + d as u16),
+        Key::F(n) => VIRTUAL_KEY(VK_F1.0 + n as u16 - 1),
+        Key::Numpad(d) => VIRTUAL_KEY(VK_NUMPAD0.0 + d as u16),
+        Key::Space => VK_SPACE,
+        Key::Enter => VK_RETURN,
+        Key::Tab => VK_TAB,
+        Key::Escape => VK_ESCAPE,
+        Key::Backspace => VK_BACK,
+        Key::Delete => VK_DELETE,
+        Key::Insert => VK_INSERT,
+        Key::Home => VK_HOME,
+        Key::End => VK_END,
+        Key::PageUp => VK_PRIOR,
+        Key::PageDown => VK_NEXT,
+        Key::Up => VK_UP,
+        Key::Down => VK_DOWN,
+        Key::Left => VK_LEFT,
+        Key::Right => VK_RIGHT,
+        Key::PrintScreen => VK_SNAPSHOT,
+        Key::Pause => VK_PAUSE,
+        Key::NumpadAdd => VK_ADD,
+        Key::NumpadSubtract => VK_SUBTRACT,
+        Key::NumpadMultiply => VK_MULTIPLY,
+        Key::NumpadDivide => VK_DIVIDE,
+        Key::NumpadDecimal => VK_DECIMAL,
+        // 标点使用美式键盘布局下的 OEM 键码
+        Key::Minus => VK_OEM_MINUS,
+        Key::Equal => VK_OEM_PLUS,
+        Key::BracketLeft => VK_OEM_4,
+        Key::BracketRight => VK_OEM_6,
+        Key::Backslash => VK_OEM_5,
+        Key::Semicolon => VK_OEM_1,
+        Key::Quote => VK_OEM_7,
+        Key::Comma => VK_OEM_COMMA,
+        Key::Period => VK_OEM_PERIOD,
+        Key::Slash => VK_OEM_2,
+        Key::Backquote => VK_OEM_3,
+        Key::VolumeUp => VK_VOLUME_UP,
+        Key::VolumeDown => VK_VOLUME_DOWN,
+        Key::VolumeMute => VK_VOLUME_MUTE,
+        Key::MediaPlayPause => VK_MEDIA_PLAY_PAUSE,
+        Key::MediaNext => VK_MEDIA_NEXT_TRACK,
+        Key::MediaPrevious => VK_MEDIA_PREV_TRACK,
+        Key::MediaStop => VK_MEDIA_STOP,
+    }
+}
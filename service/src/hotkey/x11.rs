@@ -0,0 +1,316 @@
+// X11 hotkeys: XGrabKey on the root window, KeyPress events read from the connection,
+// waiting with poll(2) on the connection socket and a wake-up socket pair
+
+use super::{Chord, HotkeyBackend, Key, Waker};
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::result::Result as StdResult;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{ConnectionExt, GrabMode, Keycode, ModMask, Window};
+use x11rb::rust_connection::RustConnection;
+
+// keysym 取值见 X11/keysymdef.h 与 XF86keysym.h
+const XK_F1: u32 = 0xffbe;
+const XK_KP_0: u32 = 0xffb0;
+
+pub struct X11Hotkeys {
+    conn: RustConnection,
+    root: Window,
+    /// (热键 ID, 修饰键掩码, keycode)
+    grabs: Vec<(u32, u16, Keycode)>,
+    /// waker 向 wake_tx 写入一个字节，使阻塞在 wake_rx 上的 wait 返回
+    wake_rx: UnixStream,
+    wake_tx: UnixStream,
+}
+
+impl X11Hotkeys {
+    pub fn create() -> StdResult<Box<dyn HotkeyBackend>, String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("无法连接 X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        let (wake_rx, wake_tx) = UnixStream::pair().map_err(|e| e.to_string())?;
+        wake_rx.set_nonblocking(true).map_err(|e| e.to_string())?;
+        wake_tx.set_nonblocking(true).map_err(|e| e.to_string())?;
+        Ok(Box::new(X11Hotkeys {
+            conn,
+            root,
+            grabs: Vec::new(),
+            wake_rx,
+            wake_tx,
+        }))
+    }
+
+    fn keycodes_for(&self, keysym: u32) -> StdResult<Vec<Keycode>, String> {
+        let setup = self.conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = self
+            .conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        let per = mapping.keysyms_per_keycode.max(1) as usize;
+        Ok(mapping
+            .keysyms
+            .chunks(per)
+            .zip(min..=max)
+            .filter(|(syms, _)| syms.contains(&keysym))
+            .map(|(_, code)| code)
+            .collect())
+    }
+}
+
+fn modifier_mask(chord: &Chord) -> u16 {
+    let mut mask = 0u16;
+    if chord.modifiers.ctrl {
+        mask |= u16::from(ModMask::CONTROL);
+    }
+    if chord.modifiers.alt {
+        mask |= u16::from(ModMask::M1);
+    }
+    if chord.modifiers.shift {
+        mask |= u16::from(ModMask::SHIFT);
+    }
+    if chord.modifiers.meta {
+        mask |= u16::from(ModMask::M4);
+    }
+    mask
+}
+
+fn keysym(key: Key) -> u32 {
+    match key {
+        // 字母 keysym 以小写形式出现在键盘映射的第一列
+        Key::Letter(c) => c.to_ascii_lowercase() as u32,
+        Key::Digit(d) => b'0' as u32 + d as u32,
+        Key::F(n) => XK_F1 + n as u32 - 1,
+        Key::Numpad(d) => XK_KP_0 + d as u32,
+        Key::Space => 0x0020,
+        Key::Enter => 0xff0d,
+        Key::Tab => 0xff09,
+        Key::Escape => 0xff1b,
+        Key::Backspace => 0xff08,
+        Key::Delete => 0xffff,
+        Key::Insert => 0xff63,
+        Key::Home => 0xff50,
+        Key::End => 0xff57,
+        Key::PageUp => 0xff55,
+        Key::PageDown => 0xff56,
+        Key::Up => 0xff52,
+        Key::Down => 0xff54,
+        Key::Left => 0xff51,
+        Key::Right => 0xff53,
+        Key::PrintScreen => 0xff61,
+        Key::Pause => 0xff13,
+        Key::NumpadAdd => 0xffab,
+        Key::NumpadSubtract => 0xffad,
+        Key::NumpadMultiply => 0xffaa,
+        Key::NumpadDivide => 0xffaf,
+        Key::NumpadDecimal => 0xffae,
+        // 标点的 keysym 等于其 ASCII 值
+        Key::Minus => '-' as u32,
+        Key::Equal => '=' as u32,
+        Key::BracketLeft => '[' as u32,
+        Key::BracketRight => ']' as u32,
+        Key::Backslash => '\\' as u32,
+        Key::Semicolon => ';' as u32,
+        Key::Quote => '\'' as u32,
+        Key::Comma => ',' as u32,
+        Key::Period => '.' as u32,
+        Key::Slash => '/' as u32,
+        Key::Backquote => '`' as u32,
+        Key::VolumeUp => 0x1008ff13,
+        Key::VolumeDown => 0x1008ff11,
+        Key::VolumeMute => 0x1008ff12,
+        Key::MediaPlayPause => 0x1008ff14,
+        Key::MediaNext => 0x1008ff17,
+        Key::MediaPrevious => 0x1008ff16,
+        Key::MediaStop => 0x1008ff15,
+    }
+}
+
+// 需要忽略的锁定键：CapsLock (Lock) 与 NumLock (通常为 Mod2)
+fn lock_masks() -> [u16; 4] {
+    let caps = u16::from(ModMask::LOCK);
+    let num = u16::from(ModMask::M2);
+    [0, caps, num, caps | num]
+}
+
+impl HotkeyBackend for X11Hotkeys {
+    fn register(&mut self, id: u32, chord: &Chord) -> StdResult<(), String> {
+        let keycodes = self.keycodes_for(keysym(chord.key))?;
+        if keycodes.is_empty() {
+            return Err(format!("当前键盘布局中找不到按键: {}", chord.key.name()));
+        }
+
+        let mask = modifier_mask(chord);
+        for &keycode in &keycodes {
+            // 先记录再抓取：失败时 unregister 会一并撤销本热键已成功的抓取
+            self.grabs.push((id, mask, keycode));
+            for lock in lock_masks() {
+                let grabbed = self
+                    .conn
+                    .grab_key(
+                        true,
+                        self.root,
+                        ModMask::from(mask | lock),
+                        keycode,
+                        GrabMode::ASYNC,
+                        GrabMode::ASYNC,
+                    )
+                    .map_err(|e| e.to_string())
+                    .and_then(|cookie| cookie.check().map_err(|e| e.to_string()));
+                if let Err(e) = grabbed {
+                    self.unregister(id);
+                    return Err(format!(
+                        "无法注册热键 {}（可能已被其他程序占用）: {}",
+                        chord, e
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unregister(&mut self, id: u32) {
+        let root = self.root;
+        for &(_, mask, keycode) in self.grabs.iter().filter(|(g, _, _)| *g == id) {
+            // 未抓取成功的组合也一并撤销：UngrabKey 只影响本连接的抓取
+            for lock in lock_masks() {
+                let _ = self
+                    .conn
+                    .ungrab_key(keycode, root, ModMask::from(mask | lock));
+            }
+        }
+        self.grabs.retain(|(g, _, _)| *g != id);
+        let _ = self.conn.flush();
+    }
+
+    fn poll(&mut self) -> Vec<u32> {
+        let relevant = u16::from(ModMask::CONTROL)
+            | u16::from(ModMask::M1)
+            | u16::from(ModMask::SHIFT)
+            | u16::from(ModMask::M4);
+        let mut fired = Vec::new();
+        loop {
+            match self.conn.poll_for_event() {
+                Ok(Some(Event::KeyPress(e))) => {
+                    let state = u16::from(e.state) & relevant;
+                    if let Some((id, _, _)) = self
+                        .grabs
+                        .iter()
+                        .find(|(_, mask, code)| *code == e.detail && *mask == state)
+                    {
+                        fired.push(*id);
+                    }
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => {
+                    log::error!("X11 连接错误: {}", e);
+                    break;
+                }
+            }
+        }
+        fired
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> Vec<u32> {
+        let fired = self.poll();
+        if !fired.is_empty() {
+            return fired;
+        }
+        let _ = self.conn.flush();
+        let mut fds = [
+            libc::pollfd {
+                fd: self.conn.stream().as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.wake_rx.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let millis = timeout.map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+        unsafe {
+            libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, millis);
+        }
+        if fds[0].revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+            // 连接已断开：poll 会立即返回，避免空转
+            std::thread::sleep(timeout.unwrap_or(Duration::from_secs(1)));
+        }
+        let mut buf = [0u8; 16];
+        while matches!(self.wake_rx.read(&mut buf), Ok(n) if n > 0) {}
+        self.poll()
+    }
+
+    fn waker(&self) -> Waker {
+        match self.wake_tx.try_clone() {
+            Ok(tx) => Box::new(move || {
+                let _ = (&tx).write(&[1]);
+            }),
+            Err(e) => {
+                log::error!("无法创建热键线程的唤醒句柄: {}", e);
+                Box::new(|| {})
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::protocol::xproto::{KEY_PRESS_EVENT, KEY_RELEASE_EVENT};
+    use x11rb::protocol::xtest::ConnectionExt as _;
+
+    /// 需要 X server：`xvfb-run cargo test -p service -- --ignored`
+    #[test]
+    #[ignore = "requires an X server (run under xvfb-run)"]
+    fn test_grab_and_receive_hotkey_on_xvfb() {
+        let mut backend = X11Hotkeys::create().unwrap();
+        let chord = Chord::parse("Ctrl+Alt+K").unwrap();
+        backend.register(4, &chord).unwrap();
+
+        // 通过 XTest 模拟按下 Ctrl+Alt+K
+        let (conn, screen_num) = x11rb::connect(None).unwrap();
+        let root = conn.setup().roots[screen_num].root;
+        let (wake_rx, wake_tx) = UnixStream::pair().unwrap();
+        let probe = X11Hotkeys {
+            conn,
+            root,
+            grabs: Vec::new(),
+            wake_rx,
+            wake_tx,
+        };
+        let ctrl = probe.keycodes_for(0xffe3).unwrap()[0];
+        let alt = probe.keycodes_for(0xffe9).unwrap()[0];
+        let k = probe.keycodes_for('k' as u32).unwrap()[0];
+        for (kind, code) in [
+            (KEY_PRESS_EVENT, ctrl),
+            (KEY_PRESS_EVENT, alt),
+            (KEY_PRESS_EVENT, k),
+            (KEY_RELEASE_EVENT, k),
+            (KEY_RELEASE_EVENT, alt),
+            (KEY_RELEASE_EVENT, ctrl),
+        ] {
+            probe
+                .conn
+                .xtest_fake_input(kind, code, x11rb::CURRENT_TIME, root, 0, 0, 0)
+                .unwrap();
+        }
+        probe.conn.flush().unwrap();
+
+        let timeout = Some(Duration::from_secs(1));
+        assert_eq!(backend.wait(timeout), vec![4]);
+
+        // waker 使阻塞中的 wait 立即返回
+        backend.waker()();
+        let started = std::time::Instant::now();
+        assert!(backend.wait(Some(Duration::from_secs(5))).is_empty());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
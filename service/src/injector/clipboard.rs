@@ -0,0 +1,161 @@
+// Clipboard
+// 剪贴板的平台抽象：剪贴板注入前对所有格式做快照，粘贴后原样恢复。
+// 平台实现见 win32.rs（Win32 剪贴板格式）与 x11.rs（CLIPBOARD selection targets）。
+
+use crate::richtext::FormattedText;
+use std::result::Result as StdResult;
+use std::sync::Mutex;
+
+/// 快照的总大小上限，超出部分的格式不做备份（避免复制超大图片/文件列表时占用过多内存）
+pub(crate) const MAX_SNAPSHOT_BYTES: usize = 128 * 1024 * 1024;
+
+/// 剪贴板中一种格式的原始数据
+///
+/// format 为平台相关的格式名：Win32 注册格式名或 "#<id>"（标准格式），X11 target 名（如 "text/html"）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardItem {
+    pub format: String,
+    pub data: Vec<u8>,
+}
+
+/// 剪贴板在某一时刻的全部内容；空快照表示剪贴板为空
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClipboardSnapshot {
+    pub items: Vec<ClipboardItem>,
+}
+
+impl ClipboardSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// 所有格式的数据总字节数
+    pub fn size(&self) -> usize {
+        self.items.iter().map(|i| i.data.len()).sum()
+    }
+
+    pub fn get(&self, format: &str) -> Option<&[u8]> {
+        self.items
+            .iter()
+            .find(|i| i.format == format)
+            .map(|i| i.data.as_slice())
+    }
+
+    /// 按大小上限追加一种格式；超出时跳过并返回 false
+    pub(crate) fn push_within_limit(&mut self, format: String, data: Vec<u8>) -> bool {
+        if self.size() + data.len() > MAX_SNAPSHOT_BYTES {
+            log::warn!(
+                "Clipboard format '{}' ({} bytes) exceeds the snapshot limit, not preserved",
+                format,
+                data.len()
+            );
+            return false;
+        }
+        self.items.push(ClipboardItem { format, data });
+        true
+    }
+}
+
+pub trait Clipboard: Send + Sync {
+    /// 读取纯文本（供 {{clipboard}} 动态变量使用）
+    fn read_text(&self) -> Option<String>;
+
+    /// 写入注入用的临时内容（纯文本及可选的 HTML / RTF），并附带标记让剪贴板历史管理器忽略这次写入
+    fn write_transient(&self, content: &FormattedText)
+    -> StdResult<(), Box<dyn std::error::Error>>;
+
+    fn write_transient_text(&self, text: &str) -> StdResult<(), Box<dyn std::error::Error>> {
+        self.write_transient(&FormattedText::plain(text))
+    }
+
+    /// 复制剪贴板中当前可用的全部格式
+    fn snapshot(&self) -> StdResult<ClipboardSnapshot, Box<dyn std::error::Error>>;
+
+    /// 用快照替换剪贴板内容（空快照会清空剪贴板）
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> StdResult<(), Box<dyn std::error::Error>>;
+
+    /// 剪贴板内容每次变化都会改变的标记（Win32 序列号、X11 所有者窗口）；不支持时返回 None
+    fn change_marker(&self) -> Option<u64>;
+}
+
+/// 临时内容在 MemoryClipboard 中的标记格式（与 X11 实现使用的 KDE 约定一致）
+pub const TRANSIENT_MARKER_FORMAT: &str = "x-kde-passwordManagerHint";
+/// MemoryClipboard 中纯文本使用的格式名
+pub const MEMORY_TEXT_FORMAT: &str = "text/plain;charset=utf-8";
+pub const MEMORY_HTML_FORMAT: &str = "text/html";
+pub const MEMORY_RTF_FORMAT: &str = "text/rtf";
+
+/// 内存剪贴板：用于测试及没有系统剪贴板的环境
+#[derive(Default)]
+pub struct MemoryClipboard {
+    state: Mutex<(ClipboardSnapshot, u64)>,
+}
+
+impl MemoryClipboard {
+    pub fn new(snapshot: ClipboardSnapshot) -> Self {
+        MemoryClipboard {
+            state: Mutex::new((snapshot, 0)),
+        }
+    }
+
+    /// 模拟用户（或其他程序）复制了新内容
+    pub fn set_contents(&self, snapshot: ClipboardSnapshot) {
+        let mut state = self.state.lock().unwrap();
+        state.0 = snapshot;
+        state.1 += 1;
+    }
+
+    pub fn contents(&self) -> ClipboardSnapshot {
+        self.state.lock().unwrap().0.clone()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn read_text(&self) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .0
+            .get(MEMORY_TEXT_FORMAT)
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+    }
+
+    fn write_transient(
+        &self,
+        content: &FormattedText,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        let mut items = vec![ClipboardItem {
+            format: MEMORY_TEXT_FORMAT.to_string(),
+            data: content.plain.as_bytes().to_vec(),
+        }];
+        for (format, data) in [
+            (MEMORY_HTML_FORMAT, &content.html),
+            (MEMORY_RTF_FORMAT, &content.rtf),
+        ] {
+            if let Some(data) = data {
+                items.push(ClipboardItem {
+                    format: format.to_string(),
+                    data: data.as_bytes().to_vec(),
+                });
+            }
+        }
+        items.push(ClipboardItem {
+            format: TRANSIENT_MARKER_FORMAT.to_string(),
+            data: b"secret".to_vec(),
+        });
+        self.set_contents(ClipboardSnapshot { items });
+        Ok(())
+    }
+
+    fn snapshot(&self) -> StdResult<ClipboardSnapshot, Box<dyn std::error::Error>> {
+        Ok(self.contents())
+    }
+
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> StdResult<(), Box<dyn std::error::Error>> {
+        self.set_contents(snapshot.clone());
+        Ok(())
+    }
+
+    fn change_marker(&self) -> Option<u64> {
+        Some(self.state.lock().unwrap().1)
+    }
+}
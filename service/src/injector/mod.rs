@@ -1,326 +1,921 @@
-use crate::config::Config;
+// Text Injection
+// Injector 按配置的策略链（injection.order 或应用的 primary + fallback）依次尝试注入，
+// 具体的窗口激活、剪贴板与键盘模拟由平台后端（Win32 / X11）提供。
+
+pub mod clipboard;
+pub mod strategy;
+pub mod typing;
+#[cfg(windows)]
+mod win32;
+#[cfg(not(windows))]
+mod x11;
+
+pub use clipboard::{Clipboard, ClipboardSnapshot};
+pub use strategy::{InjectionStrategy, StrategyRegistry};
+pub use typing::{NamedKey, PartiallyTyped, TypingEngine, TypingEvent, backspaces};
+
+use crate::config::{ApplicationSettings, Config};
+use crate::context::WindowHandle;
+use crate::richtext::FormattedText;
 use std::result::Result as StdResult;
 use std::time::Duration;
-use windows::{
-    Win32::Foundation::*, Win32::System::DataExchange::*, Win32::System::Memory::*,
-    Win32::UI::Input::KeyboardAndMouse::*, Win32::UI::WindowsAndMessaging::*,
-};
-
-// windows 0.58 下方便使用的常量（CF_UNICODETEXT = 13）
-const CF_UNICODETEXT_CONST: u32 = 13;
 
-/// Maximum clipboard size to read (1M UTF-16 chars = 2MB)
+/// Maximum clipboard size to read (1M chars)
 /// Prevents potential unsafe memory overflow attacks.
-const MAX_CLIPBOARD_SIZE: usize = 1_000_000;
+pub(crate) const MAX_CLIPBOARD_SIZE: usize = 1_000_000;
 
 #[derive(Debug)]
 pub struct InjectionContext {
     pub app_name: String,
     pub window_title: String,
-    pub window_handle: HWND,
+    pub window_handle: WindowHandle,
+}
+
+/// 重试同一策略前的等待时间
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+/// 重新激活目标窗口后等待焦点切换的时间
+const FOCUS_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// 注入后校验失败的原因；只有能确定文本没有进入目标时才换下一个策略，避免重复输入
+#[derive(Debug, Clone, PartialEq)]
+enum VerifyError {
+    /// 目标窗口失去焦点且无法重新激活
+    LostFocus(WindowHandle),
+    /// 回读的控件文本与注入前相同
+    Unchanged,
 }
 
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::LostFocus(focused) => {
+                write!(f, "目标窗口失去焦点（当前焦点 0x{:x}）", focused.0)
+            }
+            VerifyError::Unchanged => write!(f, "回读的控件文本没有变化"),
+        }
+    }
+}
+
+/// 单个策略的尝试结果
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum InjectionStrategy {
-    Clipboard,
-    SendInput,
+pub struct InjectionAttempt {
+    pub strategy: &'static str,
+    /// None 表示该策略成功
+    pub error: Option<String>,
+}
+
+/// 一次注入的完整记录：依次尝试过的策略与总耗时
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectionReport {
+    pub attempts: Vec<InjectionAttempt>,
+    pub elapsed_ms: u64,
+}
+
+impl InjectionReport {
+    /// 最终成功的策略；全部失败时为 None
+    pub fn strategy(&self) -> Option<&'static str> {
+        self.attempts
+            .iter()
+            .find(|a| a.error.is_none())
+            .map(|a| a.strategy)
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.strategy().is_some()
+    }
+
+    /// 尝试过的策略链，例如 "Clipboard → SendInput"
+    pub fn fallback_chain(&self) -> String {
+        self.attempts
+            .iter()
+            .map(|a| a.strategy)
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+
+    /// 失败策略的错误信息，例如 "Clipboard: paste blocked"；没有失败时为 None
+    pub fn error(&self) -> Option<String> {
+        if self.attempts.is_empty() {
+            return Some("没有可用的注入策略".to_string());
+        }
+        let errors: Vec<String> = self
+            .attempts
+            .iter()
+            .filter_map(|a| a.error.as_ref().map(|e| format!("{}: {}", a.strategy, e)))
+            .collect();
+        (!errors.is_empty()).then(|| errors.join("; "))
+    }
+}
+
+/// 注入所需的平台能力
+pub trait InputBackend: Send + Sync {
+    /// 将目标窗口置前（尽力而为）
+    fn activate_window(&self, window: WindowHandle);
+    fn clipboard(&self) -> &dyn Clipboard;
+    /// 模拟 Ctrl+V
+    fn send_paste(&self) -> StdResult<(), Box<dyn std::error::Error>>;
+    /// 一次性发送一批键盘事件（由 TypingEngine 分批，同一批内不会插入其他输入）
+    fn send_keys(&self, events: &[TypingEvent]) -> StdResult<(), Box<dyn std::error::Error>>;
+
+    /// 关闭焦点窗口的输入法；返回 true 表示输入法原本是打开的，键入结束后需调用 open_ime
+    fn close_ime(&self) -> bool {
+        false
+    }
+
+    fn open_ime(&self) {}
+
+    /// 当前拥有焦点的顶层窗口；无法确定时返回 None（跳过焦点校验）
+    fn focused_window(&self) -> Option<WindowHandle> {
+        None
+    }
+
+    /// 回读焦点控件中的文本；平台或控件不支持时返回 None（跳过内容校验）
+    fn read_focused_text(&self) -> Option<String> {
+        None
+    }
+}
+
+fn default_backend() -> Box<dyn InputBackend> {
+    #[cfg(windows)]
+    return Box::new(win32::Win32Input);
+    #[cfg(not(windows))]
+    return Box::new(x11::X11Input);
 }
 
 // EditorType and EditorDetection removed (UIA-specific, no longer used)
 
 pub struct Injector {
     config: Config,
+    backend: Box<dyn InputBackend>,
+    registry: StrategyRegistry,
 }
 
 // describe_element deleted (T0-002 Step 1.2)
 
 impl Injector {
-    pub fn new(_strategies: Vec<InjectionStrategy>, config: Config) -> Self {
+    pub fn new(config: Config) -> Self {
         log::debug!("Creating injector with config-driven strategies");
-        Injector { config }
+        Self::with_backend(config, default_backend())
     }
 
-    pub fn inject(
+    pub fn with_backend(config: Config, backend: Box<dyn InputBackend>) -> Self {
+        Injector {
+            registry: StrategyRegistry::for_config(&config.injection),
+            config,
+            backend,
+        }
+    }
+
+    /// 替换策略注册表（测试或自定义策略用）
+    pub fn with_registry(mut self, registry: StrategyRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// 读取当前剪贴板文本（供 {{clipboard}} 动态变量使用）
+    pub fn read_clipboard_text(&self) -> Option<String> {
+        self.backend.clipboard().read_text()
+    }
+
+    /// 在目标窗口中按 count 次退格（文本扩展删除已输入的缩写）
+    pub fn erase(
         &self,
-        text: &str,
+        count: usize,
+        context: &InjectionContext,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        self.backend.activate_window(context.window_handle);
+        self.backend.send_keys(&backspaces(count))
+    }
+
+    /// 注入纯文本，见 inject_formatted
+    pub fn inject(&self, text: &str, context: &InjectionContext) -> InjectionReport {
+        self.inject_formatted(&FormattedText::plain(text), context)
+    }
+
+    /// 按策略链依次尝试注入，每个策略最多重试 injection.max_retries 次；
+    /// 返回每次尝试的结果（不会因单个策略失败而提前返回错误）
+    pub fn inject_formatted(
+        &self,
+        content: &FormattedText,
         context: &InjectionContext,
-    ) -> StdResult<(String, u64), Box<dyn std::error::Error>> {
-        log::info!("Injecting text using simplified strategy (Clipboard → SendInput)");
+    ) -> InjectionReport {
+        let text = content.plain.as_str();
+        let names = self.config.strategy_order_for(&context.app_name);
+        let chain = self
+            .registry
+            .resolve(&names, self.config.injection.allow_clipboard);
+        log::info!(
+            "Injecting text using strategies: {}",
+            chain
+                .iter()
+                .map(|s| s.name())
+                .collect::<Vec<_>>()
+                .join(" → ")
+        );
         log::debug!(
-            "Text length: {}, app: {}, window_title: {}",
+            "Text length: {}, rich text: {}, app: {}, window_title: {}",
             text.len(),
+            content.is_rich(),
             context.app_name,
             context.window_title
         );
 
         let start = std::time::Instant::now();
+        let settings = self.config.get_app_config(&context.app_name).settings;
+        let pre_inject_delay = Duration::from_millis(settings.pre_inject_delay);
+        let mut attempts = Vec::new();
 
-        // Primary strategy: Clipboard (works in 99% of scenarios)
-        match self.inject_via_clipboard(text, context) {
-            Ok(_) => {
-                let elapsed = start.elapsed().as_millis() as u64;
-                log::info!("Successfully injected text via Clipboard in {}ms", elapsed);
-                return Ok(("Clipboard".to_string(), elapsed));
-            }
-            Err(e) => {
-                log::warn!(
-                    "Clipboard injection failed: {}. Falling back to SendInput",
-                    e
-                );
+        'chain: for strategy in chain {
+            for retry in 0..=self.config.injection.max_retries {
+                if retry > 0 {
+                    log::info!(
+                        "Retrying {} ({}/{})",
+                        strategy.name(),
+                        retry,
+                        self.config.injection.max_retries
+                    );
+                    std::thread::sleep(RETRY_DELAY);
+                }
+
+                // 先将目标窗口置前，并等待焦点稳定
+                self.backend.activate_window(context.window_handle);
+                std::thread::sleep(pre_inject_delay);
+
+                // 富文本在控件中的呈现与纯文本不同，无法按内容回读校验
+                let read_back = settings.verify_injection && !content.is_rich();
+                let before = read_back
+                    .then(|| self.backend.read_focused_text())
+                    .flatten();
+
+                let result = strategy.inject(self.backend.as_ref(), content, context);
+                // 注入本身成功但校验失败时不再重试该策略，直接换下一个策略
+                if result.is_ok()
+                    && settings.verify_injection
+                    && let Err(e) = self.verify(text, before.as_deref(), context, &settings)
+                {
+                    log::warn!("{} injection could not be verified: {}", strategy.name(), e);
+                    attempts.push(InjectionAttempt {
+                        strategy: strategy.name(),
+                        error: Some(format!("验证失败: {}", e)),
+                    });
+                    continue 'chain;
+                }
+
+                match result {
+                    Ok(()) => {
+                        log::info!(
+                            "Successfully injected text via {} in {}ms",
+                            strategy.name(),
+                            start.elapsed().as_millis()
+                        );
+                        attempts.push(InjectionAttempt {
+                            strategy: strategy.name(),
+                            error: None,
+                        });
+                        break 'chain;
+                    }
+                    Err(e) => {
+                        log::warn!("{} injection failed: {}", strategy.name(), e);
+                        attempts.push(InjectionAttempt {
+                            strategy: strategy.name(),
+                            error: Some(e.to_string()),
+                        });
+                        // 部分文本已进入目标：重试或换策略会重复输入
+                        if e.downcast_ref::<PartiallyTyped>().is_some() {
+                            break 'chain;
+                        }
+                    }
+                }
             }
         }
 
-        // Fallback strategy: SendInput (for apps that block paste)
-        self.inject_via_sendinput(text, context)?;
-        let elapsed = start.elapsed().as_millis() as u64;
-        log::info!("Successfully injected text via SendInput in {}ms", elapsed);
-        Ok(("SendInput".to_string(), elapsed))
+        InjectionReport {
+            attempts,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        }
     }
 
-    // effective_strategies_for deleted (T0-003 - strategy now hardcoded)
-
-    fn inject_via_clipboard(
+    /// 注入后校验：目标窗口仍有焦点（失去焦点时重新激活，最多 focus_retry_count 次），
+    /// 有注入前的回读结果时确认焦点控件的文本发生了变化
+    ///
+    /// 控件可能改写注入的文本（自动缩进、补全括号等），文本有变化但不包含注入内容时
+    /// 仍视为成功：此时换策略会再输入一遍
+    fn verify(
         &self,
         text: &str,
+        before: Option<&str>,
         context: &InjectionContext,
-    ) -> StdResult<(), Box<dyn std::error::Error>> {
-        log::debug!("Attempting clipboard injection");
-
-        // 0) 先将目标窗口置前，确保焦点正确
-        unsafe {
-            let _ = SetForegroundWindow(context.window_handle);
-        }
-        std::thread::sleep(Duration::from_millis(
-            self.get_pre_inject_delay(&context.app_name),
-        ));
-
-        // 1) 打开剪贴板，最多尝试 5 次
-        let mut opened = false;
-        for _ in 0..5 {
-            unsafe {
-                if OpenClipboard(HWND(std::ptr::null_mut())).is_ok() {
-                    opened = true;
+        settings: &ApplicationSettings,
+    ) -> StdResult<(), VerifyError> {
+        if !context.window_handle.is_null() {
+            let mut retries = 0;
+            while let Some(focused) = self.backend.focused_window() {
+                if focused == context.window_handle {
+                    break;
                 }
+                if retries >= settings.focus_retry_count {
+                    return Err(VerifyError::LostFocus(focused));
+                }
+                retries += 1;
+                log::info!(
+                    "Target window lost focus, re-activating ({}/{})",
+                    retries,
+                    settings.focus_retry_count
+                );
+                self.backend.activate_window(context.window_handle);
+                std::thread::sleep(FOCUS_RETRY_DELAY);
+            }
+        }
+
+        if let Some(before) = before
+            && let Some(actual) = self.backend.read_focused_text()
+        {
+            // 控件可能把换行存为 \r\n
+            let normalize = |s: &str| s.replace("\r\n", "\n");
+            if actual == before {
+                return Err(VerifyError::Unchanged);
             }
-            if opened {
-                break;
+            if normalize(&actual).contains(&normalize(text)) {
+                log::debug!("Injection verified by reading back the focused control");
+            } else {
+                log::warn!("Focused control changed but does not contain the injected text");
             }
-            std::thread::sleep(Duration::from_millis(10));
         }
-        if !opened {
-            return Err("OpenClipboard failed".into());
+        Ok(())
+    }
+}
+
+// find_editable_element deleted (T0-002)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use clipboard::{
+        ClipboardItem, MEMORY_HTML_FORMAT, MEMORY_TEXT_FORMAT, MemoryClipboard,
+        TRANSIENT_MARKER_FORMAT,
+    };
+
+    // 记录调用序列的假后端；paste_fails 时模拟应用拦截粘贴，
+    // copied_during_paste 模拟用户在粘贴期间复制了新内容
+    struct FakeInput {
+        calls: Arc<Mutex<Vec<String>>>,
+        clipboard: Arc<MemoryClipboard>,
+        paste_fails: bool,
+        copied_during_paste: Option<ClipboardSnapshot>,
+        /// 前几次 send_keys 成功，之后失败
+        keys_fail_after: Option<usize>,
+    }
+
+    impl InputBackend for FakeInput {
+        fn activate_window(&self, window: WindowHandle) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("activate {}", window.0));
         }
 
-        // 2) 读取现有剪贴板文本，用于注入后恢复
-        let mut prev_text: Option<Vec<u16>> = None;
-        unsafe {
-            if IsClipboardFormatAvailable(CF_UNICODETEXT_CONST).is_ok() {
-                if let Ok(h) = GetClipboardData(CF_UNICODETEXT_CONST) {
-                    let hg = HGLOBAL(h.0);
-                    let ptr = GlobalLock(hg) as *const u16;
-                    if !ptr.is_null() {
-                        let mut v = Vec::new();
-                        let mut p = ptr;
-                        let mut len = 0usize;
-                        loop {
-                            if len >= MAX_CLIPBOARD_SIZE {
-                                log::warn!(
-                                    "Clipboard backup exceeds max size ({}), truncating",
-                                    MAX_CLIPBOARD_SIZE
-                                );
-                                break;
-                            }
-                            let ch = *p;
-                            v.push(ch);
-                            if ch == 0 {
-                                break;
-                            }
-                            p = p.add(1);
-                            len += 1;
-                        }
-                        prev_text = Some(v);
-                        let _ = GlobalUnlock(hg);
-                    }
-                }
-            }
+        fn clipboard(&self) -> &dyn Clipboard {
+            self.clipboard.as_ref()
         }
 
-        // 3) 设置我们的文本到剪贴板
-        unsafe {
-            let _ = EmptyClipboard();
-            let mut utf16: Vec<u16> = text.encode_utf16().collect();
-            utf16.push(0);
-            let bytes = (utf16.len() * std::mem::size_of::<u16>()) as usize;
-            let hmem = GlobalAlloc(GMEM_MOVEABLE, bytes).map_err(|_| "GlobalAlloc failed")?;
-            let ptr = GlobalLock(hmem) as *mut u8;
-            if ptr.is_null() {
-                let _ = GlobalFree(hmem);
-                let _ = CloseClipboard();
-                return Err("GlobalLock failed".into());
+        fn send_paste(&self) -> StdResult<(), Box<dyn std::error::Error>> {
+            let pasted = self.clipboard.read_text().unwrap_or_default();
+            let html = self
+                .clipboard
+                .contents()
+                .get(MEMORY_HTML_FORMAT)
+                .map(|html| String::from_utf8_lossy(html).into_owned());
+            if let Some(copied) = &self.copied_during_paste {
+                self.clipboard.set_contents(copied.clone());
             }
-            std::ptr::copy_nonoverlapping(utf16.as_ptr() as *const u8, ptr, bytes);
-            let _ = GlobalUnlock(hmem);
-            if SetClipboardData(CF_UNICODETEXT_CONST, HANDLE(hmem.0)).is_err() {
-                let _ = GlobalFree(hmem);
-                let _ = CloseClipboard();
-                return Err("SetClipboardData failed".into());
+            if self.paste_fails {
+                return Err("paste blocked".into());
             }
-            let _ = CloseClipboard();
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(format!("paste {}", pasted));
+            if let Some(html) = html {
+                calls.push(format!("paste html {}", html));
+            }
+            Ok(())
         }
 
-        // 4) 等待一下，确保热键修饰键已释放，然后模拟 Ctrl+V 粘贴
-        std::thread::sleep(Duration::from_millis(200));
-        unsafe {
-            let mut inputs = [
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: VK_CONTROL,
-                            wScan: VIRTUAL_KEY(0).0 as u16,
-                            dwFlags: KEYBD_EVENT_FLAGS(0),
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: VIRTUAL_KEY(0x56),
-                            wScan: 0,
-                            dwFlags: KEYBD_EVENT_FLAGS(0),
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: VIRTUAL_KEY(0x56),
-                            wScan: 0,
-                            dwFlags: KEYBD_EVENT_FLAGS(KEYEVENTF_KEYUP.0),
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: VK_CONTROL,
-                            wScan: VIRTUAL_KEY(0).0 as u16,
-                            dwFlags: KEYBD_EVENT_FLAGS(KEYEVENTF_KEYUP.0),
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-            ];
-            if SendInput(&mut inputs, std::mem::size_of::<INPUT>() as i32) == 0 {
-                return Err("SendInput Ctrl+V failed".into());
+        fn send_keys(&self, events: &[TypingEvent]) -> StdResult<(), Box<dyn std::error::Error>> {
+            let text: String = events
+                .iter()
+                .filter_map(|e| match e {
+                    TypingEvent::Char(c) => Some(*c),
+                    TypingEvent::KeyDown(NamedKey::Enter) => Some('\n'),
+                    _ => None,
+                })
+                .collect();
+            let mut calls = self.calls.lock().unwrap();
+            let typed = calls.iter().filter(|c| c.starts_with("type ")).count();
+            if self.keys_fail_after.is_some_and(|n| typed >= n) {
+                return Err("input blocked".into());
             }
+            calls.push(format!("type {}", text));
+            Ok(())
+        }
+    }
+
+    fn snapshot(items: &[(&str, &[u8])]) -> ClipboardSnapshot {
+        ClipboardSnapshot {
+            items: items
+                .iter()
+                .map(|&(format, data)| ClipboardItem {
+                    format: format.to_string(),
+                    data: data.to_vec(),
+                })
+                .collect(),
         }
+    }
 
-        // 5) 粘贴后稍等再恢复剪贴板（避免覆盖目标应用读取）
-        std::thread::sleep(Duration::from_millis(100));
-
-        if let Some(v) = prev_text {
-            unsafe {
-                if OpenClipboard(HWND(std::ptr::null_mut())).is_ok() {
-                    let _ = EmptyClipboard();
-                    let bytes = (v.len() * std::mem::size_of::<u16>()) as usize;
-                    let hmem =
-                        GlobalAlloc(GMEM_MOVEABLE, bytes).map_err(|_| "GlobalAlloc failed")?;
-                    let ptr = GlobalLock(hmem) as *mut u8;
-                    if !ptr.is_null() {
-                        std::ptr::copy_nonoverlapping(v.as_ptr() as *const u8, ptr, bytes);
-                        let _ = GlobalUnlock(hmem);
-                        let _ = SetClipboardData(CF_UNICODETEXT_CONST, HANDLE(hmem.0));
-                    } else {
-                        let _ = GlobalFree(hmem);
-                    }
-                    let _ = CloseClipboard();
-                }
+    /// 用户剪贴板中同时有文本、HTML 与图片
+    fn rich_snapshot() -> ClipboardSnapshot {
+        snapshot(&[
+            (MEMORY_TEXT_FORMAT, b"old"),
+            ("text/html", b"<b>old</b>"),
+            ("image/png", &[0x89, b'P', b'N', b'G', 0, 0xff]),
+        ])
+    }
+
+    struct FakeInputOptions {
+        paste_fails: bool,
+        copied_during_paste: Option<ClipboardSnapshot>,
+        skip_restore_if_changed: bool,
+        keys_fail_after: Option<usize>,
+    }
+
+    impl Default for FakeInputOptions {
+        fn default() -> Self {
+            FakeInputOptions {
+                paste_fails: false,
+                copied_during_paste: None,
+                skip_restore_if_changed: true,
+                keys_fail_after: None,
             }
         }
+    }
 
-        log::info!("Text injected via Clipboard paste");
-        Ok(())
+    fn injector(
+        options: FakeInputOptions,
+    ) -> (Injector, Arc<Mutex<Vec<String>>>, Arc<MemoryClipboard>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let clipboard = Arc::new(MemoryClipboard::new(rich_snapshot()));
+        let backend = FakeInput {
+            calls: calls.clone(),
+            clipboard: clipboard.clone(),
+            paste_fails: options.paste_fails,
+            copied_during_paste: options.copied_during_paste,
+            keys_fail_after: options.keys_fail_after,
+        };
+        let mut config = Config::default();
+        config.injection.max_retries = 0;
+        config.injection.skip_restore_if_changed = options.skip_restore_if_changed;
+        (
+            Injector::with_backend(config, Box::new(backend)),
+            calls,
+            clipboard,
+        )
     }
 
-    fn inject_via_sendinput(
-        &self,
-        text: &str,
-        context: &InjectionContext,
-    ) -> StdResult<(), Box<dyn std::error::Error>> {
-        log::debug!("Attempting SendInput injection");
+    // 焦点依次返回 focus 中的窗口（用完后保持最后一个），回读返回 field_text
+    struct VerifyInput {
+        focus: Mutex<Vec<u64>>,
+        /// 依次回读到的控件文本，最后一项保持不变；为空时不支持回读
+        field_text: Mutex<Vec<String>>,
+        activations: Arc<Mutex<u32>>,
+        clipboard: MemoryClipboard,
+    }
 
-        // 将目标窗口置前
-        unsafe {
-            let _ = SetForegroundWindow(context.window_handle);
+    impl InputBackend for VerifyInput {
+        fn activate_window(&self, _window: WindowHandle) {
+            *self.activations.lock().unwrap() += 1;
         }
 
-        // 等待焦点稳定
-        std::thread::sleep(Duration::from_millis(
-            self.get_pre_inject_delay(&context.app_name),
-        ));
-
-        // 直接使用 SendInput 模拟输入
-        self.type_text_via_sendinput(text)
-    }
-
-    fn get_pre_inject_delay(&self, app_name: &str) -> u64 {
-        let app_config = self.config.get_app_config(app_name);
-        app_config.settings.pre_inject_delay
-    }
-
-    fn type_text_via_sendinput(&self, text: &str) -> StdResult<(), Box<dyn std::error::Error>> {
-        log::debug!("Using SendInput to simulate typing: '{}'", text);
-        // 小延时，避免与热键修饰键冲突或焦点切换未完成
-        std::thread::sleep(Duration::from_millis(80));
-        unsafe {
-            for ch in text.encode_utf16() {
-                let mut inputs = [
-                    INPUT {
-                        r#type: INPUT_KEYBOARD,
-                        Anonymous: INPUT_0 {
-                            ki: KEYBDINPUT {
-                                wVk: VIRTUAL_KEY(0),
-                                wScan: ch,
-                                dwFlags: KEYBD_EVENT_FLAGS(KEYEVENTF_UNICODE.0),
-                                time: 0,
-                                dwExtraInfo: 0,
-                            },
-                        },
-                    },
-                    INPUT {
-                        r#type: INPUT_KEYBOARD,
-                        Anonymous: INPUT_0 {
-                            ki: KEYBDINPUT {
-                                wVk: VIRTUAL_KEY(0),
-                                wScan: ch,
-                                dwFlags: KEYBD_EVENT_FLAGS(KEYEVENTF_UNICODE.0 | KEYEVENTF_KEYUP.0),
-                                time: 0,
-                                dwExtraInfo: 0,
-                            },
-                        },
-                    },
-                ];
-                let sent = SendInput(&mut inputs, std::mem::size_of::<INPUT>() as i32);
-                if sent == 0 {
-                    let err = windows::Win32::Foundation::GetLastError();
-                    log::error!("SendInput failed with error: {:?}", err);
-                    return Err("SendInput failed".into());
-                }
+        fn clipboard(&self) -> &dyn Clipboard {
+            &self.clipboard
+        }
+
+        fn send_paste(&self) -> StdResult<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn send_keys(&self, _events: &[TypingEvent]) -> StdResult<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn focused_window(&self) -> Option<WindowHandle> {
+            let mut focus = self.focus.lock().unwrap();
+            let next = if focus.len() > 1 {
+                focus.remove(0)
+            } else {
+                *focus.first()?
+            };
+            Some(WindowHandle(next))
+        }
+
+        fn read_focused_text(&self) -> Option<String> {
+            let mut texts = self.field_text.lock().unwrap();
+            if texts.len() > 1 {
+                Some(texts.remove(0))
+            } else {
+                texts.first().cloned()
             }
         }
-        Ok(())
     }
-}
 
-// find_editable_element deleted (T0-002)
+    fn verify_input(focus: &[u64], field_text: &[&str]) -> (Box<VerifyInput>, Arc<Mutex<u32>>) {
+        let activations = Arc::new(Mutex::new(0));
+        let backend = VerifyInput {
+            focus: Mutex::new(focus.to_vec()),
+            field_text: Mutex::new(field_text.iter().map(|t| t.to_string()).collect()),
+            activations: activations.clone(),
+            clipboard: MemoryClipboard::default(),
+        };
+        (Box::new(backend), activations)
+    }
+
+    fn context(app_name: &str) -> InjectionContext {
+        InjectionContext {
+            app_name: app_name.to_string(),
+            window_title: "main.rs".to_string(),
+            window_handle: WindowHandle(42),
+        }
+    }
+
+    // 假策略：前 failures 次调用失败，记录每次调用
+    struct MockStrategy {
+        name: &'static str,
+        clipboard: bool,
+        failures: Mutex<u32>,
+        calls: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl InjectionStrategy for MockStrategy {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn uses_clipboard(&self) -> bool {
+            self.clipboard
+        }
+
+        fn inject(
+            &self,
+            _backend: &dyn InputBackend,
+            _content: &FormattedText,
+            _context: &InjectionContext,
+        ) -> StdResult<(), Box<dyn std::error::Error>> {
+            self.calls.lock().unwrap().push(self.name);
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(format!("{} failed", self.name).into());
+            }
+            Ok(())
+        }
+    }
+
+    /// 由 (名称, 是否使用剪贴板, 失败次数) 构造注入器；应用 "editor" 配置为 mock → clipboard
+    fn mock_injector(
+        strategies: &[(&'static str, bool, u32)],
+        configure: impl FnOnce(&mut Config),
+    ) -> (Injector, Arc<Mutex<Vec<&'static str>>>) {
+        let backend = FakeInput {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            clipboard: Arc::new(MemoryClipboard::default()),
+            paste_fails: false,
+            copied_during_paste: None,
+            keys_fail_after: None,
+        };
+        mock_injector_with(strategies, Box::new(backend), configure)
+    }
+
+    fn mock_injector_with(
+        strategies: &[(&'static str, bool, u32)],
+        backend: Box<dyn InputBackend>,
+        configure: impl FnOnce(&mut Config),
+    ) -> (Injector, Arc<Mutex<Vec<&'static str>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = StrategyRegistry::new();
+        for &(name, clipboard, failures) in strategies {
+            registry.register(Box::new(MockStrategy {
+                name,
+                clipboard,
+                failures: Mutex::new(failures),
+                calls: calls.clone(),
+            }));
+        }
+
+        let mut config = Config::default();
+        config.injection.order = vec!["clipboard".to_string(), "sendinput".to_string()];
+        config.injection.max_retries = 0;
+        config.applications.insert(
+            "editor".to_string(),
+            crate::config::ApplicationConfig {
+                strategies: crate::config::StrategyConfig {
+                    primary: "mock".to_string(),
+                    fallback: vec!["uia".to_string(), "clipboard".to_string()],
+                },
+                settings: crate::config::ApplicationSettings {
+                    pre_inject_delay: 0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        configure(&mut config);
+
+        let injector = Injector::with_backend(config, backend).with_registry(registry);
+        (injector, calls)
+    }
+
+    #[test]
+    fn test_clipboard_paste_then_fallback_to_typing() {
+        let ctx = context("notes");
+
+        let (inj, calls, clipboard) = injector(FakeInputOptions::default());
+        let report = inj.inject("hello", &ctx);
+        assert_eq!(report.strategy(), Some("Clipboard"));
+        assert_eq!(report.fallback_chain(), "Clipboard");
+        assert_eq!(report.error(), None);
+        assert_eq!(*calls.lock().unwrap(), ["activate 42", "paste hello"]);
+        // 注入前的全部格式（含 HTML 与图片）原样恢复
+        assert_eq!(clipboard.contents(), rich_snapshot());
+
+        // 粘贴失败时也恢复剪贴板，再回退到模拟键入
+        let (inj, calls, clipboard) = injector(FakeInputOptions {
+            paste_fails: true,
+            ..Default::default()
+        });
+        let report = inj.inject("hello", &ctx);
+        assert_eq!(report.strategy(), Some("SendInput"));
+        assert_eq!(report.fallback_chain(), "Clipboard → SendInput");
+        assert_eq!(report.error().as_deref(), Some("Clipboard: paste blocked"));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["activate 42", "activate 42", "type hello"]
+        );
+        assert_eq!(clipboard.contents(), rich_snapshot());
+    }
+
+    #[test]
+    fn test_partial_typing_is_not_repeated() {
+        let (mut inj, calls, _) = injector(FakeInputOptions {
+            keys_fail_after: Some(1),
+            ..Default::default()
+        });
+        inj.config.injection.order = vec!["sendinput".to_string(), "clipboard".to_string()];
+        inj.config.injection.max_retries = 2;
+
+        // 默认每批 32 个字符：第二批失败时不再重试，也不用剪贴板再粘贴一遍
+        let text = "x".repeat(40);
+        let report = inj.inject(&text, &context("notes"));
+        assert!(!report.succeeded());
+        assert_eq!(report.fallback_chain(), "SendInput");
+        assert_eq!(
+            report.error().as_deref(),
+            Some("SendInput: 已键入 32 个字符后失败: input blocked")
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "activate 42".to_string(),
+                format!("type {}", "x".repeat(32))
+            ]
+        );
+
+        // 第一批就失败时没有输入任何内容，照常回退
+        let (mut inj, calls, _) = injector(FakeInputOptions {
+            keys_fail_after: Some(0),
+            ..Default::default()
+        });
+        inj.config.injection.order = vec!["sendinput".to_string(), "clipboard".to_string()];
+        assert_eq!(
+            inj.inject("hi", &context("notes")).strategy(),
+            Some("Clipboard")
+        );
+        assert_eq!(calls.lock().unwrap().last().unwrap(), "paste hi");
+    }
+
+    #[test]
+    fn test_clipboard_temporary_content_is_tagged() {
+        let clipboard = MemoryClipboard::default();
+        clipboard.write_transient_text("hello").unwrap();
+        assert_eq!(clipboard.read_text().as_deref(), Some("hello"));
+        assert!(clipboard.contents().get(TRANSIENT_MARKER_FORMAT).is_some());
+    }
+
+    #[test]
+    fn test_rich_text_pasted_with_plain_fallback() {
+        let content = FormattedText::from_content("Say **hi**", Some("markdown"));
+
+        let (inj, calls, clipboard) = injector(FakeInputOptions::default());
+        assert!(
+            inj.inject_formatted(&content, &context("notes"))
+                .succeeded()
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "activate 42",
+                "paste Say hi",
+                "paste html <p>Say <strong>hi</strong></p>\n"
+            ]
+        );
+        assert_eq!(clipboard.contents(), rich_snapshot());
+
+        // 键入回退只能输入纯文本
+        let (inj, calls, _) = injector(FakeInputOptions {
+            paste_fails: true,
+            ..Default::default()
+        });
+        assert!(
+            inj.inject_formatted(&content, &context("notes"))
+                .succeeded()
+        );
+        assert_eq!(calls.lock().unwrap().last().unwrap(), "type Say hi");
+    }
+
+    #[test]
+    fn test_clipboard_restore_skipped_when_changed() {
+        let copied = snapshot(&[(MEMORY_TEXT_FORMAT, b"copied meanwhile")]);
+
+        // 粘贴期间用户复制了新内容：保留新内容
+        let (inj, _, clipboard) = injector(FakeInputOptions {
+            copied_during_paste: Some(copied.clone()),
+            ..Default::default()
+        });
+        assert!(inj.inject("hello", &context("notes")).succeeded());
+        assert_eq!(clipboard.contents(), copied);
+
+        // 关闭 skip_restore_if_changed 时总是恢复注入前的内容
+        let (inj, _, clipboard) = injector(FakeInputOptions {
+            copied_during_paste: Some(copied),
+            skip_restore_if_changed: false,
+            ..Default::default()
+        });
+        assert!(inj.inject("hello", &context("notes")).succeeded());
+        assert_eq!(clipboard.contents(), rich_snapshot());
+    }
+
+    #[test]
+    fn test_chain_follows_global_order_and_app_override() {
+        let strategies = [
+            ("Clipboard", true, 1),
+            ("SendInput", false, 0),
+            ("Mock", false, 1),
+        ];
+
+        // 未配置的应用使用全局 injection.order
+        let (inj, calls) = mock_injector(&strategies, |c| {
+            c.injection.order = vec!["sendinput".to_string(), "clipboard".to_string()];
+        });
+        assert_eq!(
+            inj.inject("hi", &context("notes")).strategy(),
+            Some("SendInput")
+        );
+        assert_eq!(*calls.lock().unwrap(), ["SendInput"]);
+
+        // 已配置的应用使用 primary + fallback；未注册的 uia 被跳过
+        let (inj, calls) = mock_injector(&strategies, |_| {});
+        let report = inj.inject("hi", &context("Editor"));
+        assert_eq!(report.strategy(), None);
+        assert_eq!(report.fallback_chain(), "Mock → Clipboard");
+        assert_eq!(
+            report.error().as_deref(),
+            Some("Mock: Mock failed; Clipboard: Clipboard failed")
+        );
+        assert_eq!(*calls.lock().unwrap(), ["Mock", "Clipboard"]);
+    }
+
+    #[test]
+    fn test_chain_honors_allow_clipboard() {
+        let strategies = [("Clipboard", true, 0), ("SendInput", false, 0)];
+        let (inj, calls) = mock_injector(&strategies, |c| c.injection.allow_clipboard = false);
+        assert_eq!(
+            inj.inject("hi", &context("notes")).strategy(),
+            Some("SendInput")
+        );
+        assert_eq!(*calls.lock().unwrap(), ["SendInput"]);
+
+        // 只剩剪贴板策略时没有可用策略
+        let (inj, calls) = mock_injector(&strategies, |c| {
+            c.injection.allow_clipboard = false;
+            c.injection.order = vec!["clipboard".to_string()];
+        });
+        let report = inj.inject("hi", &context("notes"));
+        assert!(!report.succeeded());
+        assert_eq!(report.error().as_deref(), Some("没有可用的注入策略"));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retries_before_falling_back() {
+        // 第三次尝试成功（max_retries = 2）
+        let strategies = [("Mock", false, 2), ("Clipboard", true, 0)];
+        let (inj, calls) = mock_injector(&strategies, |c| c.injection.max_retries = 2);
+        let report = inj.inject("hi", &context("editor"));
+        assert_eq!(report.strategy(), Some("Mock"));
+        assert_eq!(report.fallback_chain(), "Mock → Mock → Mock");
+        assert_eq!(*calls.lock().unwrap(), ["Mock", "Mock", "Mock"]);
+
+        // 重试用尽后回退到下一个策略
+        let strategies = [("Mock", false, 5), ("Clipboard", true, 0)];
+        let (inj, calls) = mock_injector(&strategies, |c| c.injection.max_retries = 1);
+        let report = inj.inject("hi", &context("editor"));
+        assert_eq!(report.strategy(), Some("Clipboard"));
+        assert_eq!(*calls.lock().unwrap(), ["Mock", "Mock", "Clipboard"]);
+    }
+
+    #[test]
+    fn test_verification_refocuses_target() {
+        let strategies = [("Mock", false, 0), ("Clipboard", true, 0)];
+
+        // 焦点两次被别的窗口抢走，第三次检查时回到目标窗口
+        let (backend, activations) = verify_input(&[7, 7, 42], &[]);
+        let (inj, _) = mock_injector_with(&strategies, backend, |c| {
+            c.applications
+                .get_mut("editor")
+                .unwrap()
+                .settings
+                .focus_retry_count = 3;
+        });
+        let report = inj.inject("hi", &context("editor"));
+        assert_eq!(report.strategy(), Some("Mock"));
+        // 注入前激活一次，校验时重新激活两次
+        assert_eq!(*activations.lock().unwrap(), 3);
+
+        // 重新激活次数用尽后换下一个策略
+        let (backend, _) = verify_input(&[7], &[]);
+        let (inj, calls) = mock_injector_with(&strategies, backend, |c| {
+            c.applications
+                .get_mut("editor")
+                .unwrap()
+                .settings
+                .focus_retry_count = 1;
+        });
+        let report = inj.inject("hi", &context("editor"));
+        assert!(!report.succeeded());
+        assert_eq!(report.fallback_chain(), "Mock → Clipboard");
+        assert!(
+            report
+                .error()
+                .unwrap()
+                .contains("验证失败: 目标窗口失去焦点")
+        );
+        assert_eq!(*calls.lock().unwrap(), ["Mock", "Clipboard"]);
+    }
+
+    #[test]
+    fn test_verification_reads_back_text() {
+        let strategies = [("Mock", false, 0), ("Clipboard", true, 0)];
+
+        let (backend, _) = verify_input(&[42], &["fn main() {\r\n}", "fn main() {\r\n    hi\r\n}"]);
+        let (inj, calls) = mock_injector_with(&strategies, backend, |_| {});
+        assert!(inj.inject("hi\n}", &context("editor")).succeeded());
+        assert_eq!(*calls.lock().unwrap(), ["Mock"]);
+
+        // 控件改写了注入的文本：内容有变化就不再用下一个策略重复输入
+        let (backend, _) = verify_input(&[42], &["", "hi()"]);
+        let (inj, calls) = mock_injector_with(&strategies, backend, |_| {});
+        assert_eq!(
+            inj.inject("hi(", &context("editor")).strategy(),
+            Some("Mock")
+        );
+        assert_eq!(*calls.lock().unwrap(), ["Mock"]);
+
+        // 控件文本没有变化：注入未生效，换下一个策略
+        let (backend, _) = verify_input(&[42], &["unrelated"]);
+        let (inj, calls) = mock_injector_with(&strategies, backend, |_| {});
+        let report = inj.inject("hi", &context("editor"));
+        assert_eq!(
+            report.error().as_deref(),
+            Some(
+                "Mock: 验证失败: 回读的控件文本没有变化; \
+                 Clipboard: 验证失败: 回读的控件文本没有变化"
+            )
+        );
+        assert_eq!(*calls.lock().unwrap(), ["Mock", "Clipboard"]);
+
+        // 富文本不做回读比较
+        let (backend, _) = verify_input(&[42], &["unrelated"]);
+        let (inj, _) = mock_injector_with(&strategies, backend, |_| {});
+        let rich = FormattedText::from_content("**hi**", Some("markdown"));
+        assert!(inj.inject_formatted(&rich, &context("editor")).succeeded());
+
+        // verify_injection = false 时跳过校验
+        let (backend, _) = verify_input(&[7], &["unrelated"]);
+        let (inj, _) = mock_injector_with(&strategies, backend, |c| {
+            c.applications
+                .get_mut("editor")
+                .unwrap()
+                .settings
+                .verify_injection = false;
+        });
+        assert!(inj.inject("hi", &context("editor")).succeeded());
+    }
+}
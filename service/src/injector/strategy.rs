@@ -0,0 +1,213 @@
+// Injection Strategies
+// 每个策略代表一种把文本送入目标窗口的方式；Injector 按配置的顺序依次尝试。
+// 目标窗口的激活与 pre_inject_delay 由 Injector 负责，策略只做实际的输入动作。
+
+use super::{InjectionContext, InputBackend, TypingEngine};
+use crate::config::{InjectionConfig, TypingConfig};
+use crate::richtext::FormattedText;
+use std::collections::HashMap;
+use std::result::Result as StdResult;
+use std::time::Duration;
+
+pub trait InjectionStrategy: Send + Sync {
+    /// 显示与记录用的名称（如 "Clipboard"）；配置中按不区分大小写匹配
+    fn name(&self) -> &'static str;
+
+    /// 是否会改写剪贴板；injection.allow_clipboard = false 时跳过此类策略
+    fn uses_clipboard(&self) -> bool {
+        false
+    }
+
+    /// content.plain 总是可用；能携带格式的策略可以使用 content.html / content.rtf
+    fn inject(
+        &self,
+        backend: &dyn InputBackend,
+        content: &FormattedText,
+        context: &InjectionContext,
+    ) -> StdResult<(), Box<dyn std::error::Error>>;
+}
+
+/// 写入剪贴板（富文本提示词同时写入 HTML / RTF）后模拟 Ctrl+V，完成后恢复注入前剪贴板中的全部格式
+pub struct ClipboardStrategy {
+    /// 粘贴期间剪贴板被其他程序（或用户）改写时不再恢复，以免覆盖新内容
+    pub skip_restore_if_changed: bool,
+}
+
+impl Default for ClipboardStrategy {
+    fn default() -> Self {
+        ClipboardStrategy {
+            skip_restore_if_changed: true,
+        }
+    }
+}
+
+impl InjectionStrategy for ClipboardStrategy {
+    fn name(&self) -> &'static str {
+        "Clipboard"
+    }
+
+    fn uses_clipboard(&self) -> bool {
+        true
+    }
+
+    fn inject(
+        &self,
+        backend: &dyn InputBackend,
+        content: &FormattedText,
+        _context: &InjectionContext,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        log::debug!(
+            "Attempting clipboard injection (rich text: {})",
+            content.is_rich()
+        );
+        let clipboard = backend.clipboard();
+
+        // 1) 对现有剪贴板的全部格式做快照，用于注入后恢复；快照失败时不恢复（避免清空用户数据）
+        let saved = match clipboard.snapshot() {
+            Ok(snapshot) => {
+                log::debug!(
+                    "Clipboard snapshot: {} formats, {} bytes",
+                    snapshot.items.len(),
+                    snapshot.size()
+                );
+                Some(snapshot)
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to snapshot clipboard, it will not be restored: {}",
+                    e
+                );
+                None
+            }
+        };
+
+        // 2) 设置我们的文本到剪贴板（带有让剪贴板历史忽略的标记）
+        clipboard.write_transient(content)?;
+        let marker = clipboard.change_marker();
+
+        // 3) 等待一下，确保热键修饰键已释放，然后模拟 Ctrl+V 粘贴
+        std::thread::sleep(Duration::from_millis(200));
+        let pasted = backend.send_paste();
+
+        // 4) 粘贴后稍等再恢复剪贴板（避免覆盖目标应用读取）；
+        //    粘贴失败也要恢复，否则重试时会把我们的文本当成原剪贴板
+        std::thread::sleep(Duration::from_millis(100));
+        if let Some(saved) = saved {
+            if self.skip_restore_if_changed
+                && marker.is_some()
+                && clipboard.change_marker() != marker
+            {
+                log::info!("Clipboard changed during injection, not restoring previous contents");
+            } else if let Err(e) = clipboard.restore(&saved) {
+                log::warn!("Failed to restore clipboard: {}", e);
+            }
+        }
+
+        pasted?;
+        log::info!("Text injected via Clipboard paste");
+        Ok(())
+    }
+}
+
+/// 模拟键入（用于拦截粘贴的应用）；富文本提示词只能键入纯文本
+pub struct SendInputStrategy {
+    pub typing: TypingEngine,
+}
+
+impl Default for SendInputStrategy {
+    fn default() -> Self {
+        SendInputStrategy {
+            typing: TypingEngine::new(TypingConfig::default()),
+        }
+    }
+}
+
+impl InjectionStrategy for SendInputStrategy {
+    fn name(&self) -> &'static str {
+        "SendInput"
+    }
+
+    fn inject(
+        &self,
+        backend: &dyn InputBackend,
+        content: &FormattedText,
+        context: &InjectionContext,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        log::debug!("Attempting SendInput injection");
+        log::debug!("Simulating typing: '{}'", content.plain);
+        // 小延时，避免与热键修饰键冲突或焦点切换未完成
+        std::thread::sleep(Duration::from_millis(80));
+        self.typing
+            .type_text(backend, &content.plain, &context.app_name)
+    }
+}
+
+/// 注入策略注册表，按名称（不区分大小写）查找
+pub struct StrategyRegistry {
+    strategies: HashMap<String, Box<dyn InjectionStrategy>>,
+}
+
+impl Default for StrategyRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl StrategyRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        StrategyRegistry {
+            strategies: HashMap::new(),
+        }
+    }
+
+    /// 创建包含内置策略（clipboard、sendinput）的注册表
+    pub fn with_builtins() -> Self {
+        Self::for_config(&InjectionConfig::default())
+    }
+
+    /// 按注入配置创建内置策略
+    pub fn for_config(config: &InjectionConfig) -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(ClipboardStrategy {
+            skip_restore_if_changed: config.skip_restore_if_changed,
+        }));
+        registry.register(Box::new(SendInputStrategy {
+            typing: TypingEngine::new(config.typing.clone()),
+        }));
+        registry
+    }
+
+    /// 注册（或覆盖）一个策略
+    pub fn register(&mut self, strategy: Box<dyn InjectionStrategy>) {
+        self.strategies
+            .insert(strategy.name().to_lowercase(), strategy);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn InjectionStrategy> {
+        self.strategies
+            .get(&name.to_lowercase())
+            .map(|s| s.as_ref())
+    }
+
+    /// 将配置中的策略名称解析为策略链：跳过未注册（如已废弃的 uia）与重复的名称，
+    /// allow_clipboard = false 时跳过会改写剪贴板的策略
+    pub fn resolve(&self, names: &[String], allow_clipboard: bool) -> Vec<&dyn InjectionStrategy> {
+        let mut chain: Vec<&dyn InjectionStrategy> = Vec::new();
+        for name in names {
+            let Some(strategy) = self.get(name) else {
+                log::debug!("Skipping unknown injection strategy '{}'", name);
+                continue;
+            };
+            if !allow_clipboard && strategy.uses_clipboard() {
+                log::debug!("Skipping '{}': clipboard injection is disabled", name);
+                continue;
+            }
+            if chain.iter().any(|s| s.name() == strategy.name()) {
+                continue;
+            }
+            chain.push(strategy);
+        }
+        chain
+    }
+}
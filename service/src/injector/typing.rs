@@ -0,0 +1,252 @@
+// Typing Engine
+// 把文本转换为平台无关的键盘事件，按 typing.chunk_size 分批、按 typing.chars_per_second 限速，
+// 交给 InputBackend::send_keys 发送。事件生成不依赖真实输入，可以直接测试。
+
+use super::InputBackend;
+use crate::config::{NewlineKey, TypingConfig};
+use std::result::Result as StdResult;
+use std::time::Duration;
+
+/// 键入时使用的具名按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedKey {
+    Enter,
+    Tab,
+    Backspace,
+    Shift,
+    Control,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypingEvent {
+    /// 输入一个完整字符（后端负责按下与释放；UTF-16 代理对在同一批中一起发送）
+    Char(char),
+    KeyDown(NamedKey),
+    KeyUp(NamedKey),
+}
+
+/// 已发送部分文本后键入失败。重试或换用其他策略会把已键入的部分再输入一遍，
+/// 注入器遇到此错误时直接报告失败
+#[derive(Debug)]
+pub struct PartiallyTyped {
+    /// 已发送的字符数（换行、Tab 各算一个）
+    pub typed_chars: usize,
+    pub source: Box<dyn std::error::Error>,
+}
+
+impl std::fmt::Display for PartiallyTyped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "已键入 {} 个字符后失败: {}",
+            self.typed_chars, self.source
+        )
+    }
+}
+
+impl std::error::Error for PartiallyTyped {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+pub struct TypingEngine {
+    config: TypingConfig,
+}
+
+impl TypingEngine {
+    pub fn new(config: TypingConfig) -> Self {
+        TypingEngine { config }
+    }
+
+    /// 把文本转换为键盘事件并分批：每批最多 chunk_size 个字符，换行等组合键不会被拆到两批中
+    pub fn plan(&self, text: &str, app_name: &str) -> Vec<Vec<TypingEvent>> {
+        let newline = self.config.newline_for(app_name);
+        let chunk_size = self.config.chunk_size.max(1);
+        let mut chunks: Vec<Vec<TypingEvent>> = Vec::new();
+        let mut current = Vec::new();
+        let mut chars_in_chunk = 0;
+
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let events = match c {
+                // "\r\n" 与单独的 "\r" 都视为一次换行
+                '\r' => {
+                    chars.next_if_eq(&'\n');
+                    newline_events(newline)
+                }
+                '\n' => newline_events(newline),
+                '\t' => tap(NamedKey::Tab),
+                c => vec![TypingEvent::Char(c)],
+            };
+            if chars_in_chunk == chunk_size {
+                chunks.push(std::mem::take(&mut current));
+                chars_in_chunk = 0;
+            }
+            current.extend(events);
+            chars_in_chunk += 1;
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+        chunks
+    }
+
+    /// 发送完一批后的等待时间，使平均速度不超过 chars_per_second
+    pub fn chunk_delay(&self, chunk: &[TypingEvent]) -> Duration {
+        if self.config.chars_per_second == 0 {
+            return Duration::ZERO;
+        }
+        let chars = chunk_chars(chunk) as u64;
+        Duration::from_millis(chars * 1000 / self.config.chars_per_second as u64)
+    }
+
+    /// 分批键入文本；disable_ime 时键入期间关闭目标窗口的输入法，结束后恢复。
+    /// 第一批之后的发送失败返回 PartiallyTyped
+    pub fn type_text(
+        &self,
+        backend: &dyn InputBackend,
+        text: &str,
+        app_name: &str,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        let chunks = self.plan(text, app_name);
+        let ime_closed = self.config.disable_ime && backend.close_ime();
+        if ime_closed {
+            log::debug!("Closed the target window's IME while typing");
+        }
+
+        let mut result = Ok(());
+        let mut typed_chars = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            if let Err(e) = backend.send_keys(chunk) {
+                result = if typed_chars > 0 {
+                    Err(Box::new(PartiallyTyped {
+                        typed_chars,
+                        source: e,
+                    }) as Box<dyn std::error::Error>)
+                } else {
+                    Err(e)
+                };
+                break;
+            }
+            typed_chars += chunk_chars(chunk);
+            if i + 1 < chunks.len() {
+                std::thread::sleep(self.chunk_delay(chunk));
+            }
+        }
+
+        if ime_closed {
+            backend.open_ime();
+        }
+        result
+    }
+}
+
+/// 一批事件输入的字符数：每个字符与每次换行、Tab 各算一个，修饰键不计
+fn chunk_chars(chunk: &[TypingEvent]) -> usize {
+    chunk
+        .iter()
+        .filter(|e| !matches!(e, TypingEvent::KeyUp(_)))
+        .filter(|e| !matches!(e, TypingEvent::KeyDown(NamedKey::Shift | NamedKey::Control)))
+        .count()
+}
+
+/// 删除光标前 count 个字符的按键事件
+pub fn backspaces(count: usize) -> Vec<TypingEvent> {
+    (0..count).flat_map(|_| tap(NamedKey::Backspace)).collect()
+}
+
+fn tap(key: NamedKey) -> Vec<TypingEvent> {
+    vec![TypingEvent::KeyDown(key), TypingEvent::KeyUp(key)]
+}
+
+fn newline_events(newline: NewlineKey) -> Vec<TypingEvent> {
+    let modifier = match newline {
+        NewlineKey::Enter => return tap(NamedKey::Enter),
+        NewlineKey::ShiftEnter => NamedKey::Shift,
+        NewlineKey::CtrlEnter => NamedKey::Control,
+    };
+    vec![
+        TypingEvent::KeyDown(modifier),
+        TypingEvent::KeyDown(NamedKey::Enter),
+        TypingEvent::KeyUp(NamedKey::Enter),
+        TypingEvent::KeyUp(modifier),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypingEvent::{Char, KeyDown, KeyUp};
+    use super::*;
+
+    fn engine(chunk_size: usize, chars_per_second: u32) -> TypingEngine {
+        TypingEngine::new(TypingConfig {
+            chunk_size,
+            chars_per_second,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_newline_and_tab_keys() {
+        let engine = engine(32, 0);
+        assert_eq!(
+            engine.plan("a\r\nb\tc\rd", "notes"),
+            vec![vec![
+                Char('a'),
+                KeyDown(NamedKey::Enter),
+                KeyUp(NamedKey::Enter),
+                Char('b'),
+                KeyDown(NamedKey::Tab),
+                KeyUp(NamedKey::Tab),
+                Char('c'),
+                KeyDown(NamedKey::Enter),
+                KeyUp(NamedKey::Enter),
+                Char('d'),
+            ]]
+        );
+
+        // 聊天应用默认使用 Shift+Enter（进程名不区分大小写）
+        assert_eq!(
+            engine.plan("\n", "Slack.exe"),
+            vec![vec![
+                KeyDown(NamedKey::Shift),
+                KeyDown(NamedKey::Enter),
+                KeyUp(NamedKey::Enter),
+                KeyUp(NamedKey::Shift),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_chunks_keep_characters_whole() {
+        let engine = engine(2, 0);
+        // emoji 在 UTF-16 中是代理对，但作为一个字符计数且不会被拆开
+        assert_eq!(
+            engine.plan("a😀b\nc", "notes"),
+            vec![
+                vec![Char('a'), Char('😀')],
+                vec![Char('b'), KeyDown(NamedKey::Enter), KeyUp(NamedKey::Enter)],
+                vec![Char('c')],
+            ]
+        );
+        assert!(engine.plan("", "notes").is_empty());
+    }
+
+    #[test]
+    fn test_chunk_delay_throttles_rate() {
+        let chunk = [
+            Char('a'),
+            KeyDown(NamedKey::Shift),
+            KeyDown(NamedKey::Enter),
+            KeyUp(NamedKey::Enter),
+            KeyUp(NamedKey::Shift),
+        ];
+        // 两个字符（a 与换行）按 100 字符/秒需要 20ms
+        assert_eq!(
+            engine(32, 100).chunk_delay(&chunk),
+            Duration::from_millis(20)
+        );
+        assert_eq!(engine(32, 0).chunk_delay(&chunk), Duration::ZERO);
+    }
+}
@@ -0,0 +1,421 @@
+// Win32 input backend: SetForegroundWindow, clipboard (all HGLOBAL formats), SendInput,
+// WM_GETTEXT read-back for standard Edit / RichEdit controls
+
+use super::clipboard::{Clipboard, ClipboardSnapshot, MAX_SNAPSHOT_BYTES};
+use super::{InputBackend, MAX_CLIPBOARD_SIZE, NamedKey, TypingEvent};
+use crate::context::WindowHandle;
+use crate::richtext::{self, FormattedText};
+use std::result::Result as StdResult;
+use std::time::Duration;
+use windows::{
+    Win32::Foundation::*, Win32::System::DataExchange::*, Win32::System::Memory::*,
+    Win32::UI::Input::Ime::ImmGetDefaultIMEWnd, Win32::UI::Input::KeyboardAndMouse::*,
+    Win32::UI::WindowsAndMessaging::*, core::PCWSTR,
+};
+
+// windows 0.58 下方便使用的常量（CF_UNICODETEXT = 13）
+const CF_UNICODETEXT_CONST: u32 = 13;
+
+/// WM_IME_CONTROL 的子命令（imm.h）
+const IMC_GETOPENSTATUS: usize = 0x0005;
+const IMC_SETOPENSTATUS: usize = 0x0006;
+
+/// 富文本使用的注册格式名
+const HTML_FORMAT: &str = "HTML Format";
+const RTF_FORMAT: &str = "Rich Text Format";
+
+/// GDI 句柄类格式（CF_BITMAP、CF_METAFILEPICT、CF_PALETTE、CF_ENHMETAFILE、CF_OWNERDISPLAY
+/// 及 CF_DSP* 变体）不是 HGLOBAL，无法按字节复制；位图仍通过 CF_DIB / CF_DIBV5 保留
+const HANDLE_FORMATS: [u32; 8] = [2, 3, 9, 14, 0x80, 0x82, 0x83, 0x8E];
+
+/// 临时内容附带的格式：Windows 剪贴板历史、云剪贴板与常见剪贴板管理器会忽略带有这些格式的内容
+const HISTORY_EXCLUSION_FORMATS: [(&str, &[u8]); 4] = [
+    ("ExcludeClipboardContentFromMonitorProcessing", &[0]),
+    ("CanIncludeInClipboardHistory", &[0, 0, 0, 0]),
+    ("CanUploadToCloudClipboard", &[0, 0, 0, 0]),
+    ("Clipboard Viewer Ignore", &[0]),
+];
+
+pub struct Win32Input;
+
+/// 系统剪贴板：快照时复制所有 HGLOBAL 格式，格式名使用注册名或 "#<id>"
+pub struct Win32Clipboard;
+
+fn to_hwnd(handle: WindowHandle) -> HWND {
+    HWND(handle.0 as usize as *mut core::ffi::c_void)
+}
+
+/// 已打开的剪贴板，离开作用域时关闭
+struct OpenClipboardGuard;
+
+impl Drop for OpenClipboardGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseClipboard();
+        }
+    }
+}
+
+// 打开剪贴板，最多尝试 5 次
+fn open_clipboard() -> StdResult<OpenClipboardGuard, Box<dyn std::error::Error>> {
+    for _ in 0..5 {
+        unsafe {
+            if OpenClipboard(HWND(std::ptr::null_mut())).is_ok() {
+                return Ok(OpenClipboardGuard);
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Err("OpenClipboard failed".into())
+}
+
+fn format_name(format: u32) -> String {
+    let mut name = [0u16; 256];
+    let len = unsafe { GetClipboardFormatNameW(format, &mut name) };
+    if len > 0 {
+        String::from_utf16_lossy(&name[..len as usize])
+    } else {
+        format!("#{}", format)
+    }
+}
+
+fn format_id(name: &str) -> u32 {
+    if let Some(id) = name.strip_prefix('#').and_then(|n| n.parse().ok()) {
+        return id;
+    }
+    let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe { RegisterClipboardFormatW(PCWSTR(wide.as_ptr())) }
+}
+
+/// 复制某个格式的 HGLOBAL 数据（剪贴板需已打开）；超过 limit 字节时跳过
+fn clipboard_bytes(format: u32, limit: usize) -> Option<Vec<u8>> {
+    unsafe {
+        let handle = GetClipboardData(format).ok()?;
+        let hg = HGLOBAL(handle.0);
+        let size = GlobalSize(hg);
+        if size > limit {
+            log::warn!(
+                "Clipboard format {} ({} bytes) exceeds the snapshot limit, not preserved",
+                format_name(format),
+                size
+            );
+            return None;
+        }
+        let ptr = GlobalLock(hg) as *const u8;
+        if ptr.is_null() {
+            return None;
+        }
+        let data = std::slice::from_raw_parts(ptr, size).to_vec();
+        let _ = GlobalUnlock(hg);
+        Some(data)
+    }
+}
+
+/// 把 bytes 复制到新分配的全局内存并放入剪贴板（剪贴板需已打开并清空）；成功后内存归剪贴板所有
+fn set_clipboard_bytes(format: u32, bytes: &[u8]) -> StdResult<(), Box<dyn std::error::Error>> {
+    unsafe {
+        let hmem =
+            GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1)).map_err(|_| "GlobalAlloc failed")?;
+        let ptr = GlobalLock(hmem) as *mut u8;
+        if ptr.is_null() {
+            let _ = GlobalFree(hmem);
+            return Err("GlobalLock failed".into());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+        let _ = GlobalUnlock(hmem);
+        if SetClipboardData(format, HANDLE(hmem.0)).is_err() {
+            let _ = GlobalFree(hmem);
+            return Err(format!("SetClipboardData({}) failed", format_name(format)).into());
+        }
+    }
+    Ok(())
+}
+
+/// 只有标准编辑控件的 WM_GETTEXT 返回的是正文（其他控件通常返回空或标题）
+fn is_edit_control(hwnd: HWND) -> bool {
+    let mut class = [0u16; 64];
+    let len = unsafe { GetClassNameW(hwnd, &mut class) };
+    if len <= 0 {
+        return false;
+    }
+    let class = String::from_utf16_lossy(&class[..len as usize]).to_lowercase();
+    class == "edit" || class.starts_with("richedit")
+}
+
+/// 发送消息给可能无响应的控件，最多等待 200ms
+fn send_message_timeout(hwnd: HWND, msg: u32, wparam: usize, lparam: isize) -> Option<usize> {
+    let mut result = 0usize;
+    let ok = unsafe {
+        SendMessageTimeoutW(
+            hwnd,
+            msg,
+            WPARAM(wparam),
+            LPARAM(lparam),
+            SMTO_ABORTIFHUNG,
+            200,
+            Some(&mut result),
+        )
+    };
+    (ok.0 != 0).then_some(result)
+}
+
+fn key_input(vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+fn named_vk(key: NamedKey) -> VIRTUAL_KEY {
+    match key {
+        NamedKey::Enter => VK_RETURN,
+        NamedKey::Tab => VK_TAB,
+        NamedKey::Backspace => VK_BACK,
+        NamedKey::Shift => VK_SHIFT,
+        NamedKey::Control => VK_CONTROL,
+    }
+}
+
+/// 前台窗口所属线程的默认输入法窗口（通过它可以跨进程查询与切换输入法开关）
+fn foreground_ime_window() -> Option<HWND> {
+    unsafe {
+        let foreground = GetForegroundWindow();
+        if foreground.0.is_null() {
+            return None;
+        }
+        let ime = ImmGetDefaultIMEWnd(foreground);
+        (!ime.0.is_null()).then_some(ime)
+    }
+}
+
+impl InputBackend for Win32Input {
+    fn activate_window(&self, window: WindowHandle) {
+        unsafe {
+            let _ = SetForegroundWindow(to_hwnd(window));
+        }
+    }
+
+    fn clipboard(&self) -> &dyn Clipboard {
+        &Win32Clipboard
+    }
+
+    fn send_paste(&self) -> StdResult<(), Box<dyn std::error::Error>> {
+        let v = VIRTUAL_KEY(0x56);
+        let mut inputs = [
+            key_input(VK_CONTROL, 0, KEYBD_EVENT_FLAGS(0)),
+            key_input(v, 0, KEYBD_EVENT_FLAGS(0)),
+            key_input(v, 0, KEYEVENTF_KEYUP),
+            key_input(VK_CONTROL, 0, KEYEVENTF_KEYUP),
+        ];
+        unsafe {
+            if SendInput(&mut inputs, std::mem::size_of::<INPUT>() as i32) == 0 {
+                return Err("SendInput Ctrl+V failed".into());
+            }
+        }
+        Ok(())
+    }
+
+    fn send_keys(&self, events: &[TypingEvent]) -> StdResult<(), Box<dyn std::error::Error>> {
+        let mut inputs = Vec::with_capacity(events.len() * 2);
+        for event in events {
+            match *event {
+                TypingEvent::Char(c) => {
+                    // 代理对的两个码元先依次按下再释放，保证在同一次 SendInput 中成对到达
+                    let mut buf = [0u16; 2];
+                    let units = c.encode_utf16(&mut buf);
+                    for &unit in units.iter() {
+                        inputs.push(key_input(VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE));
+                    }
+                    for &unit in units.iter() {
+                        inputs.push(key_input(
+                            VIRTUAL_KEY(0),
+                            unit,
+                            KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
+                        ));
+                    }
+                }
+                TypingEvent::KeyDown(key) => {
+                    inputs.push(key_input(named_vk(key), 0, KEYBD_EVENT_FLAGS(0)))
+                }
+                TypingEvent::KeyUp(key) => {
+                    inputs.push(key_input(named_vk(key), 0, KEYEVENTF_KEYUP))
+                }
+            }
+        }
+        if inputs.is_empty() {
+            return Ok(());
+        }
+
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if (sent as usize) < inputs.len() {
+            let err = unsafe { GetLastError() };
+            log::error!("SendInput failed with error: {:?}", err);
+            return Err(format!("SendInput sent {} of {} events", sent, inputs.len()).into());
+        }
+        Ok(())
+    }
+
+    fn close_ime(&self) -> bool {
+        let Some(ime) = foreground_ime_window() else {
+            return false;
+        };
+        let open = send_message_timeout(ime, WM_IME_CONTROL, IMC_GETOPENSTATUS, 0);
+        if open.is_none_or(|open| open == 0) {
+            return false;
+        }
+        send_message_timeout(ime, WM_IME_CONTROL, IMC_SETOPENSTATUS, 0).is_some()
+    }
+
+    fn open_ime(&self) {
+        if let Some(ime) = foreground_ime_window() {
+            let _ = send_message_timeout(ime, WM_IME_CONTROL, IMC_SETOPENSTATUS, 1);
+        }
+    }
+
+    fn focused_window(&self) -> Option<WindowHandle> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        (!hwnd.0.is_null()).then(|| WindowHandle(hwnd.0 as usize as u64))
+    }
+
+    fn read_focused_text(&self) -> Option<String> {
+        unsafe {
+            let foreground = GetForegroundWindow();
+            if foreground.0.is_null() {
+                return None;
+            }
+            let thread_id = GetWindowThreadProcessId(foreground, None);
+            let mut info = GUITHREADINFO {
+                cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+                ..Default::default()
+            };
+            GetGUIThreadInfo(thread_id, &mut info).ok()?;
+            let focus = info.hwndFocus;
+            if focus.0.is_null() || !is_edit_control(focus) {
+                return None;
+            }
+
+            let len = send_message_timeout(focus, WM_GETTEXTLENGTH, 0, 0)?.min(MAX_CLIPBOARD_SIZE);
+            let mut buffer = vec![0u16; len + 1];
+            let copied = send_message_timeout(
+                focus,
+                WM_GETTEXT,
+                buffer.len(),
+                buffer.as_mut_ptr() as isize,
+            )?;
+            Some(String::from_utf16_lossy(&buffer[..copied.min(len)]))
+        }
+    }
+}
+
+impl Clipboard for Win32Clipboard {
+    fn read_text(&self) -> Option<String> {
+        let _clipboard = open_clipboard().ok()?;
+        let mut text = None;
+        unsafe {
+            if IsClipboardFormatAvailable(CF_UNICODETEXT_CONST).is_ok()
+                && let Ok(h) = GetClipboardData(CF_UNICODETEXT_CONST)
+            {
+                let hg = HGLOBAL(h.0);
+                let ptr = GlobalLock(hg) as *const u16;
+                if !ptr.is_null() {
+                    let mut len = 0usize;
+                    while len < MAX_CLIPBOARD_SIZE && *ptr.add(len) != 0 {
+                        len += 1;
+                    }
+                    if len >= MAX_CLIPBOARD_SIZE {
+                        log::warn!(
+                            "Clipboard text exceeds max size ({}), truncating",
+                            MAX_CLIPBOARD_SIZE
+                        );
+                    }
+                    let units = std::slice::from_raw_parts(ptr, len);
+                    text = Some(String::from_utf16_lossy(units));
+                    let _ = GlobalUnlock(hg);
+                }
+            }
+        }
+        text
+    }
+
+    fn write_transient(
+        &self,
+        content: &FormattedText,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        let _clipboard = open_clipboard()?;
+        unsafe {
+            let _ = EmptyClipboard();
+        }
+        let utf16: Vec<u8> = content
+            .plain
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_ne_bytes)
+            .collect();
+        set_clipboard_bytes(CF_UNICODETEXT_CONST, &utf16)?;
+        // CF_HTML 与 RTF 均为以 NUL 结尾的 8 位文本；失败时仍保留纯文本
+        let rich = [
+            (HTML_FORMAT, content.html.as_deref().map(richtext::cf_html)),
+            (RTF_FORMAT, content.rtf.clone()),
+        ];
+        for (name, data) in rich {
+            if let Some(data) = data {
+                let mut bytes = data.into_bytes();
+                bytes.push(0);
+                if let Err(e) = set_clipboard_bytes(format_id(name), &bytes) {
+                    log::warn!("Failed to put {} on the clipboard: {}", name, e);
+                }
+            }
+        }
+        for (name, data) in HISTORY_EXCLUSION_FORMATS {
+            if let Err(e) = set_clipboard_bytes(format_id(name), data) {
+                log::debug!("Failed to tag clipboard with {}: {}", name, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> StdResult<ClipboardSnapshot, Box<dyn std::error::Error>> {
+        let _clipboard = open_clipboard()?;
+        let mut snapshot = ClipboardSnapshot::default();
+        let mut format = 0;
+        loop {
+            format = unsafe { EnumClipboardFormats(format) };
+            if format == 0 {
+                break;
+            }
+            if HANDLE_FORMATS.contains(&format) {
+                continue;
+            }
+            let remaining = MAX_SNAPSHOT_BYTES.saturating_sub(snapshot.size());
+            if let Some(data) = clipboard_bytes(format, remaining) {
+                snapshot.push_within_limit(format_name(format), data);
+            }
+        }
+        Ok(snapshot)
+    }
+
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> StdResult<(), Box<dyn std::error::Error>> {
+        let _clipboard = open_clipboard()?;
+        unsafe {
+            EmptyClipboard()?;
+        }
+        for item in &snapshot.items {
+            if let Err(e) = set_clipboard_bytes(format_id(&item.format), &item.data) {
+                log::warn!("Failed to restore clipboard format {}: {}", item.format, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn change_marker(&self) -> Option<u64> {
+        Some(unsafe { GetClipboardSequenceNumber() } as u64)
+    }
+}
@@ -0,0 +1,651 @@
+// X11 input backend: _NET_ACTIVE_WINDOW, CLIPBOARD selection ownership, XTest key events
+
+use super::clipboard::{Clipboard, ClipboardSnapshot, MAX_SNAPSHOT_BYTES};
+use super::{InputBackend, MAX_CLIPBOARD_SIZE, NamedKey, TypingEvent};
+use crate::context::WindowHandle;
+use crate::richtext::FormattedText;
+use std::result::Result as StdResult;
+use std::time::{Duration, Instant};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask, InputFocus,
+    KEY_PRESS_EVENT, KEY_RELEASE_EVENT, Keycode, PropMode, Property, SELECTION_NOTIFY_EVENT,
+    SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        TARGETS,
+        UTF8_STRING,
+        TEXT,
+        COMPOUND_TEXT,
+        INCR,
+        MULTIPLE,
+        TIMESTAMP,
+        SAVE_TARGETS,
+        DELETE,
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        TEXT_HTML: b"text/html",
+        TEXT_RTF: b"text/rtf",
+        APPLICATION_RTF: b"application/rtf",
+        // KDE 约定：剪贴板历史管理器（Klipper 等）忽略带有此 target 的内容
+        PASSWORD_MANAGER_HINT: b"x-kde-passwordManagerHint",
+        _NET_ACTIVE_WINDOW,
+        PROMPTKEY_SELECTION,
+    }
+}
+
+// 常用 keysym（见 X11/keysymdef.h）
+const XK_CONTROL_L: u32 = 0xffe3;
+const XK_SHIFT_L: u32 = 0xffe1;
+const XK_V: u32 = 0x0076;
+const XK_RETURN: u32 = 0xff0d;
+const XK_TAB: u32 = 0xff09;
+const XK_BACKSPACE: u32 = 0xff08;
+
+/// 等待剪贴板所有者响应的最长时间
+const SELECTION_TIMEOUT: Duration = Duration::from_millis(500);
+/// 临时改写 keycode 映射后等待客户端刷新键盘映射的时间
+const REMAP_SETTLE: Duration = Duration::from_millis(10);
+
+pub struct X11Input;
+
+/// CLIPBOARD selection：读取时作为 requestor 逐个转换 target，写入时作为 owner 在后台线程应答
+pub struct X11Clipboard;
+
+fn connect() -> StdResult<(RustConnection, Window, Atoms), Box<dyn std::error::Error>> {
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let root = conn.setup().roots[screen_num].root;
+    let atoms = Atoms::new(&conn)?.reply()?;
+    Ok((conn, root, atoms))
+}
+
+// 不可见的辅助窗口，用于收发 selection 事件
+fn helper_window(
+    conn: &RustConnection,
+    root: Window,
+) -> StdResult<Window, Box<dyn std::error::Error>> {
+    let win = conn.generate_id()?;
+    conn.create_window(
+        0,
+        win,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        0,
+        // PropertyNotify 用于 INCR 分段读取
+        &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+    )?;
+    Ok(win)
+}
+
+/// 当前键盘映射
+struct Keymap {
+    min_keycode: Keycode,
+    per_keycode: usize,
+    keysyms: Vec<u32>,
+}
+
+impl Keymap {
+    fn load(conn: &RustConnection) -> StdResult<Self, Box<dyn std::error::Error>> {
+        let setup = conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let reply = conn.get_keyboard_mapping(min, max - min + 1)?.reply()?;
+        Ok(Keymap {
+            min_keycode: min,
+            per_keycode: reply.keysyms_per_keycode.max(1) as usize,
+            keysyms: reply.keysyms,
+        })
+    }
+
+    fn keycodes(&self) -> impl DoubleEndedIterator<Item = (Keycode, &[u32])> {
+        self.keysyms
+            .chunks(self.per_keycode)
+            .enumerate()
+            .map(|(i, syms)| (self.min_keycode + i as u8, syms))
+    }
+
+    /// 不按 Shift 即可输入该 keysym 的 keycode
+    fn unshifted(&self, keysym: u32) -> Option<Keycode> {
+        self.keycodes()
+            .find(|(_, syms)| syms.first() == Some(&keysym))
+            .map(|(code, _)| code)
+    }
+
+    /// 没有绑定任何 keysym 的 keycode，可临时借用
+    fn spare(&self) -> Option<Keycode> {
+        self.keycodes()
+            .rev()
+            .find(|(_, syms)| syms.iter().all(|s| *s == 0))
+            .map(|(code, _)| code)
+    }
+}
+
+fn keysym_for(c: char) -> Option<u32> {
+    match c {
+        '\r' => None,
+        '\n' => Some(XK_RETURN),
+        '\t' => Some(XK_TAB),
+        // Latin-1 可打印字符的 keysym 与码位相同
+        ' '..='~' | '\u{a0}'..='\u{ff}' => Some(c as u32),
+        c => Some(0x0100_0000 | c as u32),
+    }
+}
+
+fn named_keysym(key: NamedKey) -> u32 {
+    match key {
+        NamedKey::Enter => XK_RETURN,
+        NamedKey::Tab => XK_TAB,
+        NamedKey::Backspace => XK_BACKSPACE,
+        NamedKey::Shift => XK_SHIFT_L,
+        NamedKey::Control => XK_CONTROL_L,
+    }
+}
+
+fn tap(
+    conn: &RustConnection,
+    root: Window,
+    keycode: Keycode,
+) -> StdResult<(), Box<dyn std::error::Error>> {
+    conn.xtest_fake_input(KEY_PRESS_EVENT, keycode, x11rb::CURRENT_TIME, root, 0, 0, 0)?;
+    conn.xtest_fake_input(
+        KEY_RELEASE_EVENT,
+        keycode,
+        x11rb::CURRENT_TIME,
+        root,
+        0,
+        0,
+        0,
+    )?;
+    Ok(())
+}
+
+impl InputBackend for X11Input {
+    fn activate_window(&self, window: WindowHandle) {
+        if window.is_null() {
+            return;
+        }
+        let result = (|| -> StdResult<(), Box<dyn std::error::Error>> {
+            let (conn, root, atoms) = connect()?;
+            let win = window.0 as Window;
+            // 有窗口管理器时由其切换焦点（source = 2 表示来自 pager/工具）
+            let event = ClientMessageEvent::new(
+                32,
+                win,
+                atoms._NET_ACTIVE_WINDOW,
+                [2, x11rb::CURRENT_TIME, 0, 0, 0],
+            );
+            conn.send_event(
+                false,
+                root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )?;
+            // 没有窗口管理器时直接设置输入焦点
+            conn.set_input_focus(InputFocus::PARENT, win, x11rb::CURRENT_TIME)?;
+            conn.sync()?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            log::warn!("Failed to activate window 0x{:x}: {}", window.0, e);
+        }
+    }
+
+    fn focused_window(&self) -> Option<WindowHandle> {
+        let (conn, root, atoms) = connect().ok()?;
+        let reply = conn
+            .get_property(
+                false,
+                root,
+                atoms._NET_ACTIVE_WINDOW,
+                AtomEnum::WINDOW,
+                0,
+                1,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        match reply.value32().and_then(|mut v| v.next()) {
+            Some(win) if win != x11rb::NONE => Some(WindowHandle(win as u64)),
+            _ => {
+                // 没有窗口管理器时退回到输入焦点（0 = None，1 = PointerRoot）
+                let focus = conn.get_input_focus().ok()?.reply().ok()?.focus;
+                (focus > 1).then_some(WindowHandle(focus as u64))
+            }
+        }
+    }
+
+    // read_focused_text：X11 没有通用的控件文本接口（需要 AT-SPI），不做内容校验
+
+    fn clipboard(&self) -> &dyn Clipboard {
+        &X11Clipboard
+    }
+
+    fn send_paste(&self) -> StdResult<(), Box<dyn std::error::Error>> {
+        let (conn, root, _) = connect()?;
+        let keymap = Keymap::load(&conn)?;
+        let ctrl = keymap
+            .unshifted(XK_CONTROL_L)
+            .ok_or("No keycode for Control_L")?;
+        let v = keymap.unshifted(XK_V).ok_or("No keycode for 'v'")?;
+        conn.xtest_fake_input(KEY_PRESS_EVENT, ctrl, x11rb::CURRENT_TIME, root, 0, 0, 0)?;
+        tap(&conn, root, v)?;
+        conn.xtest_fake_input(KEY_RELEASE_EVENT, ctrl, x11rb::CURRENT_TIME, root, 0, 0, 0)?;
+        conn.sync()?;
+        Ok(())
+    }
+
+    // close_ime / open_ime：X11 下输入法由各客户端的 XIM / IBus 连接管理，无法从外部关闭
+
+    fn send_keys(&self, events: &[TypingEvent]) -> StdResult<(), Box<dyn std::error::Error>> {
+        let (conn, root, _) = connect()?;
+        let keymap = Keymap::load(&conn)?;
+        let spare = keymap.spare();
+        let mut remapped = false;
+
+        for event in events {
+            let keysym = match *event {
+                TypingEvent::Char(c) => match keysym_for(c) {
+                    Some(keysym) => keysym,
+                    None => continue,
+                },
+                TypingEvent::KeyDown(key) | TypingEvent::KeyUp(key) => {
+                    let code = keymap
+                        .unshifted(named_keysym(key))
+                        .ok_or_else(|| format!("No keycode for {:?}", key))?;
+                    let kind = if matches!(event, TypingEvent::KeyDown(_)) {
+                        KEY_PRESS_EVENT
+                    } else {
+                        KEY_RELEASE_EVENT
+                    };
+                    conn.xtest_fake_input(kind, code, x11rb::CURRENT_TIME, root, 0, 0, 0)?;
+                    continue;
+                }
+            };
+            if let Some(code) = keymap.unshifted(keysym) {
+                tap(&conn, root, code)?;
+                continue;
+            }
+            // 布局中没有的字符：临时把空闲 keycode 映射到该 keysym
+            let code = spare.ok_or("No spare keycode available for typing")?;
+            conn.change_keyboard_mapping(
+                1,
+                code,
+                keymap.per_keycode as u8,
+                &vec![keysym; keymap.per_keycode],
+            )?;
+            conn.sync()?;
+            std::thread::sleep(REMAP_SETTLE);
+            tap(&conn, root, code)?;
+            conn.sync()?;
+            std::thread::sleep(REMAP_SETTLE);
+            remapped = true;
+        }
+
+        if remapped && let Some(code) = spare {
+            conn.change_keyboard_mapping(
+                1,
+                code,
+                keymap.per_keycode as u8,
+                &vec![0; keymap.per_keycode],
+            )?;
+        }
+        conn.sync()?;
+        Ok(())
+    }
+}
+
+/// 转换 selection 得到的数据；INCR 分段传输已拼接完整
+struct SelectionData {
+    format: u8,
+    value: Vec<u8>,
+}
+
+/// 等待发给 win 的事件，直到 matches 返回 true 或超时
+fn wait_for(
+    conn: &RustConnection,
+    mut matches: impl FnMut(&Event) -> bool,
+) -> StdResult<Event, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + SELECTION_TIMEOUT;
+    while Instant::now() < deadline {
+        match conn.poll_for_event()? {
+            Some(event) if matches(&event) => return Ok(event),
+            Some(_) => {}
+            None => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+    Err("Timed out waiting for clipboard owner".into())
+}
+
+/// 请求把 CLIPBOARD 转换为 target 并读取结果；所有者拒绝时返回 None。
+/// 数据超过 limit 字节时只返回前 limit + 1 字节，调用方据此判断是否超限
+fn convert_selection(
+    conn: &RustConnection,
+    win: Window,
+    atoms: &Atoms,
+    target: Atom,
+    limit: usize,
+) -> StdResult<Option<SelectionData>, Box<dyn std::error::Error>> {
+    let property = atoms.PROMPTKEY_SELECTION;
+    conn.convert_selection(win, atoms.CLIPBOARD, target, property, x11rb::CURRENT_TIME)?;
+    conn.flush()?;
+
+    let event = wait_for(
+        conn,
+        |e| matches!(e, Event::SelectionNotify(n) if n.requestor == win && n.target == target),
+    )?;
+    if let Event::SelectionNotify(n) = event
+        && n.property == x11rb::NONE
+    {
+        return Ok(None);
+    }
+
+    // long_length 以 4 字节为单位
+    let max_words = (limit / 4 + 1).min(u32::MAX as usize) as u32;
+    let reply = conn
+        .get_property(true, win, property, AtomEnum::ANY, 0, max_words)?
+        .reply()?;
+    if reply.type_ != atoms.INCR {
+        return Ok(Some(SelectionData {
+            format: reply.format,
+            value: reply.value,
+        }));
+    }
+
+    // INCR：删除属性后所有者逐段写入，长度为 0 的一段表示结束
+    let mut data = SelectionData {
+        format: 8,
+        value: Vec::new(),
+    };
+    loop {
+        wait_for(conn, |e| {
+            matches!(e, Event::PropertyNotify(p)
+                if p.window == win && p.atom == property && p.state == Property::NEW_VALUE)
+        })?;
+        let chunk = conn
+            .get_property(true, win, property, AtomEnum::ANY, 0, max_words)?
+            .reply()?;
+        if chunk.value.is_empty() {
+            return Ok(Some(data));
+        }
+        data.format = chunk.format;
+        data.value.extend_from_slice(&chunk.value);
+        if data.value.len() > limit {
+            // 不再读取剩余分段，所有者会自行超时
+            return Ok(Some(data));
+        }
+    }
+}
+
+/// 成为 CLIPBOARD 的所有者，并在后台线程中提供 items 中的各个 target，直到所有权被他人取走
+fn take_ownership(items: Vec<(Atom, Vec<u8>)>) -> StdResult<(), Box<dyn std::error::Error>> {
+    let (conn, root, atoms) = connect()?;
+    let win = helper_window(&conn, root)?;
+    conn.set_selection_owner(win, atoms.CLIPBOARD, x11rb::CURRENT_TIME)?;
+    if conn.get_selection_owner(atoms.CLIPBOARD)?.reply()?.owner != win {
+        return Err("Failed to take CLIPBOARD ownership".into());
+    }
+
+    // X11 剪贴板由所有者按需提供数据：在后台线程中应答，直到所有权被他人取走
+    std::thread::spawn(move || serve_selection(conn, atoms, items));
+    Ok(())
+}
+
+impl Clipboard for X11Clipboard {
+    fn read_text(&self) -> Option<String> {
+        let (conn, root, atoms) = connect().ok()?;
+        let win = helper_window(&conn, root).ok()?;
+        match convert_selection(&conn, win, &atoms, atoms.UTF8_STRING, MAX_CLIPBOARD_SIZE) {
+            Ok(Some(data)) if data.format == 8 => {
+                let mut value = data.value;
+                value.truncate(MAX_CLIPBOARD_SIZE);
+                Some(String::from_utf8_lossy(&value).into_owned())
+            }
+            Ok(Some(data)) => {
+                log::warn!("Unsupported clipboard property format: {}", data.format);
+                None
+            }
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!("Failed to read clipboard text: {}", e);
+                None
+            }
+        }
+    }
+
+    fn write_transient(
+        &self,
+        content: &FormattedText,
+    ) -> StdResult<(), Box<dyn std::error::Error>> {
+        let (conn, _, atoms) = connect()?;
+        // 作为所有者发送数据时不支持 INCR，超过单个请求上限的文本交给键入回退处理
+        if content.plain.len() + 1024 > conn.maximum_request_bytes() {
+            return Err("Clipboard text too large for a single X request".into());
+        }
+        let data = content.plain.as_bytes().to_vec();
+        let mut items = vec![
+            (atoms.UTF8_STRING, data.clone()),
+            (atoms.TEXT_PLAIN_UTF8, data.clone()),
+            (AtomEnum::STRING.into(), data),
+        ];
+        // 富文本超过单个请求上限时只提供纯文本
+        let fits = |data: &str| data.len() + 1024 <= conn.maximum_request_bytes();
+        if let Some(html) = content.html.as_deref().filter(|h| fits(h)) {
+            items.push((atoms.TEXT_HTML, html.as_bytes().to_vec()));
+        }
+        if let Some(rtf) = content.rtf.as_deref().filter(|r| fits(r)) {
+            items.push((atoms.TEXT_RTF, rtf.as_bytes().to_vec()));
+            items.push((atoms.APPLICATION_RTF, rtf.as_bytes().to_vec()));
+        }
+        items.push((atoms.PASSWORD_MANAGER_HINT, b"secret".to_vec()));
+        take_ownership(items)
+    }
+
+    fn snapshot(&self) -> StdResult<ClipboardSnapshot, Box<dyn std::error::Error>> {
+        let (conn, root, atoms) = connect()?;
+        let mut snapshot = ClipboardSnapshot::default();
+        if conn.get_selection_owner(atoms.CLIPBOARD)?.reply()?.owner == x11rb::NONE {
+            return Ok(snapshot);
+        }
+
+        let win = helper_window(&conn, root)?;
+        let targets = match convert_selection(&conn, win, &atoms, atoms.TARGETS, 64 * 1024)? {
+            Some(data) if data.format == 32 => data
+                .value
+                .chunks_exact(4)
+                .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                .collect::<Vec<Atom>>(),
+            _ => return Err("Clipboard owner did not report its TARGETS".into()),
+        };
+
+        // 元 target 与由其他 target 派生的文本格式不需要保存
+        let skipped = [
+            atoms.TARGETS,
+            atoms.MULTIPLE,
+            atoms.TIMESTAMP,
+            atoms.SAVE_TARGETS,
+            atoms.DELETE,
+            atoms.INCR,
+            atoms.TEXT,
+            atoms.COMPOUND_TEXT,
+        ];
+        for target in targets {
+            if skipped.contains(&target) || target == x11rb::NONE {
+                continue;
+            }
+            let name =
+                String::from_utf8_lossy(&conn.get_atom_name(target)?.reply()?.name).into_owned();
+            let remaining = MAX_SNAPSHOT_BYTES.saturating_sub(snapshot.size());
+            match convert_selection(&conn, win, &atoms, target, remaining) {
+                Ok(Some(data)) if data.format == 8 => {
+                    snapshot.push_within_limit(name, data.value);
+                }
+                Ok(Some(_)) => log::debug!("Skipping non 8-bit clipboard target '{}'", name),
+                Ok(None) => log::debug!("Clipboard owner refused target '{}'", name),
+                Err(e) => log::warn!("Failed to read clipboard target '{}': {}", name, e),
+            }
+        }
+        Ok(snapshot)
+    }
+
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> StdResult<(), Box<dyn std::error::Error>> {
+        if snapshot.is_empty() {
+            // 注入前剪贴板为空：放弃我们的临时内容
+            let (conn, _, atoms) = connect()?;
+            conn.set_selection_owner(x11rb::NONE, atoms.CLIPBOARD, x11rb::CURRENT_TIME)?;
+            conn.sync()?;
+            return Ok(());
+        }
+
+        let (conn, _, _) = connect()?;
+        let mut items = Vec::with_capacity(snapshot.items.len());
+        for item in &snapshot.items {
+            let atom = conn
+                .intern_atom(false, item.format.as_bytes())?
+                .reply()?
+                .atom;
+            items.push((atom, item.data.clone()));
+        }
+        take_ownership(items)
+    }
+
+    fn change_marker(&self) -> Option<u64> {
+        // 每次写入都使用新的辅助窗口，所有者窗口变化即表示剪贴板被改写
+        let (conn, _, atoms) = connect().ok()?;
+        let owner = conn
+            .get_selection_owner(atoms.CLIPBOARD)
+            .ok()?
+            .reply()
+            .ok()?
+            .owner;
+        Some(owner as u64)
+    }
+}
+
+fn serve_selection(conn: RustConnection, atoms: Atoms, items: Vec<(Atom, Vec<u8>)>) {
+    loop {
+        match conn.wait_for_event() {
+            Ok(Event::SelectionRequest(req)) => {
+                if let Err(e) = answer_request(&conn, &atoms, &items, &req) {
+                    log::warn!("Failed to answer clipboard request: {}", e);
+                }
+            }
+            Ok(Event::SelectionClear(_)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("Clipboard owner connection closed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+fn answer_request(
+    conn: &RustConnection,
+    atoms: &Atoms,
+    items: &[(Atom, Vec<u8>)],
+    req: &SelectionRequestEvent,
+) -> StdResult<(), Box<dyn std::error::Error>> {
+    // 旧式客户端可能不指定 property，此时使用 target 本身
+    let mut property = if req.property == x11rb::NONE {
+        req.target
+    } else {
+        req.property
+    };
+    let find = |target: Atom| items.iter().find(|(atom, _)| *atom == target);
+    // TEXT 由 UTF8_STRING 提供
+    let requested = if req.target == atoms.TEXT {
+        find(atoms.UTF8_STRING)
+    } else {
+        find(req.target)
+    };
+
+    if req.target == atoms.TARGETS {
+        let mut targets = vec![atoms.TARGETS];
+        targets.extend(items.iter().map(|(atom, _)| *atom));
+        if find(atoms.UTF8_STRING).is_some() {
+            targets.push(atoms.TEXT);
+        }
+        conn.change_property32(
+            PropMode::REPLACE,
+            req.requestor,
+            property,
+            AtomEnum::ATOM,
+            &targets,
+        )?;
+    } else if let Some((type_, data)) = requested
+        && data.len() + 1024 <= conn.maximum_request_bytes()
+    {
+        conn.change_property8(PropMode::REPLACE, req.requestor, property, *type_, data)?;
+    } else {
+        if requested.is_some() {
+            log::warn!("Clipboard data too large to send without INCR, refusing request");
+        }
+        property = x11rb::NONE;
+    }
+
+    let notify = SelectionNotifyEvent {
+        response_type: SELECTION_NOTIFY_EVENT,
+        sequence: 0,
+        time: req.time,
+        requestor: req.requestor,
+        selection: req.selection,
+        target: req.target,
+        property,
+    };
+    conn.send_event(false, req.requestor, EventMask::NO_EVENT, notify)?;
+    conn.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keysym_for_char() {
+        assert_eq!(keysym_for('a'), Some(0x61));
+        assert_eq!(keysym_for('é'), Some(0xe9));
+        assert_eq!(keysym_for('\n'), Some(XK_RETURN));
+        assert_eq!(keysym_for('\r'), None);
+        assert_eq!(keysym_for('中'), Some(0x0100_4e2d));
+    }
+
+    /// 需要 X server：`xvfb-run cargo test -p service -- --ignored`
+    #[test]
+    #[ignore = "requires an X server (run under xvfb-run)"]
+    fn test_clipboard_round_trip_on_xvfb() {
+        let clipboard = X11Clipboard;
+        clipboard.write_transient_text("提示词 clipboard").unwrap();
+        assert_eq!(clipboard.read_text().as_deref(), Some("提示词 clipboard"));
+
+        // 快照包含全部 target（含历史管理器忽略标记），恢复后可以原样读回
+        let snapshot = clipboard.snapshot().unwrap();
+        assert_eq!(
+            snapshot.get("UTF8_STRING"),
+            Some("提示词 clipboard".as_bytes())
+        );
+        assert_eq!(
+            snapshot.get("x-kde-passwordManagerHint"),
+            Some(b"secret".as_slice())
+        );
+
+        // 再次写入会取走上一个所有者的所有权
+        let marker = clipboard.change_marker();
+        clipboard.write_transient_text("second").unwrap();
+        assert_ne!(clipboard.change_marker(), marker);
+        assert_eq!(clipboard.read_text().as_deref(), Some("second"));
+
+        clipboard.restore(&snapshot).unwrap();
+        assert_eq!(clipboard.read_text().as_deref(), Some("提示词 clipboard"));
+    }
+}
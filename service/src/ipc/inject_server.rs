@@ -1,17 +1,30 @@
-// TW001: Inject Pipe Server (Robust Tokio Implementation)
-// Listens on \\.\pipe\promptkey_inject for INJECT_PROMPT:{id}\n messages
+// TW001: Inject Server (Robust Tokio Implementation)
+// Listens on the `promptkey_inject` endpoint (named pipe on Windows, unix socket elsewhere)
+// for `Command::InjectPrompt` requests (see ipc::protocol)
 
+use super::protocol::{Command, IpcError, Reply};
+use super::transport::{self, Endpoint, PlatformTransport, Transport};
+use std::collections::HashMap;
 use std::sync::mpsc;
 use std::thread;
-use tokio::io::AsyncReadExt;
-use tokio::net::windows::named_pipe::ServerOptions;
 use tokio::runtime::Runtime;
 
-const PIPE_NAME: &str = r"\\.\pipe\promptkey_inject";
+/// Injection request received from the GUI
+#[derive(Debug, Clone, PartialEq)]
+pub struct InjectRequest {
+    pub prompt_id: i32,
+    /// Template variable values filled in by the user (may be empty)
+    pub variables: HashMap<String, String>,
+}
+
+/// Start the inject server in a background thread
+pub fn start() -> mpsc::Receiver<InjectRequest> {
+    start_with(PlatformTransport::default())
+}
 
-/// Start the inject pipe server in a background thread
-pub fn start() -> mpsc::Receiver<i32> {
-    let (tx, rx) = mpsc::channel::<i32>();
+/// Start the inject server on a specific transport
+pub fn start_with<T: Transport + 'static>(transport: T) -> mpsc::Receiver<InjectRequest> {
+    let (tx, rx) = mpsc::channel::<InjectRequest>();
 
     thread::spawn(move || {
         log::info!("[InjectServer] Background thread started");
@@ -26,57 +39,132 @@ pub fn start() -> mpsc::Receiver<i32> {
         };
 
         rt.block_on(async {
-            loop {
-                match listen_once(&tx).await {
-                    Ok(_) => {}
+            let listener = loop {
+                match transport.bind(&Endpoint::inject()) {
+                    Ok(listener) => break listener,
                     Err(e) => {
-                        log::error!("[InjectServer] Loop error: {}", e);
+                        log::error!("[InjectServer] Failed to listen: {}", e);
                         tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
                     }
                 }
-            }
+            };
+            transport::serve(listener, |command| handle_command(&tx, command)).await;
         });
     });
 
     rx
 }
 
-async fn listen_once(tx: &mpsc::Sender<i32>) -> Result<(), Box<dyn std::error::Error>> {
-    log::info!("[InjectServer] Creating named pipe: {}", PIPE_NAME);
-
-    // Create a new pipe instance
-    let mut server = ServerOptions::new()
-        .first_pipe_instance(true)
-        .create(PIPE_NAME)?;
-
-    log::info!("[InjectServer] Waiting for client connection...");
-    server.connect().await?;
+/// Forward inject commands to the service loop; everything else is not served here
+fn handle_command(tx: &mpsc::Sender<InjectRequest>, command: Command) -> Reply {
+    match command {
+        Command::InjectPrompt {
+            prompt_id,
+            variables,
+        } => {
+            log::info!(
+                "[InjectServer] Valid prompt_id received: {} ({} variables)",
+                prompt_id,
+                variables.len()
+            );
+            match tx.send(InjectRequest {
+                prompt_id,
+                variables,
+            }) {
+                Ok(()) => Reply::Ok,
+                Err(_) => Reply::Error {
+                    error: IpcError::Unavailable {
+                        message: "service loop is not running".to_string(),
+                    },
+                },
+            }
+        }
+        Command::Ping => Reply::Ok,
+        other => Reply::Error {
+            error: IpcError::UnsupportedCommand {
+                command: other.name().to_string(),
+            },
+        },
+    }
+}
 
-    log::info!("[InjectServer] Client connected, reading message...");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::protocol;
+
+    #[test]
+    fn test_handle_command() {
+        let (tx, rx) = mpsc::channel();
+        let command = Command::InjectPrompt {
+            prompt_id: 5,
+            variables: HashMap::new(),
+        };
+        assert_eq!(handle_command(&tx, command), Reply::Ok);
+        assert_eq!(rx.try_recv().unwrap().prompt_id, 5);
 
-    let mut buffer = [0u8; 256];
-    let n = server.read(&mut buffer).await?;
+        assert!(matches!(
+            handle_command(&tx, Command::ShowWheel),
+            Reply::Error {
+                error: IpcError::UnsupportedCommand { .. }
+            }
+        ));
 
-    if n > 0 {
-        let message = String::from_utf8_lossy(&buffer[..n]);
-        log::debug!("[InjectServer] Received: {}", message.trim());
+        drop(rx);
+        let command = Command::InjectPrompt {
+            prompt_id: 5,
+            variables: HashMap::new(),
+        };
+        assert!(matches!(
+            handle_command(&tx, command),
+            Reply::Error {
+                error: IpcError::Unavailable { .. }
+            }
+        ));
+    }
 
-        if let Some(prompt_id) = parse_message(&message) {
-            log::info!("[InjectServer] Valid prompt_id received: {}", prompt_id);
-            let _ = tx.send(prompt_id);
-        } else {
-            log::warn!("[InjectServer] Invalid message format: {}", message.trim());
+    #[cfg(unix)]
+    #[test]
+    fn test_inject_over_unix_socket() {
+        let dir = std::env::temp_dir().join(format!("promptkey-ipc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let transport = transport::UnixTransport::in_dir(&dir);
+        let rx = start_with(transport.clone());
+
+        // Wait for the server thread to bind
+        let mut stream = None;
+        for _ in 0..200 {
+            if let Ok(s) = transport.connect(&Endpoint::inject()) {
+                stream = Some(s);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
-    }
+        let mut stream = stream.expect("inject server did not start");
 
-    Ok(())
-}
+        let mut variables = HashMap::new();
+        variables.insert("lang".to_string(), "rust".to_string());
+        let command = Command::InjectPrompt {
+            prompt_id: 9,
+            variables: variables.clone(),
+        };
+        assert_eq!(protocol::call(&mut stream, command).unwrap(), Reply::Ok);
+        let request = rx.recv_timeout(std::time::Duration::from_secs(2)).unwrap();
+        assert_eq!(
+            request,
+            InjectRequest {
+                prompt_id: 9,
+                variables
+            }
+        );
+
+        // One request per connection; a second client gets its own answer
+        let mut stream = transport.connect(&Endpoint::inject()).unwrap();
+        assert_eq!(
+            protocol::call(&mut stream, Command::Ping).unwrap(),
+            Reply::Ok
+        );
 
-fn parse_message(msg: &str) -> Option<i32> {
-    let trimmed = msg.trim();
-    if let Some(id_str) = trimmed.strip_prefix("INJECT_PROMPT:") {
-        id_str.parse::<i32>().ok()
-    } else {
-        None
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -1,69 +1,53 @@
-// IPC Client Module - Service → GUI Communication via Named Pipe
+// IPC Client Module - Service → GUI Communication via Named Pipe / Unix Socket
 // T1-006: Quick Selection Panel IPC Layer
 
 pub mod inject_server; // TW001: Inject pipe server
+pub mod protocol;
+pub mod transport;
 
+use protocol::Command;
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::io::Write;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use transport::{Endpoint, PlatformTransport, Transport};
 
-/// IPC Client for sending messages to GUI via Named Pipe
+/// IPC Client for sending messages to GUI
 pub struct IPCClient {
-    pipe_name: String,
+    endpoint: Endpoint,
+    transport: PlatformTransport,
     last_send: Mutex<Option<Instant>>,
 }
 
+impl Default for IPCClient {
+    /// Default constructor using the standard selector endpoint
+    fn default() -> Self {
+        Self::new(Endpoint::selector())
+    }
+}
+
 impl IPCClient {
-    /// Create a new IPC client with the specified pipe name
-    pub fn new(pipe_name: String) -> Self {
+    /// Create a new IPC client for the specified endpoint
+    pub fn new(endpoint: Endpoint) -> Self {
         IPCClient {
-            pipe_name,
+            endpoint,
+            transport: PlatformTransport::default(),
             last_send: Mutex::new(None),
         }
     }
 
-    /// Default constructor using standard pipe name
-    pub fn default() -> Self {
-        Self::new("\\\\.\\pipe\\promptkey_selector".to_string())
-    }
-
     /// Send "show selector" command to GUI
     /// Includes 500ms debounce to prevent spam
     pub fn send_show_selector(&self) -> Result<(), Box<dyn Error>> {
-        // Debounce: check if 500ms has passed since last send
-        {
-            let mut last = self.last_send.lock().unwrap();
-            if let Some(last_time) = *last {
-                let elapsed = last_time.elapsed();
-                if elapsed < Duration::from_millis(500) {
-                    log::debug!("IPC send debounced ({}ms since last)", elapsed.as_millis());
-                    return Ok(()); // Debounced, silently ignore
-                }
-            }
-            *last = Some(Instant::now());
-        }
-
-        // Send message via Named Pipe
-        match OpenOptions::new().write(true).open(&self.pipe_name) {
-            Ok(mut pipe) => {
-                let message = "SHOW_SELECTOR\n";
-                pipe.write_all(message.as_bytes())?;
-                log::info!("IPC: Sent SHOW_SELECTOR to GUI via {}", self.pipe_name);
-                Ok(())
-            }
-            Err(e) => {
-                // Non-critical: GUI might not be running or pipe not ready
-                log::warn!("IPC: Failed to open named pipe '{}': {}", self.pipe_name, e);
-                Err(Box::new(e))
-            }
-        }
+        self.send_debounced(Command::ShowSelector)
     }
 
     /// TW013: Send "show wheel" command to GUI
     /// Includes 500ms debounce to prevent spam
     pub fn send_show_wheel(&self) -> Result<(), Box<dyn Error>> {
+        self.send_debounced(Command::ShowWheel)
+    }
+
+    fn send_debounced(&self, command: Command) -> Result<(), Box<dyn Error>> {
         // Debounce: check if 500ms has passed since last send
         {
             let mut last = self.last_send.lock().unwrap();
@@ -76,18 +60,21 @@ impl IPCClient {
             }
             *last = Some(Instant::now());
         }
+        self.send_command(command)
+    }
 
-        // Send message via Named Pipe
-        match OpenOptions::new().write(true).open(&self.pipe_name) {
-            Ok(mut pipe) => {
-                let message = "SHOW_WHEEL\n";
-                pipe.write_all(message.as_bytes())?;
-                log::info!("IPC: Sent SHOW_WHEEL to GUI via {}", self.pipe_name);
+    /// Send one command and wait for the GUI's reply
+    pub fn send_command(&self, command: Command) -> Result<(), Box<dyn Error>> {
+        let name = command.name();
+        match self.transport.connect(&self.endpoint) {
+            Ok(mut stream) => {
+                protocol::call(&mut stream, command)?.into_result()?;
+                log::info!("IPC: Sent {} to GUI via {}", name, self.endpoint.name);
                 Ok(())
             }
             Err(e) => {
-                // Non-critical: GUI might not be running or pipe not ready
-                log::warn!("IPC: Failed to open named pipe '{}': {}", self.pipe_name, e);
+                // Non-critical: GUI might not be running or endpoint not ready
+                log::warn!("IPC: Failed to connect to '{}': {}", self.endpoint.name, e);
                 Err(Box::new(e))
             }
         }
@@ -101,7 +88,7 @@ mod tests {
 
     #[test]
     fn test_debounce() {
-        let client = IPCClient::new("\\\\.\\pipe\\test_pipe".to_string());
+        let client = IPCClient::new(Endpoint::new("test_pipe"));
 
         // First send updates timestamp
         let _ = client.send_show_selector();
@@ -0,0 +1,539 @@
+// IPC Protocol - versioned request/response messages shared by both pipes
+// Frame: u32 little-endian body length, then a UTF-8 JSON body.
+// Every Request carries an id; the peer answers with exactly one Response echoing it.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Upper bound for a single frame body; guards against garbage length prefixes
+pub const MAX_FRAME_LEN: usize = 4 * 1024 * 1024;
+
+const HEADER_LEN: usize = 4;
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Commands understood by the service or the GUI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    /// Service → GUI: show the quick selection panel
+    ShowSelector,
+    /// Service → GUI: show the prompt wheel (TW013)
+    ShowWheel,
+    /// GUI → Service: inject a prompt into the previously active window (TW001)
+    InjectPrompt {
+        prompt_id: i32,
+        /// Template variable values filled in by the user (may be empty)
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        variables: HashMap<String, String>,
+    },
+    /// Service → GUI: result of an injection, shown to the user as a toast
+    InjectionOutcome(InjectionOutcome),
+    /// Service → GUI: which chord each hotkey binding ended up registered with
+    HotkeyStatus {
+        registrations: Vec<HotkeyRegistration>,
+    },
+    /// Service → GUI: keys available after the leader hotkey; an empty list hides the hint
+    SequenceHints {
+        /// Keys typed so far after the leader (e.g. "R")
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        pressed: String,
+        hints: Vec<SequenceHint>,
+    },
+    /// Liveness check; always answered with `Reply::Ok`
+    Ping,
+}
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::ShowSelector => "show_selector",
+            Command::ShowWheel => "show_wheel",
+            Command::InjectPrompt { .. } => "inject_prompt",
+            Command::InjectionOutcome(_) => "injection_outcome",
+            Command::HotkeyStatus { .. } => "hotkey_status",
+            Command::SequenceHints { .. } => "sequence_hints",
+            Command::Ping => "ping",
+        }
+    }
+}
+
+/// What happened to one injection request (wheel selection or hotkey)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InjectionOutcome {
+    pub prompt_id: Option<i32>,
+    pub prompt_name: Option<String>,
+    pub success: bool,
+    /// Strategy that delivered the text (e.g. "Clipboard", "SendInput")
+    pub strategy: Option<String>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+impl InjectionOutcome {
+    /// The request failed before any text was injected
+    pub fn failed(prompt_id: Option<i32>, prompt_name: Option<String>, error: String) -> Self {
+        InjectionOutcome {
+            prompt_id,
+            prompt_name,
+            success: false,
+            strategy: None,
+            elapsed_ms: 0,
+            error: Some(error),
+        }
+    }
+}
+
+/// Registration result of one hotkey binding; its chord and fallbacks are tried in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyRegistration {
+    /// Action the binding triggers (e.g. "show_wheel")
+    pub action: String,
+    /// Chord that is now active; None when every candidate failed
+    pub active: Option<String>,
+    /// Candidates that could not be registered, in the order they were tried
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<ChordFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordFailure {
+    pub chord: String,
+    pub error: String,
+}
+
+/// One key that can follow the current leader sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceHint {
+    pub key: String,
+    /// Prompt injected when this key completes a sequence
+    pub label: Option<String>,
+    /// Longer sequences continue with this key
+    pub more: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub version: u32,
+    pub id: u64,
+    pub command: Command,
+}
+
+impl Request {
+    /// Build a request with a fresh process-unique id
+    pub fn new(command: Command) -> Self {
+        Request {
+            version: PROTOCOL_VERSION,
+            id: NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+            command,
+        }
+    }
+
+    /// Decode a request body. Failures come back as the error Response to send,
+    /// echoing the request id whenever it could be read.
+    pub fn parse(body: &[u8]) -> Result<Request, Response> {
+        let value: serde_json::Value = serde_json::from_slice(body).map_err(|e| {
+            Response::error(
+                0,
+                IpcError::BadRequest {
+                    message: e.to_string(),
+                },
+            )
+        })?;
+        let id = value.get("id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        if version != PROTOCOL_VERSION {
+            return Err(Response::error(
+                id,
+                IpcError::UnsupportedVersion {
+                    version,
+                    supported: PROTOCOL_VERSION,
+                },
+            ));
+        }
+        serde_json::from_value(value).map_err(|e| {
+            Response::error(
+                id,
+                IpcError::BadRequest {
+                    message: e.to_string(),
+                },
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub version: u32,
+    /// Id of the request being answered (0 if it could not be decoded)
+    pub id: u64,
+    pub reply: Reply,
+}
+
+impl Response {
+    pub fn new(id: u64, reply: Reply) -> Self {
+        Response {
+            version: PROTOCOL_VERSION,
+            id,
+            reply,
+        }
+    }
+
+    pub fn error(id: u64, error: IpcError) -> Self {
+        Self::new(id, Reply::Error { error })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Reply {
+    Ok,
+    Error { error: IpcError },
+}
+
+impl Reply {
+    pub fn into_result(self) -> Result<(), IpcError> {
+        match self {
+            Reply::Ok => Ok(()),
+            Reply::Error { error } => Err(error),
+        }
+    }
+}
+
+/// Typed error responses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum IpcError {
+    /// The peer speaks a different protocol version
+    UnsupportedVersion {
+        version: u32,
+        supported: u32,
+    },
+    /// The body was not a valid request
+    BadRequest {
+        message: String,
+    },
+    /// The command is valid but not handled by this endpoint
+    UnsupportedCommand {
+        command: String,
+    },
+    /// The endpoint cannot act right now (window missing, service loop stopped, ...)
+    Unavailable {
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
+}
+
+impl fmt::Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcError::UnsupportedVersion { version, supported } => write!(
+                f,
+                "unsupported protocol version {} (supported: {})",
+                version, supported
+            ),
+            IpcError::BadRequest { message } => write!(f, "bad request: {}", message),
+            IpcError::UnsupportedCommand { command } => {
+                write!(f, "unsupported command: {}", command)
+            }
+            IpcError::Unavailable { message } => write!(f, "unavailable: {}", message),
+            IpcError::Internal { message } => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for IpcError {}
+
+/// Transport / framing errors (as opposed to error *responses*)
+#[derive(Debug)]
+pub enum CodecError {
+    Io(std::io::Error),
+    FrameTooLarge(usize),
+    Json(serde_json::Error),
+    /// The response id does not match the request that was sent
+    IdMismatch {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(e) => write!(f, "I/O error: {}", e),
+            CodecError::FrameTooLarge(len) => {
+                write!(
+                    f,
+                    "frame of {} bytes exceeds limit of {}",
+                    len, MAX_FRAME_LEN
+                )
+            }
+            CodecError::Json(e) => write!(f, "invalid JSON: {}", e),
+            CodecError::IdMismatch { expected, actual } => write!(
+                f,
+                "response id {} does not match request id {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<std::io::Error> for CodecError {
+    fn from(e: std::io::Error) -> Self {
+        CodecError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(e: serde_json::Error) -> Self {
+        CodecError::Json(e)
+    }
+}
+
+/// Serialize a message into a complete frame (header + body)
+pub fn encode_frame<T: Serialize>(msg: &T) -> Result<Vec<u8>, CodecError> {
+    let body = serde_json::to_vec(msg)?;
+    if body.len() > MAX_FRAME_LEN {
+        return Err(CodecError::FrameTooLarge(body.len()));
+    }
+    let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
+
+/// Try to split one frame off the front of `buf`.
+/// Returns the body and the number of bytes consumed, or `None` if `buf` is incomplete.
+pub fn split_frame(buf: &[u8]) -> Result<Option<(&[u8], usize)>, CodecError> {
+    let Some(header) = buf.get(..HEADER_LEN) else {
+        return Ok(None);
+    };
+    let len = body_len(header.try_into().unwrap())?;
+    Ok(buf
+        .get(HEADER_LEN..HEADER_LEN + len)
+        .map(|body| (body, HEADER_LEN + len)))
+}
+
+fn body_len(header: [u8; HEADER_LEN]) -> Result<usize, CodecError> {
+    let len = u32::from_le_bytes(header) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(CodecError::FrameTooLarge(len));
+    }
+    Ok(len)
+}
+
+pub fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, CodecError> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+pub fn write_frame<W: Write, T: Serialize>(w: &mut W, msg: &T) -> Result<(), CodecError> {
+    w.write_all(&encode_frame(msg)?)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Read one frame body
+pub fn read_frame<R: Read>(r: &mut R) -> Result<Vec<u8>, CodecError> {
+    let mut header = [0u8; HEADER_LEN];
+    r.read_exact(&mut header)?;
+    let mut body = vec![0u8; body_len(header)?];
+    r.read_exact(&mut body)?;
+    Ok(body)
+}
+
+pub async fn write_frame_async<W, T>(w: &mut W, msg: &T) -> Result<(), CodecError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    w.write_all(&encode_frame(msg)?).await?;
+    w.flush().await?;
+    Ok(())
+}
+
+pub async fn read_frame_async<R: AsyncRead + Unpin>(r: &mut R) -> Result<Vec<u8>, CodecError> {
+    let mut header = [0u8; HEADER_LEN];
+    r.read_exact(&mut header).await?;
+    let mut body = vec![0u8; body_len(header)?];
+    r.read_exact(&mut body).await?;
+    Ok(body)
+}
+
+/// Client side: send one command over a blocking stream and wait for its reply
+pub fn call<S: Read + Write>(stream: &mut S, command: Command) -> Result<Reply, CodecError> {
+    let request = Request::new(command);
+    write_frame(stream, &request)?;
+    let response: Response = decode(&read_frame(stream)?)?;
+    if response.id != request.id {
+        return Err(CodecError::IdMismatch {
+            expected: request.id,
+            actual: response.id,
+        });
+    }
+    Ok(response.reply)
+}
+
+/// Server side: read one request, answer it with `handler` and write the response
+pub async fn serve_one<S, F>(stream: &mut S, handler: F) -> Result<(), CodecError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: FnOnce(Command) -> Reply,
+{
+    let body = read_frame_async(stream).await?;
+    let response = match Request::parse(&body) {
+        Ok(request) => {
+            log::debug!("[IPC] Request #{}: {}", request.id, request.command.name());
+            Response::new(request.id, handler(request.command))
+        }
+        Err(response) => {
+            log::warn!("[IPC] Rejected request: {:?}", response.reply);
+            response
+        }
+    };
+    write_frame_async(stream, &response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frame_round_trip_and_partial_input() {
+        let mut variables = HashMap::new();
+        variables.insert("lang".to_string(), "rust".to_string());
+        let request = Request::new(Command::InjectPrompt {
+            prompt_id: 7,
+            variables,
+        });
+        let frame = encode_frame(&request).unwrap();
+
+        assert!(split_frame(&frame[..3]).unwrap().is_none());
+        assert!(split_frame(&frame[..frame.len() - 1]).unwrap().is_none());
+        let (body, used) = split_frame(&frame).unwrap().unwrap();
+        assert_eq!(used, frame.len());
+        assert_eq!(Request::parse(body).unwrap(), request);
+
+        let outcome = Request::new(Command::InjectionOutcome(InjectionOutcome::failed(
+            Some(7),
+            Some("Review".to_string()),
+            "paste blocked".to_string(),
+        )));
+        let frame = encode_frame(&outcome).unwrap();
+        let (body, _) = split_frame(&frame).unwrap().unwrap();
+        assert!(
+            std::str::from_utf8(body)
+                .unwrap()
+                .contains(r#""type":"injection_outcome""#)
+        );
+        assert_eq!(Request::parse(body).unwrap(), outcome);
+
+        let status = Request::new(Command::HotkeyStatus {
+            registrations: vec![HotkeyRegistration {
+                action: "show_wheel".to_string(),
+                active: Some("Ctrl+Alt+W".to_string()),
+                failures: vec![ChordFailure {
+                    chord: "Ctrl+Alt+Space".to_string(),
+                    error: "already registered".to_string(),
+                }],
+            }],
+        });
+        let frame = encode_frame(&status).unwrap();
+        let (body, _) = split_frame(&frame).unwrap().unwrap();
+        assert_eq!(Request::parse(body).unwrap(), status);
+
+        let oversized = ((MAX_FRAME_LEN + 1) as u32).to_le_bytes();
+        assert!(matches!(
+            split_frame(&oversized),
+            Err(CodecError::FrameTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_requests_with_typed_errors() {
+        let old = br#"{"version":0,"id":9,"command":{"type":"ping"}}"#;
+        let response = Request::parse(old).unwrap_err();
+        assert_eq!(response.id, 9);
+        assert_eq!(
+            response.reply,
+            Reply::Error {
+                error: IpcError::UnsupportedVersion {
+                    version: 0,
+                    supported: PROTOCOL_VERSION
+                }
+            }
+        );
+
+        let unknown = br#"{"version":1,"id":3,"command":{"type":"reboot"}}"#;
+        let response = Request::parse(unknown).unwrap_err();
+        assert_eq!(response.id, 3);
+        assert!(matches!(
+            response.reply,
+            Reply::Error {
+                error: IpcError::BadRequest { .. }
+            }
+        ));
+    }
+
+    // Blocking stream whose reads come from a pre-filled buffer
+    struct Loopback {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            Read::read(&mut self.input, buf)
+        }
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Write::write(&mut self.output, buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_and_serve_one() {
+        // Server answers the client's encoded request
+        let request = Request::new(Command::ShowWheel);
+        let mut server_io = tokio::io::join(
+            Cursor::new(encode_frame(&request).unwrap()),
+            Vec::<u8>::new(),
+        );
+        serve_one(&mut server_io, |command| {
+            assert_eq!(command, Command::ShowWheel);
+            Reply::Ok
+        })
+        .await
+        .unwrap();
+        let (_, written) = server_io.into_inner();
+        let response: Response = decode(split_frame(&written).unwrap().unwrap().0).unwrap();
+        assert_eq!(response, Response::new(request.id, Reply::Ok));
+
+        // A reply for some other request is detected by the client
+        let mut stream = Loopback {
+            input: Cursor::new(written),
+            output: Vec::new(),
+        };
+        assert!(matches!(
+            call(&mut stream, Command::ShowWheel),
+            Err(CodecError::IdMismatch { .. })
+        ));
+    }
+}
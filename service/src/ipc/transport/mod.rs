@@ -0,0 +1,85 @@
+// IPC Transport - where protocol frames travel
+// Windows: named pipes (\\.\pipe\<name>); Unix: domain sockets under $XDG_RUNTIME_DIR/promptkey/
+
+#[cfg(windows)]
+mod pipe;
+#[cfg(unix)]
+mod unix;
+
+#[cfg(windows)]
+pub use pipe::PipeTransport;
+#[cfg(unix)]
+pub use unix::UnixTransport;
+
+use super::protocol::{self, Command, Reply};
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Transport used by the service and the GUI on this platform
+#[cfg(unix)]
+pub type PlatformTransport = UnixTransport;
+#[cfg(windows)]
+pub type PlatformTransport = PipeTransport;
+
+/// A named IPC endpoint, e.g. the GUI's selector listener or the service's inject server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub name: String,
+}
+
+impl Endpoint {
+    pub fn new(name: impl Into<String>) -> Self {
+        Endpoint { name: name.into() }
+    }
+
+    /// T1-006: GUI listener for show selector / show wheel
+    pub fn selector() -> Self {
+        Self::new("promptkey_selector")
+    }
+
+    /// TW001: service listener for inject requests
+    pub fn inject() -> Self {
+        Self::new("promptkey_inject")
+    }
+}
+
+/// Server side of a transport: yields one connection per client
+pub trait Listener: Send {
+    type Conn: AsyncRead + AsyncWrite + Unpin + Send;
+
+    fn accept(&mut self) -> impl Future<Output = io::Result<Self::Conn>> + Send;
+}
+
+pub trait Transport: Send + Sync {
+    type Listener: Listener;
+    /// Blocking client stream
+    type Client: Read + Write;
+
+    /// Start listening on `endpoint`. Must be called from within a Tokio runtime.
+    fn bind(&self, endpoint: &Endpoint) -> io::Result<Self::Listener>;
+
+    fn connect(&self, endpoint: &Endpoint) -> io::Result<Self::Client>;
+}
+
+/// Accept connections forever, answering one request per connection with `handler`
+pub async fn serve<L, F>(mut listener: L, mut handler: F)
+where
+    L: Listener,
+    F: FnMut(Command) -> Reply,
+{
+    loop {
+        match listener.accept().await {
+            Ok(mut conn) => {
+                if let Err(e) = protocol::serve_one(&mut conn, &mut handler).await {
+                    log::warn!("[IPC] Error serving request: {}", e);
+                }
+            }
+            Err(e) => {
+                log::error!("[IPC] Failed to accept connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(1000)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Named pipe transport: one pipe instance per connection
+
+use super::{Endpoint, Listener, Transport};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::windows::io::AsRawHandle;
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::System::IO::CancelIoEx;
+
+/// Client-side read/write timeout, so a stuck peer cannot block the caller forever
+/// (same as the Unix socket client)
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default)]
+pub struct PipeTransport;
+
+fn pipe_path(endpoint: &Endpoint) -> String {
+    format!(r"\\.\pipe\{}", endpoint.name)
+}
+
+pub struct PipeListener {
+    path: String,
+}
+
+impl Listener for PipeListener {
+    type Conn = NamedPipeServer;
+
+    async fn accept(&mut self) -> io::Result<NamedPipeServer> {
+        // first_pipe_instance: refuse to share the name with another process
+        let server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&self.path)?;
+        server.connect().await?;
+        Ok(server)
+    }
+}
+
+/// Blocking pipe client whose reads and writes give up after CLIENT_TIMEOUT.
+/// Synchronous pipe I/O has no timeout of its own, so each operation runs on a helper
+/// thread and is cancelled with CancelIoEx when the wait expires.
+pub struct PipeClient {
+    file: Arc<File>,
+}
+
+impl PipeClient {
+    fn with_timeout<T, F>(&self, op: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&File) -> io::Result<T> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let file = self.file.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(op(&file));
+        });
+        match rx.recv_timeout(CLIENT_TIMEOUT) {
+            Ok(result) => result,
+            Err(_) => {
+                let handle = HANDLE(self.file.as_raw_handle());
+                let _ = unsafe { CancelIoEx(handle, None) };
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "named pipe peer did not respond",
+                ))
+            }
+        }
+    }
+}
+
+impl Read for PipeClient {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len();
+        let data = self.with_timeout(move |mut file| {
+            let mut data = vec![0; len];
+            let n = file.read(&mut data)?;
+            data.truncate(n);
+            Ok(data)
+        })?;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
+
+impl Write for PipeClient {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let data = buf.to_vec();
+        self.with_timeout(move |mut file| file.write(&data))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.with_timeout(|mut file| file.flush())
+    }
+}
+
+impl Transport for PipeTransport {
+    type Listener = PipeListener;
+    type Client = PipeClient;
+
+    fn bind(&self, endpoint: &Endpoint) -> io::Result<PipeListener> {
+        let path = pipe_path(endpoint);
+        log::info!("[IPC] Listening on named pipe {}", path);
+        Ok(PipeListener { path })
+    }
+
+    fn connect(&self, endpoint: &Endpoint) -> io::Result<PipeClient> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(pipe_path(endpoint))?;
+        Ok(PipeClient {
+            file: Arc::new(file),
+        })
+    }
+}
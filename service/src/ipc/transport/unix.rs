@@ -0,0 +1,190 @@
+// Unix domain socket transport
+// Sockets live in a private directory ($XDG_RUNTIME_DIR/promptkey, or /tmp/promptkey-<uid>)
+// that must be owned by the current user and closed to group/others.
+
+use super::{Endpoint, Listener, Transport};
+use std::fs::DirBuilder;
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::UnixStream as StdUnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::{UnixListener, UnixStream};
+
+/// Client-side read/write timeout, so a stuck peer cannot block the caller forever
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct UnixTransport {
+    dir: PathBuf,
+}
+
+impl Default for UnixTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnixTransport {
+    pub fn new() -> Self {
+        let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(runtime) if !runtime.is_empty() => PathBuf::from(runtime).join("promptkey"),
+            _ => std::env::temp_dir().join(format!("promptkey-{}", current_uid())),
+        };
+        Self::in_dir(dir)
+    }
+
+    /// Use a specific socket directory (tests, portable setups)
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        UnixTransport { dir: dir.into() }
+    }
+
+    pub fn socket_path(&self, endpoint: &Endpoint) -> PathBuf {
+        self.dir.join(format!("{}.sock", endpoint.name))
+    }
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() }
+}
+
+fn permission_denied(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, msg)
+}
+
+/// Create the socket directory (0700) or verify an existing one is private to us
+fn ensure_private_dir(dir: &Path) -> io::Result<()> {
+    if !dir.exists() {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+    // symlink_metadata: a symlink planted in place of the directory is rejected
+    let meta = std::fs::symlink_metadata(dir)?;
+    if !meta.is_dir() {
+        return Err(permission_denied(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+    if meta.uid() != current_uid() {
+        return Err(permission_denied(format!(
+            "{} is owned by uid {}, not by the current user",
+            dir.display(),
+            meta.uid()
+        )));
+    }
+    if meta.mode() & 0o077 != 0 {
+        return Err(permission_denied(format!(
+            "{} is accessible by other users (mode {:o}); expected 0700",
+            dir.display(),
+            meta.mode() & 0o777
+        )));
+    }
+    Ok(())
+}
+
+/// An existing socket file must be a socket owned by us
+fn check_socket(path: &Path) -> io::Result<()> {
+    let meta = std::fs::symlink_metadata(path)?;
+    if !meta.file_type().is_socket() {
+        return Err(permission_denied(format!(
+            "{} is not a socket",
+            path.display()
+        )));
+    }
+    if meta.uid() != current_uid() {
+        return Err(permission_denied(format!(
+            "{} is owned by uid {}, not by the current user",
+            path.display(),
+            meta.uid()
+        )));
+    }
+    Ok(())
+}
+
+pub struct UnixSocketListener {
+    inner: UnixListener,
+    path: PathBuf,
+}
+
+impl Listener for UnixSocketListener {
+    type Conn = UnixStream;
+
+    async fn accept(&mut self) -> io::Result<UnixStream> {
+        let (stream, _) = self.inner.accept().await?;
+        Ok(stream)
+    }
+}
+
+impl Drop for UnixSocketListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Transport for UnixTransport {
+    type Listener = UnixSocketListener;
+    type Client = StdUnixStream;
+
+    fn bind(&self, endpoint: &Endpoint) -> io::Result<UnixSocketListener> {
+        ensure_private_dir(&self.dir)?;
+        let path = self.socket_path(endpoint);
+
+        if std::fs::symlink_metadata(&path).is_ok() {
+            check_socket(&path)?;
+            // Someone is still listening: behave like an exclusive named pipe
+            if StdUnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} is already in use", path.display()),
+                ));
+            }
+            // Stale socket left by a crashed process
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        log::info!("[IPC] Listening on unix socket {}", path.display());
+        Ok(UnixSocketListener {
+            inner: listener,
+            path,
+        })
+    }
+
+    fn connect(&self, endpoint: &Endpoint) -> io::Result<StdUnixStream> {
+        let path = self.socket_path(endpoint);
+        check_socket(&path)?;
+        let stream = StdUnixStream::connect(&path)?;
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_shared_socket_dir() {
+        let dir = std::env::temp_dir().join(format!("promptkey-ipc-perm-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let err = ensure_private_dir(&dir).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        ensure_private_dir(&dir).unwrap();
+
+        // A regular file where the socket should be is refused by clients
+        let transport = UnixTransport::in_dir(&dir);
+        std::fs::write(transport.socket_path(&Endpoint::inject()), b"").unwrap();
+        let err = transport.connect(&Endpoint::inject()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
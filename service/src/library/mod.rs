@@ -0,0 +1,422 @@
+// Folder-backed Prompt Library
+// Mirrors Markdown files from mounted directories into the prompts table so the
+// selector, wheel, search and scope matching work on them unchanged. Files stay
+// the source of truth: edits to read-write sources are written back to disk.
+
+pub mod watcher;
+
+pub use watcher::LibraryWatcher;
+
+use crate::config::PromptSourceConfig;
+use crate::db::{Database, Prompt, PromptEdit};
+use crate::exchange::{ExportedPrompt, markdown, same_prompt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+type LibResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// 一个已挂载的提示词目录
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptSource {
+    pub name: String,
+    pub root: PathBuf,
+    pub writable: bool,
+}
+
+impl PromptSource {
+    pub fn from_config(config: &PromptSourceConfig) -> Self {
+        let root = PathBuf::from(&config.path);
+        // 规范化路径，使 watcher 事件中的路径可以按前缀归属到来源
+        let root = root.canonicalize().unwrap_or(root);
+        let name = config
+            .name
+            .clone()
+            .filter(|n| !n.trim().is_empty())
+            .or_else(|| root.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| config.path.clone());
+        PromptSource {
+            name,
+            root,
+            writable: config.writable,
+        }
+    }
+
+    /// 按配置创建全部来源。来源名称是数据库中索引的归属，重名的来源会互相删除对方的索引，
+    /// 因此只保留第一个，其余记录错误后忽略
+    pub fn from_configs(configs: &[PromptSourceConfig]) -> Vec<Self> {
+        let mut sources: Vec<PromptSource> = Vec::new();
+        for config in configs {
+            let source = Self::from_config(config);
+            if sources.iter().any(|s| s.name == source.name) {
+                log::error!(
+                    "提示词目录 {} 的名称 '{}' 与其他目录重复，已忽略；请在配置中指定不同的 name",
+                    config.path,
+                    source.name
+                );
+                continue;
+            }
+            sources.push(source);
+        }
+        sources
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.root)
+    }
+}
+
+/// 一次同步的结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    /// 无法解析的文件与无法读取的来源（对应的旧索引保持不变）
+    pub errors: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.updated == 0 && self.removed == 0 && self.errors.is_empty()
+    }
+
+    fn merge(&mut self, other: SyncReport) {
+        self.added += other.added;
+        self.updated += other.updated;
+        self.removed += other.removed;
+        self.errors.extend(other.errors);
+    }
+}
+
+/// 同步全部来源，并清理配置中已移除的来源
+///
+/// 某个来源无法读取（例如目录不存在）时记录错误并继续同步其他来源，其旧索引保持不变。
+pub fn sync_all(db: &Database, sources: &[PromptSource]) -> LibResult<SyncReport> {
+    let names: Vec<String> = sources.iter().map(|s| s.name.clone()).collect();
+    let mut report = SyncReport {
+        removed: db.remove_unmounted_sources(&names)?,
+        ..Default::default()
+    };
+    for source in sources {
+        match sync_source(db, source) {
+            Ok(source_report) => report.merge(source_report),
+            Err(e) => {
+                log::error!("同步提示词目录 {} 失败: {}", source.name, e);
+                report
+                    .errors
+                    .push(format!("{}: {}", source.root.display(), e));
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// 将目录中的 .md 文件与数据库中的索引对齐
+///
+/// 置顶状态属于本地偏好：仅在首次索引时采用 front-matter 中的 pinned。
+pub fn sync_source(db: &Database, source: &PromptSource) -> LibResult<SyncReport> {
+    let mut report = SyncReport::default();
+    let indexed = db.get_prompts_by_source(&source.name)?;
+    let mut seen = HashSet::new();
+
+    for path in markdown_files(&source.root)? {
+        let key = path.to_string_lossy().into_owned();
+        seen.insert(key.clone());
+
+        let mut prompt = match load_file(&path) {
+            Ok(p) => p,
+            Err(e) => {
+                log::warn!("跳过无法解析的提示词文件 {}: {}", key, e);
+                report.errors.push(format!("{}: {}", key, e));
+                continue;
+            }
+        };
+
+        match indexed
+            .iter()
+            .find(|p| p.source_path.as_deref() == Some(key.as_str()))
+        {
+            Some(current) => {
+                let id = current.id.unwrap_or_default();
+                prompt.is_pinned = current.is_pinned;
+                if current.name != prompt.name || !same_prompt(current, &prompt) {
+                    prompt.id = Some(id);
+                    db.update_prompt(&prompt)?;
+                    report.updated += 1;
+                }
+                if current.read_only == source.writable {
+                    db.set_prompt_source(id, &source.name, &key, source.writable)?;
+                }
+            }
+            None => {
+                let id = db.create_prompt(&prompt)?;
+                db.set_prompt_source(id, &source.name, &key, source.writable)?;
+                report.added += 1;
+            }
+        }
+    }
+
+    for stale in indexed
+        .iter()
+        .filter(|p| !p.source_path.as_ref().is_some_and(|s| seen.contains(s)))
+    {
+        if let Some(id) = stale.id {
+            db.delete_prompt(id)?;
+            report.removed += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+fn load_file(path: &Path) -> LibResult<Prompt> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("prompt");
+    let text = std::fs::read_to_string(path)?;
+    Ok(markdown::from_markdown(&text, stem)?.to_prompt()?)
+}
+
+/// 递归列出目录下的 .md 文件，跳过隐藏目录（例如 .git）
+fn markdown_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if is_hidden(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_markdown(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.'))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md"))
+}
+
+/// 只读来源的提示词不允许修改
+pub fn ensure_editable(db: &Database, id: i32) -> LibResult<Prompt> {
+    let prompt = db.get_prompt_by_id(id)?;
+    if prompt.read_only {
+        return Err(format!(
+            "提示词 '{}' 来自只读目录 {}，请直接修改源文件",
+            prompt.name,
+            prompt.source.as_deref().unwrap_or("")
+        )
+        .into());
+    }
+    Ok(prompt)
+}
+
+/// 将数据库中的当前内容写回来源文件（非目录来源的提示词不做任何事）
+pub fn write_back(db: &Database, id: i32) -> LibResult<()> {
+    let prompt = ensure_editable(db, id)?;
+    if let Some(path) = &prompt.source_path {
+        std::fs::write(
+            path,
+            markdown::to_markdown(&ExportedPrompt::from_prompt(&prompt))?,
+        )?;
+        log::info!("已写回提示词文件 {}", path);
+    }
+    Ok(())
+}
+
+/// GUI 保存提示词：更新数据库，读写来源同时写回文件；返回新版本号
+pub fn save_prompt(db: &Database, prompt: &Prompt) -> LibResult<i32> {
+    let id = prompt.id.ok_or("Prompt id is required for update")?;
+    ensure_editable(db, id)?;
+    let version = db.update_prompt(prompt)?;
+    write_back(db, id)?;
+    Ok(version)
+}
+
+/// GUI 编辑表单保存：只修改表单中的字段，其余列沿用数据库中的值
+pub fn edit_prompt(db: &Database, edit: &PromptEdit) -> LibResult<i32> {
+    let mut prompt = db.get_prompt_by_id(edit.id)?;
+    edit.apply(&mut prompt);
+    save_prompt(db, &prompt)
+}
+
+/// GUI 删除提示词：读写来源同时删除文件
+pub fn delete_prompt(db: &Database, id: i32) -> LibResult<()> {
+    let prompt = ensure_editable(db, id)?;
+    if let Some(path) = &prompt.source_path {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    db.delete_prompt(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("promptkey-library-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        dir
+    }
+
+    fn source(dir: &Path, writable: bool) -> PromptSource {
+        PromptSource::from_config(&PromptSourceConfig {
+            path: dir.to_string_lossy().into_owned(),
+            name: Some("team".to_string()),
+            writable,
+        })
+    }
+
+    #[test]
+    fn test_sync_adds_updates_and_removes() {
+        let dir = temp_dir("sync");
+        std::fs::write(
+            dir.join("review.md"),
+            "---\nname: Review\ntags: [dev]\n---\nv1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("nested/plain.md"), "plain body\n").unwrap();
+        std::fs::write(dir.join("broken.md"), "---\nname: [\n---\nx\n").unwrap();
+
+        let db = Database::new(":memory:").unwrap();
+        let src = source(&dir, false);
+        let report = sync_all(&db, std::slice::from_ref(&src)).unwrap();
+        assert_eq!((report.added, report.errors.len()), (2, 1));
+
+        let prompts = db.get_prompts_by_source("team").unwrap();
+        let review = prompts.iter().find(|p| p.name == "Review").unwrap();
+        assert!(review.read_only);
+        assert!(
+            prompts
+                .iter()
+                .any(|p| p.name == "plain" && p.content == "plain body")
+        );
+        assert!(save_prompt(&db, review).is_err());
+
+        std::fs::write(dir.join("review.md"), "---\nname: Review\n---\nv2\n").unwrap();
+        std::fs::remove_file(dir.join("nested/plain.md")).unwrap();
+        let report = sync_source(&db, &src).unwrap();
+        assert_eq!((report.added, report.updated, report.removed), (0, 1, 1));
+        assert!(sync_source(&db, &src).unwrap().errors.len() == 1);
+
+        // 只修改 front-matter 中的缩写也会更新索引
+        std::fs::write(
+            dir.join("review.md"),
+            "---\nname: Review\nabbreviation: ;;rv\n---\nv2\n",
+        )
+        .unwrap();
+        assert_eq!(sync_source(&db, &src).unwrap().updated, 1);
+        let review = &db.get_prompts_by_source("team").unwrap()[0];
+        assert_eq!(review.abbreviation.as_deref(), Some(";;rv"));
+
+        // 来源从配置中移除后其索引被清理
+        sync_all(&db, &[]).unwrap();
+        assert!(db.get_prompts_by_source("team").unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_writable_source_writes_back() {
+        let dir = temp_dir("writeback");
+        std::fs::write(dir.join("fix.md"), "---\nname: Fix\n---\nold\n").unwrap();
+
+        let db = Database::new(":memory:").unwrap();
+        let src = source(&dir, true);
+        sync_source(&db, &src).unwrap();
+
+        let mut prompt = db.get_prompts_by_source("team").unwrap().remove(0);
+        prompt.content = "new content".to_string();
+        save_prompt(&db, &prompt).unwrap();
+
+        let text = std::fs::read_to_string(dir.join("fix.md")).unwrap();
+        assert!(text.ends_with("---\nnew content\n"));
+        // 写回后再次同步不产生变更
+        assert!(sync_source(&db, &src).unwrap().is_empty());
+
+        delete_prompt(&db, prompt.id.unwrap()).unwrap();
+        assert!(!dir.join("fix.md").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_edit_keeps_fields_not_in_form() {
+        let dir = temp_dir("edit");
+        std::fs::write(
+            dir.join("translate.md"),
+            concat!(
+                "---\nname: Translate\nvariables:\n- name: lang\napp_scopes: [code.exe]\n",
+                "---\nTranslate to {{lang}}\n",
+            ),
+        )
+        .unwrap();
+
+        let db = Database::new(":memory:").unwrap();
+        let src = source(&dir, true);
+        sync_source(&db, &src).unwrap();
+        let id = db.get_prompts_by_source("team").unwrap()[0].id.unwrap();
+
+        let edit = PromptEdit {
+            id,
+            name: "Translate".to_string(),
+            tags: None,
+            content: "Translate into {{lang}}".to_string(),
+            abbreviation: None,
+        };
+        edit_prompt(&db, &edit).unwrap();
+
+        let prompt = db.get_prompt_by_id(id).unwrap();
+        assert_eq!(prompt.content, "Translate into {{lang}}");
+        assert!(prompt.variables_json.unwrap().contains("lang"));
+        assert!(prompt.app_scopes_json.unwrap().contains("code.exe"));
+        // 写回的文件保留变量定义与应用作用域，再次同步不产生变更
+        let text = std::fs::read_to_string(dir.join("translate.md")).unwrap();
+        assert!(text.contains("variables:") && text.contains("app_scopes:"));
+        assert!(sync_source(&db, &src).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bad_and_duplicate_sources_do_not_block_others() {
+        let dir = temp_dir("sources");
+        std::fs::write(dir.join("ok.md"), "body\n").unwrap();
+        let config = |path: &Path, name: &str| PromptSourceConfig {
+            path: path.to_string_lossy().into_owned(),
+            name: Some(name.to_string()),
+            writable: false,
+        };
+
+        let sources = PromptSource::from_configs(&[
+            config(&dir.join("missing"), "gone"),
+            config(&dir, "team"),
+            config(&dir.join("nested"), "team"),
+        ]);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[1].root, dir.canonicalize().unwrap());
+
+        let db = Database::new(":memory:").unwrap();
+        let report = sync_all(&db, &sources).unwrap();
+        assert_eq!((report.added, report.errors.len()), (1, 1));
+        assert_eq!(db.get_prompts_by_source("team").unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
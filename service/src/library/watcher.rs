@@ -0,0 +1,88 @@
+// Watches mounted prompt directories and re-indexes them on change
+
+use super::{PromptSource, sync_all, sync_source};
+use crate::db::Database;
+use notify::{RecursiveMode, Watcher};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// 连续的文件事件在安静这么久之后才触发一次同步（编辑器保存通常产生多次事件）
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 持有文件监听器；drop 后停止监听，同步线程随之退出
+pub struct LibraryWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl LibraryWatcher {
+    /// 先完整同步一次，然后在后台线程中监听变更
+    ///
+    /// 同步线程使用独立的数据库连接，避免与 GUI / 主循环争用同一连接。
+    pub fn start(
+        db_path: &str,
+        sources: Vec<PromptSource>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let db = Database::new(db_path)?;
+        let report = sync_all(&db, &sources)?;
+        log::info!(
+            "提示词目录初始同步完成: 新增 {}, 更新 {}, 移除 {}, 错误 {}",
+            report.added,
+            report.updated,
+            report.removed,
+            report.errors.len()
+        );
+
+        let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for source in &sources {
+            if let Err(e) = watcher.watch(&source.root, RecursiveMode::Recursive) {
+                log::error!("无法监听提示词目录 {}: {}", source.root.display(), e);
+            }
+        }
+
+        std::thread::spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let mut events = vec![first];
+                while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+                    events.push(event);
+                }
+
+                for source in sources.iter().filter(|s| affects(s, &events)) {
+                    match sync_source(&db, source) {
+                        Ok(report) if !report.is_empty() => log::info!(
+                            "提示词目录 {} 已重新索引: 新增 {}, 更新 {}, 移除 {}, 错误 {}",
+                            source.name,
+                            report.added,
+                            report.updated,
+                            report.removed,
+                            report.errors.len()
+                        ),
+                        Ok(_) => {}
+                        Err(e) => log::error!("重新索引提示词目录 {} 失败: {}", source.name, e),
+                    }
+                }
+            }
+            log::info!("提示词目录监听已停止");
+        });
+
+        Ok(LibraryWatcher { _watcher: watcher })
+    }
+}
+
+// 事件是否涉及该来源中的非隐藏路径（忽略 .git 等目录的变动）
+fn affects(source: &PromptSource, events: &[notify::Result<notify::Event>]) -> bool {
+    events.iter().any(|event| match event {
+        Ok(event) => event.paths.iter().any(|path| {
+            source.contains(path)
+                && !path
+                    .strip_prefix(&source.root)
+                    .map(|rel| {
+                        rel.components()
+                            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+                    })
+                    .unwrap_or(false)
+        }),
+        // 监听出错（例如事件队列溢出）时保守地全部重新同步
+        Err(_) => true,
+    })
+}
@@ -2,12 +2,29 @@
 pub mod config;
 pub mod context;
 pub mod db;
+pub mod diff;
+pub mod events;
+pub mod exchange;
+pub mod expansion;
 pub mod hotkey;
 pub mod injector;
 pub mod ipc;
+pub mod library;
+pub mod richtext;
+pub mod scope;
+pub mod template;
 
-use std::thread;
-use std::time::Duration;
+use config::{
+    CycleDirection, ExpansionConfig, HotkeyAction, HotkeyBinding, PromptRef, SequenceConfig,
+};
+use events::{ServiceEvent, Timer};
+use hotkey::{HotkeyEvent, SequenceStep};
+use ipc::protocol::{ChordFailure, Command, HotkeyRegistration, InjectionOutcome, SequenceHint};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 重新读取提示词缩写的间隔（GUI 中修改的缩写在此时间内生效）
+const ABBREVIATION_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
 
 pub fn run_service() {
     // 初始化日志
@@ -15,81 +32,476 @@ pub fn run_service() {
     println!("🔥 [INTERNAL_ENGINE] 提示词引擎正在子线程启动...");
 
     // 1. 初始化配置 (Moved up to get DB path)
-    let config = crate::config::Config::load().unwrap_or_default();
-    let hotkey_str = config.hotkey.clone();
+    let mut config = crate::config::Config::load().unwrap_or_default();
+    let bindings = config.hotkey_bindings();
 
     // 2. 初始化数据库
     let database = db::Database::new(&config.database_path).expect("无法初始化数据库");
 
+    // 2.1 挂载提示词目录并监听变更（watcher 需在服务运行期间保持存活）
+    let sources = library::PromptSource::from_configs(&config.prompt_sources);
+    let _library_watcher = match library::LibraryWatcher::start(&config.database_path, sources) {
+        Ok(w) => Some(w),
+        Err(e) => {
+            log::error!("提示词目录同步失败: {}", e);
+            None
+        }
+    };
+
     // 3. 初始化注入器
-    let injector = injector::Injector::new(vec![], config.clone());
+    let mut injector = injector::Injector::new(config.clone());
 
     // 3. 初始化上下文管理器
     let context_manager = context::ContextManager::new();
 
-    // 5. 初始化热键服务
-    let mut hotkey_service = hotkey::HotkeyService::new(hotkey_str);
-    if let Err(e) = hotkey_service.start() {
-        log::error!("无法启动热键服务: {}", e);
-    }
+    // 4. 初始化动态变量解析器 ({{date}}, {{app}}, {{clipboard}} ...)
+    let resolvers = template::dynamic::ResolverRegistry::with_builtins();
 
-    // 6. 初始化 IPC 客户端 (用于通知 GUI 显示窗口)
+    // 5. 初始化 IPC 客户端 (用于通知 GUI 显示窗口)
     let ipc_client = ipc::IPCClient::default();
 
+    // 6. 事件总线：各事件源把事件发送到同一个通道，主循环阻塞等待
+    let bus = events::EventBus::new();
+
+    // 6.1 初始化热键服务（含前导键序列），并把注册结果（生效的组合与冲突）告知 GUI
+    let mut hotkey_service =
+        hotkey::HotkeyService::new(bindings.iter().map(HotkeyBinding::candidates).collect());
+    let mut hotkey_actions: Vec<&str> = bindings.iter().map(|b| b.action.name()).collect();
+    let mut sequence_targets = Vec::new();
+    if let Some(sequences) = &config.sequences {
+        let (matcher, targets) = build_sequences(&database, sequences);
+        hotkey_service = hotkey_service.with_sequences(sequences.leader.clone(), matcher);
+        hotkey_actions.push("sequence_leader");
+        sequence_targets = targets;
+    }
+    let show_sequence_hints = config.sequences.as_ref().is_some_and(|s| s.show_hints);
+    match hotkey_service.start() {
+        Ok(registrations) => report_hotkey_status(&ipc_client, &hotkey_actions, registrations),
+        Err(e) => log::error!("无法启动热键服务: {}", e),
+    }
+    if let Some(hotkey_events) = hotkey_service.take_events() {
+        bus.forward(hotkey_events, ServiceEvent::Hotkey);
+    }
+
     // 7. 初始化逻辑注入服务端 (接收来自 GUI 的直接注入请求)
-    let inject_rx = crate::ipc::inject_server::start();
+    bus.forward(crate::ipc::inject_server::start(), ServiceEvent::Inject);
+
+    // 7.1 文本扩展：监听键盘输入，匹配提示词缩写
+    let mut key_monitor_running = false;
+    let mut expander = None;
+    if let Some(expansion_config) = config.expansion.as_ref().filter(|e| e.enabled) {
+        key_monitor_running = start_key_monitor(&bus);
+        if key_monitor_running {
+            expander = Some(new_expander(&database, expansion_config));
+        }
+    }
+    // 早于该时刻的按键是注入期间产生的（X11 下无法与用户输入区分），不参与匹配
+    let mut keys_ignored_before = Instant::now();
+    // 只有启用文本扩展时才需要定期刷新缩写；定时器无法停止，启用后只启动一次
+    let mut refresh_timer_armed = expander.is_some();
+    if refresh_timer_armed {
+        bus.every(ABBREVIATION_REFRESH_INTERVAL, Timer::RefreshAbbreviations);
+    }
+
+    // 7.2 监听配置文件，修改后重新加载（watcher 需在服务运行期间保持存活）
+    let _config_watcher = config::Config::get_config_path()
+        .map_err(|e| e.to_string())
+        .and_then(|path| {
+            bus.watch_config(std::path::Path::new(&path))
+                .map_err(|e| e.to_string())
+        })
+        .map_err(|e| log::error!("无法监听配置文件: {}", e))
+        .ok();
 
     // 8. 进入主循环
     println!("✅ [INTERNAL_ENGINE] 引擎就绪，等待指令...");
 
-    // Store the context (window) that was active before opening the wheel/selector
-    let mut last_active_context: Option<context::AppContext> = None;
-
-    loop {
-        // A. 检查来自 GUI 的点选注入请求
-        while let Ok(prompt_id) = inject_rx.try_recv() {
-            println!("🎯 [ENGINE] 收到 GUI 注入请求: ID={}", prompt_id);
-            // Use the captured context if available, otherwise try to get current (fallback)
-            handle_injection_request(
-                &database,
-                &injector,
-                &context_manager,
-                Some(prompt_id),
-                last_active_context.as_ref(),
-            );
-        }
+    // Store the context (window) that was active before opening the wheel/selector,
+    // together with the chord that opened it
+    let mut last_active_context: Option<(context::AppContext, String)> = None;
+
+    while let Some(event) = bus.recv() {
+        match event {
+            // A. 来自 GUI 的点选注入请求
+            ServiceEvent::Inject(request) => {
+                println!("🎯 [ENGINE] 收到 GUI 注入请求: ID={}", request.prompt_id);
+                // Use the captured context if available, otherwise try to get current (fallback)
+                // 有保存的上下文说明是通过轮盘热键打开的面板；上下文只用于这一次注入
+                let captured = last_active_context.take();
+                let (target, trigger) = match &captured {
+                    Some((ctx, chord)) => (Some(ctx), chord.as_str()),
+                    None => (None, "GUI"),
+                };
+                let outcome = handle_injection_request(
+                    &database,
+                    &injector,
+                    &context_manager,
+                    &resolvers,
+                    PromptChoice::Picked(request.prompt_id),
+                    &request.variables,
+                    target,
+                    trigger,
+                );
+                report_outcome(&ipc_client, outcome);
+                ignore_injected_keys(&mut expander, &mut keys_ignored_before);
+            }
+
+            // B. 热键事件
+            ServiceEvent::Hotkey(HotkeyEvent::Pressed(index)) => {
+                let outcome = dispatch_hotkey(
+                    &bindings[index],
+                    &database,
+                    &injector,
+                    &context_manager,
+                    &resolvers,
+                    &ipc_client,
+                    &mut last_active_context,
+                );
+                if let Some(outcome) = outcome {
+                    report_outcome(&ipc_client, outcome);
+                    ignore_injected_keys(&mut expander, &mut keys_ignored_before);
+                }
+            }
+            ServiceEvent::Hotkey(HotkeyEvent::Sequence(step)) => {
+                if show_sequence_hints {
+                    send_sequence_hints(&ipc_client, &step);
+                }
+                if let SequenceStep::Matched(i) = step {
+                    let (prompt, trigger) = &sequence_targets[i];
+                    println!("⌨️ [HOTKEY] 按键序列: {}", trigger);
+                    let outcome = match resolve_prompt(&database, prompt, trigger) {
+                        Ok(choice) => handle_injection_request(
+                            &database,
+                            &injector,
+                            &context_manager,
+                            &resolvers,
+                            choice,
+                            &HashMap::new(),
+                            None,
+                            trigger,
+                        ),
+                        Err(outcome) => outcome,
+                    };
+                    report_outcome(&ipc_client, outcome);
+                    ignore_injected_keys(&mut expander, &mut keys_ignored_before);
+                }
+            }
+
+            // C. 键盘输入是否完成了某个缩写
+            ServiceEvent::Key(input, received) => {
+                if received < keys_ignored_before {
+                    continue;
+                }
+                let Some(matched) = expander.as_mut().and_then(|e| e.feed(input)) else {
+                    continue;
+                };
+                let app_name = context_manager
+                    .get_foreground_context()
+                    .map(|c| c.process_name)
+                    .unwrap_or_default();
+                if !config.expansion_enabled_for(&app_name) {
+                    continue;
+                }
+                println!("⌨️ [EXPANSION] 缩写: {}", matched.abbreviation);
+                let choice = PromptChoice::Abbreviation {
+                    id: matched.prompt_id,
+                    erase: matched.erase,
+                };
+                let outcome = handle_injection_request(
+                    &database,
+                    &injector,
+                    &context_manager,
+                    &resolvers,
+                    choice,
+                    &HashMap::new(),
+                    None,
+                    &matched.abbreviation,
+                );
+                report_outcome(&ipc_client, outcome);
+                ignore_injected_keys(&mut expander, &mut keys_ignored_before);
+            }
 
-        // B. 检查热键事件
-        while let Some(hotkey_id) = hotkey_service.try_wait_for_hotkey() {
-            match hotkey_id {
-                4 => {
-                    println!("🎡 [HOTKEY] 触发提示词轮盘");
-                    // Capture context before showing GUI
-                    if let Ok(ctx) = context_manager.get_foreground_context() {
-                        println!(
-                            "💾 保存上下文: App={}, Title={}",
-                            ctx.process_name, ctx.window_title
-                        );
-                        last_active_context = Some(ctx);
+            ServiceEvent::Timer(Timer::RefreshAbbreviations) => {
+                if let Some(expander) = expander.as_mut() {
+                    expander.set_abbreviations(load_abbreviations(&database));
+                }
+            }
+
+            // D. 配置文件修改：注入策略与文本扩展立即生效，热键需重启服务
+            ServiceEvent::ConfigChanged => {
+                let reloaded = match config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        log::error!("重新加载配置失败: {}", e);
+                        continue;
+                    }
+                };
+                if reloaded.hotkey_bindings() != bindings || reloaded.sequences != config.sequences
+                {
+                    log::warn!("热键与按键序列的修改需重启服务后生效");
+                }
+                expander = None;
+                if let Some(expansion_config) = reloaded.expansion.as_ref().filter(|e| e.enabled) {
+                    if !key_monitor_running {
+                        key_monitor_running = start_key_monitor(&bus);
+                    }
+                    if key_monitor_running {
+                        expander = Some(new_expander(&database, expansion_config));
                     }
-                    let _ = ipc_client.send_show_wheel();
                 }
-                _ => {}
+                if expander.is_some() && !refresh_timer_armed {
+                    bus.every(ABBREVIATION_REFRESH_INTERVAL, Timer::RefreshAbbreviations);
+                    refresh_timer_armed = true;
+                }
+                injector = injector::Injector::new(reloaded.clone());
+                config = reloaded;
+                println!("🔄 [ENGINE] 配置已重新加载");
             }
         }
+    }
+}
 
-        // 防止空转
-        thread::sleep(Duration::from_millis(10));
+/// 注入结束后调用：X11 下模拟输入产生的按键会回到键盘监听，
+/// 忽略此前收到的按键并清空缓冲区，以免注入的文本触发缩写
+fn ignore_injected_keys(
+    expander: &mut Option<expansion::Expander>,
+    keys_ignored_before: &mut Instant,
+) {
+    *keys_ignored_before = Instant::now();
+    if let Some(expander) = expander.as_mut() {
+        expander.reset();
     }
 }
 
+/// 执行热键绑定的动作；注入类动作返回注入结果
+fn dispatch_hotkey(
+    binding: &HotkeyBinding,
+    db: &db::Database,
+    injector: &injector::Injector,
+    ctx: &context::ContextManager,
+    resolvers: &template::dynamic::ResolverRegistry,
+    ipc_client: &ipc::IPCClient,
+    last_active_context: &mut Option<(context::AppContext, String)>,
+) -> Option<InjectionOutcome> {
+    let chord = binding.chord.as_str();
+    let choice = match &binding.action {
+        HotkeyAction::ShowWheel | HotkeyAction::ShowSelector => {
+            // Capture context before showing GUI
+            if let Ok(app_ctx) = ctx.get_foreground_context() {
+                println!(
+                    "💾 保存上下文: App={}, Title={}",
+                    app_ctx.process_name, app_ctx.window_title
+                );
+                *last_active_context = Some((app_ctx, chord.to_string()));
+            }
+            let _ = if binding.action == HotkeyAction::ShowWheel {
+                println!("🎡 [HOTKEY] 触发提示词轮盘");
+                ipc_client.send_show_wheel()
+            } else {
+                println!("📋 [HOTKEY] 打开快速选择面板");
+                ipc_client.send_show_selector()
+            };
+            return None;
+        }
+        HotkeyAction::CyclePrompt { direction } => {
+            match db.cycle_selected_prompt(*direction == CycleDirection::Next) {
+                Ok(Some(prompt)) => println!("🔁 [HOTKEY] 已选中提示词: {}", prompt.name),
+                Ok(None) => println!("⚠️ 没有可选择的提示词"),
+                Err(e) => log::error!("切换选中的提示词失败: {}", e),
+            }
+            return None;
+        }
+        HotkeyAction::InjectForContext => {
+            println!("⚡ [HOTKEY] 按当前应用自动注入");
+            PromptChoice::ForContext
+        }
+        HotkeyAction::InjectSelected => match db.get_selected_prompt_id() {
+            Ok(id) if id > 0 => PromptChoice::Bound(id),
+            Ok(_) => {
+                return Some(InjectionOutcome::failed(
+                    None,
+                    None,
+                    "尚未选中提示词".to_string(),
+                ));
+            }
+            Err(e) => {
+                return Some(InjectionOutcome::failed(
+                    None,
+                    None,
+                    format!("读取选中的提示词失败: {}", e),
+                ));
+            }
+        },
+        HotkeyAction::InjectPrompt { prompt } => match resolve_prompt(db, prompt, chord) {
+            Ok(choice) => choice,
+            Err(outcome) => return Some(outcome),
+        },
+    };
+
+    Some(handle_injection_request(
+        db,
+        injector,
+        ctx,
+        resolvers,
+        choice,
+        &HashMap::new(),
+        None,
+        chord,
+    ))
+}
+
+/// 把热键或按键序列绑定的提示词（ID 或名称）解析为注入请求
+fn resolve_prompt(
+    db: &db::Database,
+    prompt: &PromptRef,
+    trigger: &str,
+) -> Result<PromptChoice, InjectionOutcome> {
+    match prompt {
+        PromptRef::Id(id) => Ok(PromptChoice::Bound(*id)),
+        PromptRef::Name(name) => match db.get_prompt_by_name(name).map(|p| p.id) {
+            Ok(Some(id)) => Ok(PromptChoice::Bound(id)),
+            Ok(None) | Err(_) => {
+                log::error!("{} 绑定的提示词不存在: {}", trigger, name);
+                Err(InjectionOutcome::failed(
+                    None,
+                    Some(name.clone()),
+                    format!("找不到提示词: {}", name),
+                ))
+            }
+        },
+    }
+}
+
+/// 解析前导键序列配置，无法解析的序列记录错误后跳过。
+/// 返回状态机，以及每个序列对应的提示词与使用日志中的触发方式（如 "Ctrl+Alt+P r v"）
+fn build_sequences(
+    db: &db::Database,
+    config: &SequenceConfig,
+) -> (hotkey::SequenceMatcher, Vec<(PromptRef, String)>) {
+    let mut sequences = Vec::new();
+    let mut targets = Vec::new();
+    for binding in &config.bindings {
+        // 提示中显示提示词名称
+        let label = match &binding.prompt {
+            PromptRef::Name(name) => name.clone(),
+            PromptRef::Id(id) => db
+                .get_prompt_by_id(*id)
+                .map(|p| p.name)
+                .unwrap_or_else(|_| format!("#{}", id)),
+        };
+        match hotkey::Sequence::parse(&binding.keys, &label) {
+            Ok(sequence) => {
+                sequences.push(sequence);
+                let trigger = format!("{} {}", config.leader, binding.keys.trim());
+                targets.push((binding.prompt.clone(), trigger));
+            }
+            Err(e) => log::error!("无法解析按键序列 \"{}\": {}", binding.keys, e),
+        }
+    }
+    let timeout = Duration::from_millis(config.timeout_ms);
+    (hotkey::SequenceMatcher::new(sequences, timeout), targets)
+}
+
+/// 序列进行中在 GUI 显示可用的后续按键，结束（触发或取消）时隐藏
+fn send_sequence_hints(ipc_client: &ipc::IPCClient, step: &SequenceStep) {
+    let (pressed, hints) = match step {
+        SequenceStep::Pending {
+            pressed,
+            continuations,
+        } => (
+            pressed
+                .iter()
+                .map(|k| k.name())
+                .collect::<Vec<_>>()
+                .join(" "),
+            continuations
+                .iter()
+                .map(|c| SequenceHint {
+                    key: c.key.name(),
+                    label: c.label.clone(),
+                    more: c.more,
+                })
+                .collect(),
+        ),
+        _ => (String::new(), Vec::new()),
+    };
+    if let Err(e) = ipc_client.send_command(Command::SequenceHints { pressed, hints }) {
+        log::debug!("无法发送按键序列提示: {}", e);
+    }
+}
+
+/// 启动键盘监听并把输入接入事件总线；失败时返回 false
+fn start_key_monitor(bus: &events::EventBus) -> bool {
+    match expansion::start_monitor() {
+        Ok(key_rx) => {
+            bus.forward(key_rx, |input| ServiceEvent::Key(input, Instant::now()));
+            true
+        }
+        Err(e) => {
+            log::error!("无法启动文本扩展的键盘监听: {}", e);
+            false
+        }
+    }
+}
+
+fn new_expander(db: &db::Database, config: &ExpansionConfig) -> expansion::Expander {
+    let abbreviations = load_abbreviations(db);
+    println!("⌨️ [EXPANSION] 已加载 {} 个缩写", abbreviations.len());
+    expansion::Expander::new(config, abbreviations)
+}
+
+fn load_abbreviations(db: &db::Database) -> Vec<(i32, String)> {
+    db.list_abbreviations().unwrap_or_else(|e| {
+        log::error!("读取提示词缩写失败: {}", e);
+        Vec::new()
+    })
+}
+
+/// 要注入的提示词从何而来
+#[derive(Debug, Clone, Copy)]
+enum PromptChoice {
+    /// 在 GUI 轮盘/面板中点选
+    Picked(i32),
+    /// 热键绑定的指定提示词或选中的提示词
+    Bound(i32),
+    /// 按当前应用自动匹配
+    ForContext,
+    /// 输入了提示词的缩写：注入前先删除 erase 个已输入的字符
+    Abbreviation { id: i32, erase: usize },
+}
+
+impl PromptChoice {
+    fn prompt_id(self) -> Option<i32> {
+        match self {
+            PromptChoice::Picked(id)
+            | PromptChoice::Bound(id)
+            | PromptChoice::Abbreviation { id, .. } => Some(id),
+            PromptChoice::ForContext => None,
+        }
+    }
+
+    /// 使用日志中的 action
+    fn action(self) -> &'static str {
+        match self {
+            PromptChoice::Picked(_) => "wheel_select",
+            PromptChoice::Bound(_) => "hotkey_prompt",
+            PromptChoice::ForContext => "hotkey_inject",
+            PromptChoice::Abbreviation { .. } => "abbreviation",
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_injection_request(
     db: &db::Database,
     injector: &injector::Injector,
     ctx: &context::ContextManager,
-    force_id: Option<i32>,
+    resolvers: &template::dynamic::ResolverRegistry,
+    choice: PromptChoice,
+    variables: &HashMap<String, String>,
     target_override: Option<&context::AppContext>,
-) {
+    hotkey_used: &str,
+) -> InjectionOutcome {
     // 1. 获取目标上下文
     // 如果有 override (来自轮盘/面板调用)，使用保存的上下文；否则获取当前上下文
     let context = if let Some(override_ctx) = target_override {
@@ -97,34 +509,34 @@ fn handle_injection_request(
         override_ctx.clone()
     } else {
         ctx.get_foreground_context()
-            .unwrap_or(crate::context::AppContext {
-                process_name: "Unknown".to_string(),
-                window_title: "Unknown".to_string(),
-                window_handle: windows::Win32::Foundation::HWND(std::ptr::null_mut()),
-            })
+            .unwrap_or_else(|_| context::AppContext::unknown())
     };
 
     let app_name = context.process_name.clone();
     let window_title = context.window_title.clone();
 
     log::info!(
-        "⚡ 处理注入请求 | App: {} | Title: {} | ForceID: {:?}",
+        "⚡ 处理注入请求 | App: {} | Title: {} | Choice: {:?}",
         app_name,
         window_title,
-        force_id
+        choice
     );
 
     // 2. 确定要使用的 Prompt
-    let prompt_result = if let Some(id) = force_id {
-        // A. 强制指定模式 (来自 UI 选择)
-        db.get_prompt_by_id(id).map(|p| (p, "wheel_select"))
+    let prompt_result = if let Some(id) = choice.prompt_id() {
+        // A. 强制指定模式 (来自 UI 选择或热键绑定)
+        db.get_prompt_by_id(id).map(|p| (p, choice.action()))
     } else {
         // B. 自动匹配模式 (来自快捷键)
         match db.find_prompt_for_context(&app_name, &window_title) {
-            Ok(Some(p)) => Ok((p, "hotkey_inject")),
+            Ok(Some(p)) => Ok((p, choice.action())),
             Ok(None) => {
                 println!("⚠️ 当前上下文没有匹配的提示词");
-                return;
+                return InjectionOutcome::failed(
+                    None,
+                    None,
+                    format!("当前应用 ({}) 没有匹配的提示词", app_name),
+                );
             }
             Err(e) => Err(e),
         }
@@ -133,24 +545,56 @@ fn handle_injection_request(
     // 3. 执行注入
     match prompt_result {
         Ok((prompt, action_type)) => {
-            println!("✨ 正在注入: [{}] {}", prompt.name, prompt.content);
-
-            // 记录使用日志
-            if let Err(e) = db.log_usage(
-                prompt.id,
-                &prompt.name,
-                &app_name,
-                &window_title,
-                "Internal",
-                "Internal",
-                0,
-                true,
-                None,
-                "Injected",
-                action_type,
+            // 渲染模板变量；仍有未填写的变量时不注入，由 GUI 询问后重新发起
+            let clipboard_reader = || injector.read_clipboard_text();
+            let dynamic_ctx = template::dynamic::DynamicContext::new(&app_name, &window_title)
+                .with_clipboard(&clipboard_reader);
+            // 富文本提示词中的变量值（含剪贴板内容）按原样显示，不解释为标记
+            let content_type = richtext::ContentType::parse(prompt.content_type.as_deref());
+            let text = match template::render_escaped(
+                &prompt.content,
+                prompt.variables_json.as_deref(),
+                variables,
+                Some((resolvers, &dynamic_ctx)),
+                &|v| content_type.escape(v),
             ) {
-                log::error!("无法记录使用日志: {}", e);
-            }
+                Ok(template::RenderOutcome::Rendered { text }) => text,
+                Ok(template::RenderOutcome::NeedsInput { variables }) => {
+                    let names: Vec<&str> = variables.iter().map(|v| v.name.as_str()).collect();
+                    log::warn!("提示词 [{}] 需要填写变量: {:?}", prompt.name, names);
+                    println!("⚠️ 提示词需要填写变量，已跳过注入: {:?}", names);
+                    return InjectionOutcome::failed(
+                        prompt.id,
+                        Some(prompt.name.clone()),
+                        format!("需要填写变量: {}", names.join(", ")),
+                    );
+                }
+                Err(e) => {
+                    log::error!("渲染提示词模板失败: {}", e);
+                    let error = format!("渲染提示词模板失败: {}", e);
+                    log_usage(
+                        db,
+                        &db::UsageRecord {
+                            prompt_id: prompt.id,
+                            prompt_name: &prompt.name,
+                            target_app: &app_name,
+                            window_title: &window_title,
+                            hotkey_used,
+                            error: Some(&error),
+                            result: "Render failed",
+                            action: action_type,
+                            ..Default::default()
+                        },
+                    );
+                    return InjectionOutcome::failed(prompt.id, Some(prompt.name.clone()), error);
+                }
+            };
+
+            println!("✨ 正在注入: [{}] {}", prompt.name, text);
+
+            // markdown / html 提示词以富文本（附带纯文本后备）注入
+            let content =
+                richtext::FormattedText::from_content(&text, prompt.content_type.as_deref());
 
             // 构造注入上下文
             let injection_ctx = injector::InjectionContext {
@@ -159,20 +603,110 @@ fn handle_injection_request(
                 window_handle: context.window_handle,
             };
 
+            // 文本扩展：先删除已输入的缩写与分隔符
+            if let PromptChoice::Abbreviation { erase, .. } = choice
+                && let Err(e) = injector.erase(erase, &injection_ctx)
+            {
+                log::error!("删除缩写失败: {}", e);
+                return InjectionOutcome::failed(
+                    prompt.id,
+                    Some(prompt.name.clone()),
+                    format!("删除缩写失败: {}", e),
+                );
+            }
+
             // 调用注入器
-            if let Err(e) = injector.inject(&prompt.content, &injection_ctx) {
-                log::error!("❌ 注入失败: {}", e);
-                println!("❌ 注入失败: {}", e);
-            } else {
+            let report = injector.inject_formatted(&content, &injection_ctx);
+            let strategy = report.strategy().map(str::to_string);
+            let error = report.error();
+
+            // 记录使用日志（实际策略、尝试链与耗时）
+            log_usage(
+                db,
+                &db::UsageRecord {
+                    prompt_id: prompt.id,
+                    prompt_name: &prompt.name,
+                    target_app: &app_name,
+                    window_title: &window_title,
+                    hotkey_used,
+                    strategy: strategy.as_deref().unwrap_or_default(),
+                    fallback_chain: &report.fallback_chain(),
+                    injection_time_ms: report.elapsed_ms,
+                    success: report.succeeded(),
+                    error: error.as_deref(),
+                    result: if report.succeeded() {
+                        "Injected"
+                    } else {
+                        "Failed"
+                    },
+                    action: action_type,
+                },
+            );
+
+            if report.succeeded() {
                 println!("✅ 注入成功");
+            } else {
+                log::error!("❌ 注入失败: {}", error.as_deref().unwrap_or_default());
+                println!("❌ 注入失败: {}", error.as_deref().unwrap_or_default());
+            }
+            InjectionOutcome {
+                prompt_id: prompt.id,
+                prompt_name: Some(prompt.name),
+                success: report.succeeded(),
+                strategy,
+                elapsed_ms: report.elapsed_ms,
+                error: if report.succeeded() { None } else { error },
             }
         }
         Err(e) => {
             log::error!("查询提示词失败: {}", e);
+            InjectionOutcome::failed(choice.prompt_id(), None, format!("查询提示词失败: {}", e))
         }
     }
 }
 
+fn log_usage(db: &db::Database, usage: &db::UsageRecord) {
+    if let Err(e) = db.log_usage(usage) {
+        log::error!("无法记录使用日志: {}", e);
+    }
+}
+
+/// 将注入结果发送给 GUI（以 toast 形式展示）；GUI 未运行时忽略
+fn report_outcome(ipc_client: &ipc::IPCClient, outcome: InjectionOutcome) {
+    if let Err(e) = ipc_client.send_command(Command::InjectionOutcome(outcome)) {
+        log::debug!("无法发送注入结果: {}", e);
+    }
+}
+
+/// 将热键注册结果发送给 GUI（设置页据此提示冲突）；GUI 未运行时忽略
+fn report_hotkey_status(
+    ipc_client: &ipc::IPCClient,
+    actions: &[&str],
+    registrations: Vec<hotkey::Registration>,
+) {
+    let registrations = actions
+        .iter()
+        .zip(registrations)
+        .map(|(action, registration)| {
+            if registration.active.is_none() {
+                println!("⚠️ [HOTKEY] {} 的所有候选热键均注册失败", action);
+            }
+            HotkeyRegistration {
+                action: action.to_string(),
+                active: registration.active,
+                failures: registration
+                    .failures
+                    .into_iter()
+                    .map(|(chord, error)| ChordFailure { chord, error })
+                    .collect(),
+            }
+        })
+        .collect();
+    if let Err(e) = ipc_client.send_command(Command::HotkeyStatus { registrations }) {
+        log::debug!("无法发送热键注册结果: {}", e);
+    }
+}
+
 // 为了作为二进制文件运行时兼容
 #[allow(dead_code)]
 fn main() {
@@ -0,0 +1,203 @@
+// HTML → plain text
+// html 提示词的纯文本后备：去掉标签、解码实体，块级元素转换为换行。
+
+/// 前后各空一行的块级元素
+const PARAGRAPH_TAGS: [&str; 13] = [
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+    "ul",
+    "ol",
+    "table",
+    "hr",
+];
+/// 另起一行的块级元素
+const LINE_TAGS: [&str; 9] = [
+    "div", "tr", "section", "article", "header", "footer", "dt", "dd", "figure",
+];
+/// 内容不显示的元素
+const HIDDEN_TAGS: [&str; 4] = ["script", "style", "head", "title"];
+
+pub fn to_plain(html: &str) -> String {
+    let mut out = String::new();
+    let mut in_pre = false;
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        push_text(&decode_entities(&rest[..lt]), in_pre, &mut out);
+        let tag = &rest[lt..];
+        if let Some(comment) = tag.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(gt) = tag.find('>') else {
+            // 没有闭合的 '<' 按文本处理
+            push_text(&decode_entities(tag), in_pre, &mut out);
+            rest = "";
+            break;
+        };
+        let (name, closing) = tag_name(&tag[1..gt]);
+        rest = &tag[gt + 1..];
+
+        match name.as_str() {
+            name if HIDDEN_TAGS.contains(&name) && !closing => {
+                let end = rest
+                    .to_ascii_lowercase()
+                    .find(&format!("</{}", name))
+                    .map_or(rest.len(), |end| {
+                        rest[end..].find('>').map_or(rest.len(), |gt| end + gt + 1)
+                    });
+                rest = &rest[end..];
+            }
+            "br" => out.push('\n'),
+            "li" if !closing => {
+                line_break(&mut out);
+                out.push_str("- ");
+            }
+            "td" | "th" if !closing && !out.is_empty() && !out.ends_with('\n') => {
+                out.push('\t');
+            }
+            "pre" => {
+                in_pre = !closing;
+                paragraph_break(&mut out);
+            }
+            name if PARAGRAPH_TAGS.contains(&name) => paragraph_break(&mut out),
+            name if LINE_TAGS.contains(&name) || name == "li" => line_break(&mut out),
+            _ => {}
+        }
+    }
+    push_text(&decode_entities(rest), in_pre, &mut out);
+
+    out.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// 标签名（小写）及是否为结束标签；"!doctype" 等声明返回空名称
+fn tag_name(tag: &str) -> (String, bool) {
+    let (tag, closing) = match tag.strip_prefix('/') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let name = tag
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    (name, closing)
+}
+
+/// 追加文本；<pre> 之外把连续空白合并为一个空格
+fn push_text(text: &str, in_pre: bool, out: &mut String) {
+    if in_pre {
+        out.push_str(text);
+        return;
+    }
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !out.is_empty() && !out.ends_with([' ', '\n', '\t']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+fn line_break(out: &mut String) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn paragraph_break(out: &mut String) {
+    line_break(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// 把文本转义为 HTML 字符数据（也可用于属性值）
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest
+            .get(1..12.min(rest.len()))
+            .and_then(|s| s.find(';'))
+            .and_then(|end| decode_entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    let code = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(dec) = name.strip_prefix('#') {
+        dec.parse().ok()?
+    } else {
+        return match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => None,
+        };
+    };
+    char::from_u32(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_plain() {
+        let html = "<!DOCTYPE html><html><head><style>p { color: red }</style></head><body>\n\
+                    <h1>Title</h1>\n<p>Hello,   <b>world</b>&#33;<br>next &lt;line&gt;</p>\
+                    <!-- note --><ul><li>one</li><li>two &amp; three</li></ul>\
+                    <pre>  keep\n    indent</pre><div>a</div><div>b</div></body></html>";
+        assert_eq!(
+            to_plain(html),
+            "Title\n\nHello, world!\nnext <line>\n\n- one\n- two & three\n\n  keep\n    indent\n\na\nb"
+        );
+    }
+
+    #[test]
+    fn test_unknown_entities_kept() {
+        assert_eq!(to_plain("R&D &bogus; 1 < 2"), "R&D &bogus; 1 < 2");
+    }
+}
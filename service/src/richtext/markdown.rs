@@ -0,0 +1,510 @@
+// Markdown renderer
+// 支持提示词中常用的 CommonMark 子集：标题、段落、强调、行内代码、代码块、列表、引用、链接、分隔线。
+// 不支持嵌套列表（缩进的子项按同级处理）、表格与内嵌 HTML（按普通文本转义）。
+
+use super::html::escape;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    Strong(Vec<Inline>),
+    Emphasis(Vec<Inline>),
+    Code(String),
+    Link {
+        text: Vec<Inline>,
+        url: String,
+    },
+    /// 段落内的普通换行
+    SoftBreak,
+    /// 行尾两个空格或反斜杠表示的强制换行
+    HardBreak,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        text: Vec<Inline>,
+    },
+    Paragraph(Vec<Inline>),
+    CodeBlock {
+        lang: Option<String>,
+        code: String,
+    },
+    Quote(Vec<Block>),
+    /// start 为 None 表示无序列表
+    List {
+        start: Option<u32>,
+        items: Vec<Vec<Inline>>,
+    },
+    Rule,
+}
+
+pub fn parse(text: &str) -> Vec<Block> {
+    let lines: Vec<&str> = text.lines().collect();
+    parse_lines(&lines)
+}
+
+fn parse_lines(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        if line.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(fence) = fence(line) {
+            let lang = line[fence.len()..].trim();
+            let mut code = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                code.push(lines[i]);
+                i += 1;
+            }
+            // 跳过结束 fence（缺失时代码块延续到文末）
+            i += 1;
+            blocks.push(Block::CodeBlock {
+                lang: (!lang.is_empty()).then(|| lang.to_string()),
+                code: code.join("\n"),
+            });
+        } else if let Some((level, title)) = heading(line) {
+            blocks.push(Block::Heading {
+                level,
+                text: parse_inlines(title),
+            });
+            i += 1;
+        } else if is_rule(line) {
+            blocks.push(Block::Rule);
+            i += 1;
+        } else if line.starts_with('>') {
+            let mut quoted = Vec::new();
+            while let Some(rest) = lines.get(i).and_then(|l| l.trim_start().strip_prefix('>')) {
+                quoted.push(rest.strip_prefix(' ').unwrap_or(rest));
+                i += 1;
+            }
+            blocks.push(Block::Quote(parse_lines(&quoted)));
+        } else if let Some((start, _)) = list_marker(line) {
+            let ordered = start.is_some();
+            let same_kind = |l: &str| list_marker(l).is_some_and(|(s, _)| s.is_some() == ordered);
+            let mut items: Vec<Vec<&str>> = Vec::new();
+            while i < lines.len() {
+                let line = lines[i].trim_start();
+                if line.is_empty() {
+                    // 空行后紧跟同类列表项时仍属于同一个列表
+                    if lines.get(i + 1).is_some_and(|l| same_kind(l.trim_start())) {
+                        i += 1;
+                        continue;
+                    }
+                    break;
+                }
+                match list_marker(line) {
+                    Some((_, content)) if same_kind(line) => items.push(vec![content]),
+                    Some(_) => break,
+                    None if starts_block(line) => break,
+                    // 续行属于上一个列表项
+                    None => items.last_mut().expect("list has an item").push(line),
+                }
+                i += 1;
+            }
+            blocks.push(Block::List {
+                start,
+                items: items
+                    .iter()
+                    .map(|item| parse_inlines(&item.join("\n")))
+                    .collect(),
+            });
+        } else {
+            let mut paragraph = vec![line];
+            i += 1;
+            while let Some(line) = lines.get(i).map(|l| l.trim_start()) {
+                if line.is_empty() || starts_block(line) {
+                    break;
+                }
+                paragraph.push(line);
+                i += 1;
+            }
+            blocks.push(Block::Paragraph(parse_inlines(&paragraph.join("\n"))));
+        }
+    }
+    blocks
+}
+
+fn fence(line: &str) -> Option<&'static str> {
+    ["```", "~~~"].into_iter().find(|f| line.starts_with(f))
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((level as u8, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let mut marks = line.chars().filter(|c| !c.is_whitespace());
+    let Some(first) = marks.next() else {
+        return false;
+    };
+    matches!(first, '-' | '*' | '_') && marks.clone().all(|c| c == first) && marks.count() >= 2
+}
+
+/// 列表项标记："- "、"* "、"+ " 或 "1. "、"1) "；返回 (有序列表的序号, 内容)
+fn list_marker(line: &str) -> Option<(Option<u32>, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some((None, rest));
+        }
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if !(1..=9).contains(&digits) {
+        return None;
+    }
+    let rest = line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))?;
+    Some((line[..digits].parse().ok(), rest))
+}
+
+fn starts_block(line: &str) -> bool {
+    fence(line).is_some()
+        || heading(line).is_some()
+        || is_rule(line)
+        || line.starts_with('>')
+        || list_marker(line).is_some()
+}
+
+pub fn parse_inlines(text: &str) -> Vec<Inline> {
+    let mut out = Vec::new();
+    let mut buf = String::new();
+    let mut rest = text;
+
+    fn flush(buf: &mut String, out: &mut Vec<Inline>) {
+        if !buf.is_empty() {
+            out.push(Inline::Text(std::mem::take(buf)));
+        }
+    }
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\\' => match rest[1..].chars().next() {
+                Some('\n') => {
+                    flush(&mut buf, &mut out);
+                    out.push(Inline::HardBreak);
+                    rest = &rest[2..];
+                    continue;
+                }
+                Some(next) if next.is_ascii_punctuation() => {
+                    buf.push(next);
+                    rest = &rest[2..];
+                    continue;
+                }
+                _ => {}
+            },
+            '\n' => {
+                let hard = buf.ends_with("  ");
+                buf.truncate(buf.trim_end_matches(' ').len());
+                flush(&mut buf, &mut out);
+                out.push(if hard {
+                    Inline::HardBreak
+                } else {
+                    Inline::SoftBreak
+                });
+                rest = &rest[1..];
+                continue;
+            }
+            '`' => {
+                let ticks = rest.len() - rest.trim_start_matches('`').len();
+                let delim = &rest[..ticks];
+                rest = &rest[ticks..];
+                if let Some(end) = rest.find(delim) {
+                    let code = &rest[..end];
+                    // 两侧各有一个空格时去掉（用于包含反引号的代码）
+                    let code = code
+                        .strip_prefix(' ')
+                        .and_then(|c| c.strip_suffix(' '))
+                        .filter(|c| !c.is_empty())
+                        .unwrap_or(code);
+                    flush(&mut buf, &mut out);
+                    out.push(Inline::Code(code.to_string()));
+                    rest = &rest[end + ticks..];
+                } else {
+                    buf.push_str(delim);
+                }
+                continue;
+            }
+            '*' | '_' => {
+                let len = if rest[1..].starts_with(c) { 2 } else { 1 };
+                let delim = &rest[..len];
+                // '_' 只在词首生效，snake_case 不是强调
+                let opens = c == '*' || !buf.chars().last().is_some_and(char::is_alphanumeric);
+                if opens && let Some(end) = closing_delimiter(&rest[len..], delim) {
+                    let inner = parse_inlines(&rest[len..len + end]);
+                    flush(&mut buf, &mut out);
+                    out.push(if len == 2 {
+                        Inline::Strong(inner)
+                    } else {
+                        Inline::Emphasis(inner)
+                    });
+                    rest = &rest[len + end + len..];
+                } else {
+                    buf.push_str(delim);
+                    rest = &rest[len..];
+                }
+                continue;
+            }
+            '[' => {
+                if let Some((label, url, len)) = link(rest) {
+                    flush(&mut buf, &mut out);
+                    out.push(Inline::Link {
+                        text: parse_inlines(label),
+                        url: url.to_string(),
+                    });
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        buf.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    flush(&mut buf, &mut out);
+    out
+}
+
+/// 在 text 中查找与开头 delim 配对的结束位置；内容不能为空或以空白开头/结尾
+fn closing_delimiter(text: &str, delim: &str) -> Option<usize> {
+    if text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mark = delim.chars().next()?;
+    text.char_indices().map(|(i, _)| i).find(|&i| {
+        if i == 0 || !text[i..].starts_with(delim) {
+            return false;
+        }
+        let before = text[..i].chars().last();
+        let after = text[i + delim.len()..].chars().next();
+        !before.is_some_and(char::is_whitespace)
+            // 取连续标记的最后一段（"***" 中结束 ** 的是后两个）；单个 * 不能是 ** 的一部分
+            && after != Some(mark)
+            && (delim.len() == 2 || before != Some(mark))
+            && !(mark == '_' && after.is_some_and(char::is_alphanumeric))
+    })
+}
+
+/// 解析 "[label](url "title")"，返回 (label, url, 总长度)
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let mut depth = 0;
+    let close = text.char_indices().find_map(|(i, c)| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {}
+        }
+        (depth == 0).then_some(i)
+    })?;
+    let target = text[close + 1..].strip_prefix('(')?;
+    let end = target.find(')')?;
+    let url = target[..end].split_whitespace().next().unwrap_or_default();
+    Some((&text[1..close], url, close + 2 + end + 1))
+}
+
+pub fn to_html(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        block_html(block, &mut out);
+    }
+    out
+}
+
+fn block_html(block: &Block, out: &mut String) {
+    match block {
+        Block::Heading { level, text } => {
+            let _ = writeln!(out, "<h{0}>{1}</h{0}>", level, inlines_html(text));
+        }
+        Block::Paragraph(text) => {
+            let _ = writeln!(out, "<p>{}</p>", inlines_html(text));
+        }
+        Block::CodeBlock { lang, code } => {
+            match lang {
+                Some(lang) => {
+                    let _ = write!(out, "<pre><code class=\"language-{}\">", escape(lang));
+                }
+                None => out.push_str("<pre><code>"),
+            }
+            out.push_str(&escape(code));
+            if !code.is_empty() {
+                out.push('\n');
+            }
+            out.push_str("</code></pre>\n");
+        }
+        Block::Quote(inner) => {
+            let _ = write!(out, "<blockquote>\n{}</blockquote>\n", to_html(inner));
+        }
+        Block::List { start, items } => {
+            let tag = if start.is_some() { "ol" } else { "ul" };
+            match start {
+                Some(n) if *n != 1 => {
+                    let _ = writeln!(out, "<ol start=\"{}\">", n);
+                }
+                _ => {
+                    let _ = writeln!(out, "<{}>", tag);
+                }
+            }
+            for item in items {
+                let _ = writeln!(out, "<li>{}</li>", inlines_html(item));
+            }
+            let _ = writeln!(out, "</{}>", tag);
+        }
+        Block::Rule => out.push_str("<hr />\n"),
+    }
+}
+
+fn inlines_html(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape(text),
+            Inline::Strong(inner) => format!("<strong>{}</strong>", inlines_html(inner)),
+            Inline::Emphasis(inner) => format!("<em>{}</em>", inlines_html(inner)),
+            Inline::Code(code) => format!("<code>{}</code>", escape(code)),
+            Inline::Link { text, url } => {
+                format!("<a href=\"{}\">{}</a>", escape(url), inlines_html(text))
+            }
+            Inline::SoftBreak => "\n".to_string(),
+            Inline::HardBreak => "<br />\n".to_string(),
+        })
+        .collect()
+}
+
+/// 用反斜杠转义 ASCII 标点，使文本插入 Markdown 后按原样显示
+pub fn escape_literal(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// 去掉 Markdown 标记的纯文本（保留列表序号与换行）
+pub fn to_plain(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading { text, .. } | Block::Paragraph(text) => inlines_plain(text),
+            Block::CodeBlock { code, .. } => code.clone(),
+            Block::Quote(inner) => to_plain(inner),
+            Block::List { start, items } => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| match start {
+                    Some(n) => format!("{}. {}", n + i as u32, inlines_plain(item)),
+                    None => format!("- {}", inlines_plain(item)),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Rule => "---".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn inlines_plain(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) | Inline::Code(text) => text.clone(),
+            Inline::Strong(inner) | Inline::Emphasis(inner) => inlines_plain(inner),
+            Inline::Link { text, url } => {
+                let label = inlines_plain(text);
+                if label.is_empty() || label == *url {
+                    url.clone()
+                } else {
+                    format!("{} ({})", label, url)
+                }
+            }
+            Inline::SoftBreak | Inline::HardBreak => "\n".to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Inline {
+        Inline::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_inlines() {
+        assert_eq!(
+            parse_inlines("a **b *c*** `x*y` [d](https://e.f \"t\") g_h_i"),
+            vec![
+                text("a "),
+                Inline::Strong(vec![text("b "), Inline::Emphasis(vec![text("c")])]),
+                text(" "),
+                Inline::Code("x*y".to_string()),
+                text(" "),
+                Inline::Link {
+                    text: vec![text("d")],
+                    url: "https://e.f".to_string()
+                },
+                text(" g_h_i"),
+            ]
+        );
+        assert_eq!(
+            parse_inlines("line  \nnext\\*\nlast"),
+            vec![
+                text("line"),
+                Inline::HardBreak,
+                text("next*"),
+                Inline::SoftBreak,
+                text("last"),
+            ]
+        );
+        // 不成对的标记按原样保留
+        assert_eq!(
+            parse_inlines("2 * 3 = 6 `open"),
+            vec![text("2 * 3 = 6 `open")]
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let md = "## Review <code>\n\n\
+                  Check:\n\n\
+                  1. naming\n2. tests\n\n\
+                  - a\n\n- b\n\
+                  > quoted\n> line\n\n\
+                  ```rust\nfn main() {}\n```\n\
+                  ***";
+        assert_eq!(
+            to_html(&parse(md)),
+            "<h2>Review &lt;code&gt;</h2>\n\
+             <p>Check:</p>\n\
+             <ol>\n<li>naming</li>\n<li>tests</li>\n</ol>\n\
+             <ul>\n<li>a</li>\n<li>b</li>\n</ul>\n\
+             <blockquote>\n<p>quoted\nline</p>\n</blockquote>\n\
+             <pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n\
+             <hr />\n"
+        );
+    }
+
+    #[test]
+    fn test_to_plain() {
+        let md = "# Title\n\nUse **bold** and [docs](https://x.y).\n\n3. three\n4. four\n\n```\n*raw*\n```";
+        assert_eq!(
+            to_plain(&parse(md)),
+            "Title\n\nUse bold and docs (https://x.y).\n\n3. three\n4. four\n\n*raw*"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// Rich Text
+// 按 Prompt.content_type 把渲染后的提示词转换为剪贴板可用的多种表示：
+// markdown → HTML + RTF + 纯文本，html → HTML + 纯文本，其他 → 纯文本。
+
+pub mod html;
+pub mod markdown;
+pub mod rtf;
+
+/// 提示词内容的格式（来自 Prompt.content_type，不区分大小写）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Plain,
+    Markdown,
+    Html,
+}
+
+impl ContentType {
+    /// 未设置或无法识别的 content_type 按纯文本处理
+    pub fn parse(content_type: Option<&str>) -> Self {
+        match content_type
+            .map(|t| t.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("markdown" | "md" | "text/markdown") => ContentType::Markdown,
+            Some("html" | "text/html") => ContentType::Html,
+            _ => ContentType::Plain,
+        }
+    }
+
+    /// 转义插入该格式内容中的变量值，使其按原样显示而不被解释为标记
+    pub fn escape(self, value: &str) -> String {
+        match self {
+            ContentType::Plain => value.to_string(),
+            ContentType::Markdown => markdown::escape_literal(value),
+            ContentType::Html => html::escape(value),
+        }
+    }
+}
+
+/// 一段待注入的文本：plain 总是存在（键入注入与不支持富文本的应用使用），
+/// html / rtf 仅在提示词为富文本时提供
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormattedText {
+    pub plain: String,
+    /// HTML 片段（不含 <html>/<body>）
+    pub html: Option<String>,
+    /// 完整的 RTF 文档
+    pub rtf: Option<String>,
+}
+
+impl FormattedText {
+    pub fn plain(text: &str) -> Self {
+        FormattedText {
+            plain: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 按 content_type 转换渲染后的提示词内容
+    pub fn from_content(content: &str, content_type: Option<&str>) -> Self {
+        match ContentType::parse(content_type) {
+            ContentType::Plain => Self::plain(content),
+            ContentType::Markdown => {
+                let blocks = markdown::parse(content);
+                FormattedText {
+                    plain: markdown::to_plain(&blocks),
+                    html: Some(markdown::to_html(&blocks)),
+                    rtf: Some(rtf::from_blocks(&blocks)),
+                }
+            }
+            ContentType::Html => FormattedText {
+                plain: html::to_plain(content),
+                html: Some(content.to_string()),
+                rtf: None,
+            },
+        }
+    }
+
+    pub fn is_rich(&self) -> bool {
+        self.html.is_some() || self.rtf.is_some()
+    }
+}
+
+const CF_HTML_START: &str = "<html><body>\r\n<!--StartFragment-->";
+const CF_HTML_END: &str = "<!--EndFragment-->\r\n</body>\r\n</html>";
+
+/// 把 HTML 片段包装为 Windows "HTML Format"（CF_HTML）：带字节偏移头部的 UTF-8 文档
+pub fn cf_html(fragment: &str) -> String {
+    // 头部中的偏移量固定为 10 位，头部长度因此与数值无关
+    let header_len = cf_html_header(0, 0, 0, 0).len();
+    let start_html = header_len;
+    let start_fragment = start_html + CF_HTML_START.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + CF_HTML_END.len();
+    format!(
+        "{}{}{}{}",
+        cf_html_header(start_html, end_html, start_fragment, end_fragment),
+        CF_HTML_START,
+        fragment,
+        CF_HTML_END
+    )
+}
+
+fn cf_html_header(
+    start_html: usize,
+    end_html: usize,
+    start_fragment: usize,
+    end_fragment: usize,
+) -> String {
+    format!(
+        "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\n",
+        start_html, end_html, start_fragment, end_fragment
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_parse() {
+        assert_eq!(ContentType::parse(None), ContentType::Plain);
+        assert_eq!(ContentType::parse(Some("text")), ContentType::Plain);
+        assert_eq!(
+            ContentType::parse(Some(" Markdown ")),
+            ContentType::Markdown
+        );
+        assert_eq!(ContentType::parse(Some("text/html")), ContentType::Html);
+    }
+
+    #[test]
+    fn test_from_content() {
+        let plain = FormattedText::from_content("**hi**", None);
+        assert_eq!(plain, FormattedText::plain("**hi**"));
+        assert!(!plain.is_rich());
+
+        let md = FormattedText::from_content("# Title\n\nSay **hi**", Some("markdown"));
+        assert_eq!(md.plain, "Title\n\nSay hi");
+        assert_eq!(
+            md.html.as_deref(),
+            Some("<h1>Title</h1>\n<p>Say <strong>hi</strong></p>\n")
+        );
+        assert!(md.rtf.unwrap().contains(r"{\b hi}"));
+
+        let html = FormattedText::from_content("<p>a &amp; <b>b</b></p>", Some("html"));
+        assert_eq!(html.plain, "a & b");
+        assert_eq!(html.html.as_deref(), Some("<p>a &amp; <b>b</b></p>"));
+        assert_eq!(html.rtf, None);
+    }
+
+    #[test]
+    fn test_cf_html_offsets() {
+        let doc = cf_html("<b>提示</b>");
+        let offset = |key: &str| -> usize {
+            let start = doc.find(key).unwrap() + key.len();
+            doc[start..start + 10].parse().unwrap()
+        };
+        assert_eq!(
+            &doc[offset("StartFragment:")..offset("EndFragment:")],
+            "<b>提示</b>"
+        );
+        assert!(doc[offset("StartHTML:")..].starts_with("<html>"));
+        assert_eq!(offset("EndHTML:"), doc.len());
+    }
+}
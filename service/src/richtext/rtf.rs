@@ -0,0 +1,146 @@
+// RTF writer
+// 把解析后的 Markdown 写成 RTF（Word、Outlook、写字板等优先读取 RTF）。
+
+use super::markdown::{Block, Inline};
+use std::fmt::Write;
+
+const HEADER: &str = "{\\rtf1\\ansi\\ansicpg1252\\deff0\\uc1\
+{\\fonttbl{\\f0\\fswiss Calibri;}{\\f1\\fmodern Consolas;}}\\fs22\n";
+/// 标题字号（半磅）：h1 = 16pt … h6 = 11pt
+const HEADING_SIZES: [u32; 6] = [32, 28, 26, 24, 22, 22];
+/// 引用与列表每级缩进（twips）
+const INDENT: u32 = 720;
+
+pub fn from_blocks(blocks: &[Block]) -> String {
+    let mut out = String::from(HEADER);
+    write_blocks(blocks, 0, &mut out);
+    out.push('}');
+    out
+}
+
+fn write_blocks(blocks: &[Block], indent: u32, out: &mut String) {
+    for block in blocks {
+        match block {
+            Block::Heading { level, text } => {
+                let size = HEADING_SIZES[(*level as usize).clamp(1, 6) - 1];
+                let _ = write!(out, "\\pard\\sb240\\sa120\\li{}\\b\\fs{} ", indent, size);
+                write_inlines(text, out);
+                out.push_str("\\b0\\fs22\\par\n");
+            }
+            Block::Paragraph(text) => {
+                let _ = write!(out, "\\pard\\sa120\\li{} ", indent);
+                write_inlines(text, out);
+                out.push_str("\\par\n");
+            }
+            Block::CodeBlock { code, .. } => {
+                let _ = write!(out, "\\pard\\sa120\\li{}\\f1 ", indent);
+                escape(code, out);
+                out.push_str("\\f0\\par\n");
+            }
+            Block::Quote(inner) => write_blocks(inner, indent + INDENT, out),
+            Block::List { start, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    let marker = match start {
+                        Some(n) => format!("{}.", n + i as u32),
+                        None => "\\bullet".to_string(),
+                    };
+                    let _ = write!(
+                        out,
+                        "\\pard\\sa60\\fi-360\\li{} {}\\tab ",
+                        indent + INDENT,
+                        marker
+                    );
+                    write_inlines(item, out);
+                    out.push_str("\\par\n");
+                }
+            }
+            Block::Rule => {
+                let _ = writeln!(
+                    out,
+                    "\\pard\\sa120\\li{}\\brdrb\\brdrs\\brdrw10\\brsp20 \\par",
+                    indent
+                );
+            }
+        }
+    }
+}
+
+fn write_inlines(inlines: &[Inline], out: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => escape(text, out),
+            Inline::Strong(inner) => {
+                out.push_str("{\\b ");
+                write_inlines(inner, out);
+                out.push('}');
+            }
+            Inline::Emphasis(inner) => {
+                out.push_str("{\\i ");
+                write_inlines(inner, out);
+                out.push('}');
+            }
+            Inline::Code(code) => {
+                out.push_str("{\\f1 ");
+                escape(code, out);
+                out.push('}');
+            }
+            Inline::Link { text, url } => {
+                out.push_str("{\\field{\\*\\fldinst{HYPERLINK \"");
+                escape(&url.replace('"', "%22"), out);
+                out.push_str("\"}}{\\fldrslt{\\ul ");
+                write_inlines(text, out);
+                out.push_str("}}}");
+            }
+            Inline::SoftBreak => out.push(' '),
+            Inline::HardBreak => out.push_str("\\line "),
+        }
+    }
+}
+
+/// 转义控制字符；非 ASCII 字符写成 \uN?（N 为有符号 16 位 UTF-16 码元）
+fn escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\t' => out.push_str("\\tab "),
+            '\n' => out.push_str("\\line "),
+            '\r' => {}
+            c if c.is_ascii() => out.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    let _ = write!(out, "\\u{}?", *unit as i16);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::richtext::markdown;
+
+    #[test]
+    fn test_rtf_from_markdown() {
+        let rtf = from_blocks(&markdown::parse(
+            "# 标题\n\nUse {braces} and **bold** `code`\n\n- [docs](https://x.y)",
+        ));
+        assert!(rtf.starts_with("{\\rtf1"));
+        assert!(rtf.ends_with("\\par\n}"));
+        assert!(rtf.contains("\\b\\fs32 \\u26631?\\u-26472?\\b0"));
+        assert!(rtf.contains("Use \\{braces\\} and {\\b bold} {\\f1 code}\\par"));
+        assert!(rtf.contains(
+            "\\bullet\\tab {\\field{\\*\\fldinst{HYPERLINK \"https://x.y\"}}{\\fldrslt{\\ul docs}}}"
+        ));
+    }
+
+    #[test]
+    fn test_rtf_escapes_astral_chars_as_surrogates() {
+        let mut out = String::new();
+        escape("😀", &mut out);
+        assert_eq!(out, "\\u-10179?\\u-8704?");
+    }
+}
@@ -0,0 +1,293 @@
+// App Scope Matcher
+// Evaluates Prompt.app_scopes_json against the captured foreground context
+// (process name + window title) to pick the prompt to inject automatically.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// app_scopes_json 的解析结果
+///
+/// 支持两种写法:
+/// - 简写：进程名数组 `["code.exe", "idea*.exe"]`
+/// - 完整：`{"include": [{"process": "code.exe", "title": "\\.rs\\b"}], "exclude": [...], "priority": 10}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AppScope {
+    #[serde(default)]
+    pub include: Vec<ScopeRule>,
+    #[serde(default)]
+    pub exclude: Vec<ScopeRule>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// 单条规则：进程名 glob 与窗口标题正则，均指定时需同时满足
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScopeRule {
+    #[serde(default)]
+    pub process: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopeError {
+    InvalidJson(String),
+    InvalidRegex { pattern: String, message: String },
+}
+
+impl fmt::Display for ScopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScopeError::InvalidJson(e) => write!(f, "app_scopes_json 格式错误: {}", e),
+            ScopeError::InvalidRegex { pattern, message } => {
+                write!(f, "窗口标题正则无效 '{}': {}", pattern, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScopeError {}
+
+/// 单个提示词的匹配结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScopeMatch {
+    pub matched: bool,
+    pub priority: i32,
+    /// 命中规则的具体程度：精确进程名 > 进程名通配 > 仅标题
+    pub specificity: u32,
+    pub reasons: Vec<String>,
+}
+
+impl ScopeMatch {
+    fn rejected(reason: String) -> Self {
+        ScopeMatch {
+            matched: false,
+            priority: 0,
+            specificity: 0,
+            reasons: vec![reason],
+        }
+    }
+}
+
+impl AppScope {
+    /// 解析 app_scopes_json；空值表示没有作用域（不参与自动匹配）
+    pub fn parse(json: Option<&str>) -> Result<Option<AppScope>, ScopeError> {
+        let json = match json.map(str::trim) {
+            None | Some("") | Some("null") | Some("[]") | Some("{}") => return Ok(None),
+            Some(j) => j,
+        };
+
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| ScopeError::InvalidJson(e.to_string()))?;
+
+        let scope = if let Some(items) = value.as_array() {
+            let include = items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|p| ScopeRule {
+                    process: Some(p.to_string()),
+                    title: None,
+                })
+                .collect();
+            AppScope {
+                include,
+                ..Default::default()
+            }
+        } else {
+            serde_json::from_value(value).map_err(|e| ScopeError::InvalidJson(e.to_string()))?
+        };
+
+        Ok(Some(scope))
+    }
+
+    /// 预先检查所有标题正则是否可编译（导入/保存时使用）
+    pub fn validate(&self) -> Result<(), ScopeError> {
+        for rule in self.include.iter().chain(&self.exclude) {
+            if let Some(pattern) = &rule.title {
+                title_regex(pattern)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 针对当前进程名与窗口标题评估作用域
+    pub fn evaluate(
+        &self,
+        process_name: &str,
+        window_title: &str,
+    ) -> Result<ScopeMatch, ScopeError> {
+        for rule in &self.exclude {
+            if rule.matches(process_name, window_title)? {
+                return Ok(ScopeMatch::rejected(format!("命中排除规则 {}", rule)));
+            }
+        }
+
+        if self.include.is_empty() {
+            return Ok(ScopeMatch::rejected("未配置包含规则".to_string()));
+        }
+
+        let mut best: Option<(&ScopeRule, u32)> = None;
+        for rule in &self.include {
+            if rule.matches(process_name, window_title)? {
+                let specificity = rule.specificity();
+                if best.map(|(_, s)| specificity > s).unwrap_or(true) {
+                    best = Some((rule, specificity));
+                }
+            }
+        }
+
+        Ok(match best {
+            Some((rule, specificity)) => ScopeMatch {
+                matched: true,
+                priority: self.priority,
+                specificity,
+                reasons: vec![format!(
+                    "命中包含规则 {} (优先级 {}, 具体度 {})",
+                    rule, self.priority, specificity
+                )],
+            },
+            None => ScopeMatch::rejected("没有匹配的包含规则".to_string()),
+        })
+    }
+}
+
+impl ScopeRule {
+    fn matches(&self, process_name: &str, window_title: &str) -> Result<bool, ScopeError> {
+        if self.process.is_none() && self.title.is_none() {
+            return Ok(false);
+        }
+        if let Some(pattern) = &self.process
+            && !glob_match(&pattern.to_lowercase(), &process_name.to_lowercase())
+        {
+            return Ok(false);
+        }
+        if let Some(pattern) = &self.title
+            && !title_regex(pattern)?.is_match(window_title)
+        {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn specificity(&self) -> u32 {
+        let process_score = match &self.process {
+            Some(p) if p.contains(['*', '?']) => 20,
+            Some(_) => 30,
+            None => 0,
+        };
+        let title_score = if self.title.is_some() { 10 } else { 0 };
+        process_score + title_score
+    }
+}
+
+impl fmt::Display for ScopeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.process, &self.title) {
+            (Some(p), Some(t)) => write!(f, "[进程 {} + 标题 /{}/]", p, t),
+            (Some(p), None) => write!(f, "[进程 {}]", p),
+            (None, Some(t)) => write!(f, "[标题 /{}/]", t),
+            (None, None) => write!(f, "[空规则]"),
+        }
+    }
+}
+
+/// 编译窗口标题正则；每次自动注入都会评估所有提示词的作用域，因此按模式缓存编译结果
+fn title_regex(pattern: &str) -> Result<Regex, ScopeError> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(re) = cache.get(pattern) {
+        return Ok(re.clone());
+    }
+    let re = Regex::new(pattern).map_err(|e| ScopeError::InvalidRegex {
+        pattern: pattern.to_string(),
+        message: e.to_string(),
+    })?;
+    cache.insert(pattern.to_string(), re.clone());
+    Ok(re)
+}
+
+/// 简单 glob 匹配，支持 `*`（任意长度）与 `?`（单个字符）
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = star {
+            // 回溯：让上一个 * 多吞一个字符
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            star = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("code.exe", "code.exe"));
+        assert!(glob_match("idea*.exe", "idea64.exe"));
+        assert!(glob_match("*.exe", "notepad.exe"));
+        assert!(glob_match("note?ad.exe", "notepad.exe"));
+        assert!(!glob_match("code.exe", "vscode.exe"));
+        assert!(!glob_match("idea*.exe", "idea64.com"));
+    }
+
+    #[test]
+    fn test_parse_shorthand_array() {
+        let scope = AppScope::parse(Some(r#"["Code.exe"]"#)).unwrap().unwrap();
+        assert_eq!(scope.include[0].process.as_deref(), Some("Code.exe"));
+        assert!(AppScope::parse(Some("[]")).unwrap().is_none());
+        assert!(AppScope::parse(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_evaluate_include_exclude_and_specificity() {
+        let scope = AppScope::parse(Some(
+            r#"{"include": [{"process": "*.exe"}, {"process": "code.exe", "title": "\\.rs"}],
+                "exclude": [{"title": "settings"}],
+                "priority": 5}"#,
+        ))
+        .unwrap()
+        .unwrap();
+
+        let m = scope.evaluate("Code.exe", "main.rs - VS Code").unwrap();
+        assert!(m.matched);
+        assert_eq!((m.priority, m.specificity), (5, 40));
+
+        let m = scope.evaluate("notepad.exe", "notes.txt").unwrap();
+        assert_eq!((m.priority, m.specificity), (5, 20));
+
+        assert!(!scope.evaluate("code.exe", "settings").unwrap().matched);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_reported() {
+        let scope = AppScope::parse(Some(r#"{"include": [{"title": "("}]}"#))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            scope.evaluate("a.exe", "b"),
+            Err(ScopeError::InvalidRegex { .. })
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+// Dynamic Variables - values known only at injection time
+// {{date}}, {{time}}, {{app}}, {{window_title}}, {{clipboard}}, {{uuid}}
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+
+/// 注入时刻的上下文信息，供动态变量解析器使用
+pub struct DynamicContext<'a> {
+    pub app_name: &'a str,
+    pub window_title: &'a str,
+    /// 剪贴板读取函数；仅在模板引用 {{clipboard}} 时才会被调用
+    pub clipboard: Option<&'a dyn Fn() -> Option<String>>,
+    pub now: DateTime<Local>,
+}
+
+impl<'a> DynamicContext<'a> {
+    pub fn new(app_name: &'a str, window_title: &'a str) -> Self {
+        DynamicContext {
+            app_name,
+            window_title,
+            clipboard: None,
+            now: Local::now(),
+        }
+    }
+
+    pub fn with_clipboard(mut self, reader: &'a dyn Fn() -> Option<String>) -> Self {
+        self.clipboard = Some(reader);
+        self
+    }
+}
+
+/// 解析器：接收上下文与可选的格式参数（`{{name:format}}` 中冒号后的部分）
+pub type Resolver = Box<dyn Fn(&DynamicContext, Option<&str>) -> Option<String> + Send + Sync>;
+
+/// 动态变量解析器注册表
+pub struct ResolverRegistry {
+    resolvers: HashMap<String, Resolver>,
+}
+
+impl ResolverRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        ResolverRegistry {
+            resolvers: HashMap::new(),
+        }
+    }
+
+    /// 创建包含全部内置变量的注册表
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("date", |ctx, fmt| {
+            format_time(&ctx.now, fmt.unwrap_or("%Y-%m-%d"))
+        });
+        registry.register("time", |ctx, fmt| {
+            format_time(&ctx.now, fmt.unwrap_or("%H:%M:%S"))
+        });
+        registry.register("app", |ctx, _| Some(ctx.app_name.to_string()));
+        registry.register("window_title", |ctx, _| Some(ctx.window_title.to_string()));
+        registry.register("clipboard", |ctx, _| ctx.clipboard.and_then(|read| read()));
+        registry.register("uuid", |_, fmt| {
+            let id = uuid::Uuid::new_v4();
+            match fmt {
+                Some("simple") => Some(id.simple().to_string()),
+                _ => Some(id.to_string()),
+            }
+        });
+        registry
+    }
+
+    /// 注册（或覆盖）一个解析器
+    pub fn register<F>(&mut self, name: &str, resolver: F)
+    where
+        F: Fn(&DynamicContext, Option<&str>) -> Option<String> + Send + Sync + 'static,
+    {
+        self.resolvers.insert(name.to_string(), Box::new(resolver));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.resolvers.contains_key(name)
+    }
+
+    /// 解析变量；未注册时返回 None，已注册但无值时返回空字符串
+    pub fn resolve(
+        &self,
+        name: &str,
+        format: Option<&str>,
+        ctx: &DynamicContext,
+    ) -> Option<String> {
+        self.resolvers
+            .get(name)
+            .map(|resolver| resolver(ctx, format).unwrap_or_default())
+    }
+}
+
+impl Default for ResolverRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+/// 按 strftime 格式输出时间；格式串非法时返回 None（chrono 遇到非法格式会 panic）
+fn format_time(now: &DateTime<Local>, fmt: &str) -> Option<String> {
+    let items: Vec<Item> = StrftimeItems::new(fmt).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        log::warn!("无效的时间格式: {}", fmt);
+        return None;
+    }
+    Some(now.format_with_items(items.into_iter()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fixed_context() -> DynamicContext<'static> {
+        let mut ctx = DynamicContext::new("code.exe", "main.rs - VS Code");
+        ctx.now = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+        ctx
+    }
+
+    #[test]
+    fn test_builtin_date_and_time_formats() {
+        let registry = ResolverRegistry::with_builtins();
+        let ctx = fixed_context();
+        assert_eq!(registry.resolve("date", None, &ctx).unwrap(), "2024-03-09");
+        assert_eq!(
+            registry.resolve("date", Some("%d/%m"), &ctx).unwrap(),
+            "09/03"
+        );
+        assert_eq!(registry.resolve("time", None, &ctx).unwrap(), "14:05:07");
+        // 非法格式不 panic，返回空字符串
+        assert_eq!(registry.resolve("date", Some("%Q"), &ctx).unwrap(), "");
+    }
+
+    #[test]
+    fn test_context_and_clipboard_resolvers() {
+        let registry = ResolverRegistry::with_builtins();
+        let reader = || Some("copied text".to_string());
+        let ctx = fixed_context().with_clipboard(&reader);
+        assert_eq!(registry.resolve("app", None, &ctx).unwrap(), "code.exe");
+        assert_eq!(
+            registry.resolve("clipboard", None, &ctx).unwrap(),
+            "copied text"
+        );
+        assert_eq!(registry.resolve("uuid", None, &ctx).unwrap().len(), 36);
+        assert!(registry.resolve("unknown", None, &ctx).is_none());
+    }
+
+    #[test]
+    fn test_register_custom_resolver() {
+        let mut registry = ResolverRegistry::new();
+        registry.register("user", |_, fmt| Some(fmt.unwrap_or("anon").to_uppercase()));
+        let ctx = fixed_context();
+        assert_eq!(registry.resolve("user", Some("bob"), &ctx).unwrap(), "BOB");
+    }
+}
@@ -0,0 +1,490 @@
+// Prompt Template Engine
+// Parses {{name}}, {{name|default}} and {{name:choice(a,b,c)}} placeholders
+// out of Prompt.content and renders them against user-supplied values.
+// Anything else in braces (code, other template languages) stays literal; `\{{` is a literal `{{`.
+
+pub mod dynamic;
+
+use dynamic::{DynamicContext, ResolverRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// 变量定义（来自 Prompt.variables_json）
+///
+/// variables_json 为数组格式，例如:
+/// `[{"name": "lang", "label": "语言", "choices": ["rust", "go"], "required": true}]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariableSpec {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub choices: Option<Vec<String>>,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// 模板中解析出的占位符
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+    pub choices: Option<Vec<String>>,
+    /// `{{name:xxx}}` 中非 choice(...) 的修饰部分（例如 `{{date:%Y-%m-%d}}` 的格式串）
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Var(Placeholder),
+}
+
+/// 等待用户填写的变量（返回给 GUI 用于弹出输入框）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissingVariable {
+    pub name: String,
+    pub label: Option<String>,
+    pub default: Option<String>,
+    pub choices: Option<Vec<String>>,
+}
+
+/// 渲染结果：要么得到最终文本，要么列出仍需用户输入的变量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RenderOutcome {
+    Rendered { text: String },
+    NeedsInput { variables: Vec<MissingVariable> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    /// variables_json 无法解析
+    InvalidSchema(String),
+    /// 提供的值不在 choice 列表中
+    InvalidChoice {
+        name: String,
+        value: String,
+        choices: Vec<String>,
+    },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::InvalidSchema(e) => write!(f, "variables_json 格式错误: {}", e),
+            TemplateError::InvalidChoice {
+                name,
+                value,
+                choices,
+            } => write!(
+                f,
+                "变量 {} 的值 '{}' 不在可选项 [{}] 中",
+                name,
+                value,
+                choices.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// 解析模板内容为文本段与占位符段
+///
+/// 未闭合的 `{{`、名称不合法的 `{{...}}`（如代码中的 `{{}}`、`{{ user.name }}`、`{{.Name}}`）
+/// 按原文保留；`\{{` 表示字面的 `{{`
+pub fn parse(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("{{") {
+        let after_open = &rest[start + 2..];
+        if let Some(before) = rest[..start].strip_suffix('\\') {
+            text.push_str(before);
+            text.push_str("{{");
+            rest = after_open;
+            continue;
+        }
+        text.push_str(&rest[..start]);
+
+        let placeholder = after_open
+            .find("}}")
+            .and_then(|end| parse_placeholder(&after_open[..end]).map(|p| (p, end)));
+        match placeholder {
+            Some((placeholder, end)) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Var(placeholder));
+                rest = &after_open[end + 2..];
+            }
+            None => {
+                text.push_str("{{");
+                rest = after_open;
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    segments
+}
+
+/// 名称必须紧跟 `{{` 并以字母、数字或下划线开头；不是合法占位符时返回 None
+fn parse_placeholder(inner: &str) -> Option<Placeholder> {
+    if !inner.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    // {{name|default}}：默认值可以包含任意字符，因此优先按 '|' 切分
+    let (head, default) = match inner.split_once('|') {
+        Some((h, d)) => (h.trim(), Some(d.to_string())),
+        None => (inner.trim(), None),
+    };
+
+    let (name, modifier) = match head.split_once(':') {
+        Some((n, m)) => (n.trim(), Some(m.trim())),
+        None => (head, None),
+    };
+
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.');
+    if !valid_name {
+        return None;
+    }
+
+    let mut choices = None;
+    let mut format = None;
+    if let Some(m) = modifier {
+        if let Some(list) = m.strip_prefix("choice(").and_then(|s| s.strip_suffix(')')) {
+            choices = Some(
+                list.split(',')
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect(),
+            );
+        } else if !m.is_empty() {
+            format = Some(m.to_string());
+        }
+    }
+
+    Some(Placeholder {
+        name: name.to_string(),
+        default,
+        choices,
+        format,
+    })
+}
+
+/// 解析 variables_json；空值或空字符串视为没有定义
+pub fn parse_schema(variables_json: Option<&str>) -> Result<Vec<VariableSpec>, TemplateError> {
+    match variables_json.map(str::trim) {
+        None | Some("") | Some("null") => Ok(Vec::new()),
+        Some(json) => {
+            serde_json::from_str(json).map_err(|e| TemplateError::InvalidSchema(e.to_string()))
+        }
+    }
+}
+
+/// 渲染模板
+///
+/// 优先级：用户提供的值 > 占位符内默认值 > variables_json 中的默认值。
+/// 可选变量 (`required: false`) 缺失时渲染为空字符串；必填变量缺失时返回 `NeedsInput`。
+pub fn render(
+    content: &str,
+    variables_json: Option<&str>,
+    values: &HashMap<String, String>,
+) -> Result<RenderOutcome, TemplateError> {
+    render_with_resolvers(content, variables_json, values, None)
+}
+
+/// 渲染模板，并用注册表解析动态变量（{{date}}、{{app}} 等）
+///
+/// 动态变量从不要求用户输入：用户显式提供的值优先，其次是解析结果，
+/// 解析结果为空时回落到默认值，仍为空则渲染为空字符串。
+/// 提示词在 variables_json 中定义了同名变量时按普通变量处理，不使用解析器。
+pub fn render_with_resolvers(
+    content: &str,
+    variables_json: Option<&str>,
+    values: &HashMap<String, String>,
+    dynamic: Option<(&ResolverRegistry, &DynamicContext)>,
+) -> Result<RenderOutcome, TemplateError> {
+    render_escaped(content, variables_json, values, dynamic, &|v| v.to_string())
+}
+
+/// 同 render_with_resolvers，但变量值先经 escape 转义再插入（富文本提示词使用）
+pub fn render_escaped(
+    content: &str,
+    variables_json: Option<&str>,
+    values: &HashMap<String, String>,
+    dynamic: Option<(&ResolverRegistry, &DynamicContext)>,
+    escape: &dyn Fn(&str) -> String,
+) -> Result<RenderOutcome, TemplateError> {
+    let segments = parse(content);
+    let schema = parse_schema(variables_json)?;
+    let spec_of = |name: &str| schema.iter().find(|s| s.name == name);
+
+    let mut output = String::with_capacity(content.len());
+    let mut missing: Vec<MissingVariable> = Vec::new();
+
+    for segment in &segments {
+        let placeholder = match segment {
+            Segment::Text(t) => {
+                output.push_str(t);
+                continue;
+            }
+            Segment::Var(p) => p,
+        };
+
+        let spec = spec_of(&placeholder.name);
+        let choices = placeholder
+            .choices
+            .clone()
+            .or_else(|| spec.and_then(|s| s.choices.clone()));
+
+        // 提示词在 variables_json 中自行定义的同名变量（如 date）不使用内置解析器
+        let resolved = dynamic
+            .filter(|_| spec.is_none())
+            .and_then(|(registry, ctx)| {
+                registry.resolve(&placeholder.name, placeholder.format.as_deref(), ctx)
+            });
+        let is_dynamic = resolved.is_some();
+
+        let value = values
+            .get(&placeholder.name)
+            .cloned()
+            .or_else(|| resolved.filter(|v| !v.is_empty()))
+            .or_else(|| placeholder.default.clone())
+            .or_else(|| spec.and_then(|s| s.default.clone()))
+            .or_else(|| is_dynamic.then(String::new));
+
+        match value {
+            Some(v) => {
+                if let Some(list) = &choices
+                    && !list.is_empty()
+                    && !list.contains(&v)
+                {
+                    return Err(TemplateError::InvalidChoice {
+                        name: placeholder.name.clone(),
+                        value: v,
+                        choices: list.clone(),
+                    });
+                }
+                output.push_str(&escape(&v));
+            }
+            None if spec.map(|s| !s.required).unwrap_or(false) => {}
+            None => {
+                if !missing.iter().any(|m| m.name == placeholder.name) {
+                    missing.push(MissingVariable {
+                        name: placeholder.name.clone(),
+                        label: spec.and_then(|s| s.label.clone()),
+                        default: None,
+                        choices,
+                    });
+                }
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(RenderOutcome::Rendered { text: output })
+    } else {
+        Ok(RenderOutcome::NeedsInput { variables: missing })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_placeholder_forms() {
+        let segments = parse("Hi {{name}}, {{tone|polite}} {{lang:choice(rust, go)}}");
+        let vars: Vec<&Placeholder> = segments
+            .iter()
+            .filter_map(|s| match s {
+                Segment::Var(p) => Some(p),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars[0].name, "name");
+        assert_eq!(vars[1].default.as_deref(), Some("polite"));
+        assert_eq!(
+            vars[2].choices,
+            Some(vec!["rust".to_string(), "go".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_non_placeholders_stay_literal() {
+        assert_eq!(
+            parse("abc {{name"),
+            vec![Segment::Text("abc {{name".to_string())]
+        );
+
+        // 代码片段与其他模板语言中的花括号原样注入
+        let snippet = concat!(
+            "println!(\"{{}}\", x);\n",
+            "<p>{{ user.name }}</p> {{#if ok}}\n",
+            "{{.Name}} {{- trim }}",
+        );
+        assert_eq!(
+            render(snippet, None, &HashMap::new()).unwrap(),
+            RenderOutcome::Rendered {
+                text: snippet.to_string()
+            }
+        );
+
+        // \{{ 转义为字面的 {{，其后的占位符仍然生效
+        assert_eq!(
+            render(r"\{{lang}} is {{lang}}", None, &values(&[("lang", "Rust")])).unwrap(),
+            RenderOutcome::Rendered {
+                text: "{{lang}} is Rust".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_with_values_and_defaults() {
+        let outcome = render(
+            "Review this {{lang}} code in a {{tone|friendly}} tone.",
+            None,
+            &values(&[("lang", "Rust")]),
+        )
+        .unwrap();
+        assert_eq!(
+            outcome,
+            RenderOutcome::Rendered {
+                text: "Review this Rust code in a friendly tone.".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_needs_input_uses_schema() {
+        let schema = r#"[{"name": "lang", "label": "Language", "choices": ["rust", "go"]},
+                         {"name": "note", "required": false}]"#;
+        let outcome = render("{{lang}} {{lang}}{{note}}", Some(schema), &HashMap::new()).unwrap();
+        match outcome {
+            RenderOutcome::NeedsInput { variables } => {
+                assert_eq!(variables.len(), 1);
+                assert_eq!(variables[0].label.as_deref(), Some("Language"));
+                assert_eq!(variables[0].choices.as_ref().unwrap().len(), 2);
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_with_dynamic_variables() {
+        let registry = ResolverRegistry::with_builtins();
+        let ctx = DynamicContext::new("notepad.exe", "Untitled");
+        let outcome = render_with_resolvers(
+            "[{{app}}] {{clipboard|empty}} {{topic}}",
+            None,
+            &values(&[("topic", "notes")]),
+            Some((&registry, &ctx)),
+        )
+        .unwrap();
+        assert_eq!(
+            outcome,
+            RenderOutcome::Rendered {
+                text: "[notepad.exe] empty notes".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_declared_variables_shadow_dynamic_ones() {
+        let registry = ResolverRegistry::with_builtins();
+        let ctx = DynamicContext::new("notepad.exe", "Untitled");
+        let render_app = |schema: &str| {
+            render_with_resolvers(
+                "{{app}} {{date}}",
+                Some(schema),
+                &HashMap::new(),
+                Some((&registry, &ctx)),
+            )
+            .unwrap()
+        };
+
+        // 有默认值时使用默认值
+        let outcome = render_app(
+            r#"[{"name": "app", "default": "my app"}, {"name": "date", "default": "someday"}]"#,
+        );
+        assert_eq!(
+            outcome,
+            RenderOutcome::Rendered {
+                text: "my app someday".to_string()
+            }
+        );
+
+        // 必填且没有默认值时询问用户，未定义的 app 仍由解析器填写
+        match render_app(r#"[{"name": "date"}]"#) {
+            RenderOutcome::NeedsInput { variables } => {
+                assert_eq!(variables.len(), 1);
+                assert_eq!(variables[0].name, "date");
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_rejects_invalid_choice() {
+        let err = render(
+            "{{lang:choice(rust,go)}}",
+            None,
+            &values(&[("lang", "java")]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, TemplateError::InvalidChoice { .. }));
+    }
+
+    #[test]
+    fn test_render_escaped_for_rich_content() {
+        use crate::richtext::{ContentType, FormattedText};
+
+        let value = values(&[("x", "a <b> & **c**")]);
+        let rich = |content: &str, content_type: &str| {
+            let ty = ContentType::parse(Some(content_type));
+            match render_escaped(content, None, &value, None, &|v| ty.escape(v)).unwrap() {
+                RenderOutcome::Rendered { text } => {
+                    FormattedText::from_content(&text, Some(content_type))
+                }
+                other => panic!("unexpected outcome: {:?}", other),
+            }
+        };
+
+        let html = rich("<p>{{x}}</p>", "html");
+        assert_eq!(html.plain, "a <b> & **c**");
+        assert_eq!(html.html.as_deref(), Some("<p>a &lt;b&gt; &amp; **c**</p>"));
+
+        let md = rich("Say {{x}}", "markdown");
+        assert_eq!(md.plain, "Say a <b> & **c**");
+        assert_eq!(
+            md.html.as_deref(),
+            Some("<p>Say a &lt;b&gt; &amp; **c**</p>\n")
+        );
+    }
+}
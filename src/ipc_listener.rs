@@ -1,83 +1,144 @@
 // IPC Listener Module - GUI Server for Service Communication
 // T1-010: Implement IPC Listener in GUI
 
+use service::ipc::protocol::{
+    Command, HotkeyRegistration, InjectionOutcome, IpcError, Reply, SequenceHint,
+};
+use service::ipc::transport::{self, Endpoint, PlatformTransport, Transport};
+use std::sync::Mutex;
 use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
-use tokio::io::AsyncReadExt;
-use tokio::net::windows::named_pipe::ServerOptions;
-
-const PIPE_NAME: &str = r"\\.\pipe\promptkey_selector";
 
 pub fn start_ipc_listener(app: AppHandle) {
     tauri::async_runtime::spawn(async move {
-        println!("[IPC] Starting listener on {}", PIPE_NAME);
+        let endpoint = Endpoint::selector();
+        let transport = PlatformTransport::default();
+        println!("[IPC] Starting listener on {}", endpoint.name);
 
-        loop {
-            // Create named pipe server
-            // Note: We create a new instance for each connection
-            let mut server = match ServerOptions::new()
-                .first_pipe_instance(false)
-                .create(PIPE_NAME)
-            {
-                Ok(s) => s,
+        let listener = loop {
+            match transport.bind(&endpoint) {
+                Ok(listener) => break listener,
                 Err(e) => {
-                    eprintln!(
-                        "[IPC] Failed to create named pipe: {}. Retrying in 1s...",
-                        e
-                    );
+                    eprintln!("[IPC] Failed to listen: {}. Retrying in 1s...", e);
                     tokio::time::sleep(Duration::from_secs(1)).await;
-                    continue;
                 }
-            };
+            }
+        };
+
+        // One request, one response per connection
+        transport::serve(listener, |command| handle_command(&app, command)).await;
+    });
+}
 
-            // Wait for client to connect
-            if let Err(e) = server.connect().await {
-                eprintln!("[IPC] Failed to accept connection: {}", e);
-                continue;
+fn handle_command(app_handle: &AppHandle, command: Command) -> Reply {
+    println!("[IPC] Received: {}", command.name());
+    let label = match command {
+        Command::ShowSelector => "selector-panel",
+        // TW013: Handle SHOW_WHEEL message
+        Command::ShowWheel => "wheel-panel",
+        Command::InjectionOutcome(outcome) => return forward_outcome(app_handle, &outcome),
+        Command::HotkeyStatus { registrations } => {
+            return forward_hotkey_status(app_handle, registrations)
+        }
+        Command::SequenceHints { pressed, hints } => {
+            return forward_sequence_hints(app_handle, pressed, hints)
+        }
+        Command::Ping => return Reply::Ok,
+        other => {
+            return Reply::Error {
+                error: IpcError::UnsupportedCommand {
+                    command: other.name().to_string(),
+                },
             }
+        }
+    };
 
-            println!("[IPC] Client connected");
+    match app_handle.get_webview_window(label) {
+        Some(window) => {
+            // Show and focus window
+            let _ = window.show();
+            let _ = window.set_focus();
+            // Reset frontend state
+            let _ = window.emit("reset-state", ());
+            println!("[IPC] {} shown via IPC", label);
+            Reply::Ok
+        }
+        None => {
+            eprintln!("[IPC] {} not found!", label);
+            Reply::Error {
+                error: IpcError::Unavailable {
+                    message: format!("window '{}' not found", label),
+                },
+            }
+        }
+    }
+}
 
-            // Handle connection
-            let app_handle = app.clone();
-            let mut buf = [0u8; 128];
+/// Windows that show injection results as toasts
+const OUTCOME_WINDOWS: [&str; 2] = ["main", "wheel-panel"];
 
-            match server.read(&mut buf).await {
-                Ok(n) if n > 0 => {
-                    let msg = String::from_utf8_lossy(&buf[..n]);
-                    let msg_clean = msg.trim();
-                    println!("[IPC] Received: {}", msg_clean);
+fn forward_outcome(app_handle: &AppHandle, outcome: &InjectionOutcome) -> Reply {
+    if !outcome.success {
+        eprintln!("[IPC] Injection failed: {:?}", outcome.error);
+    }
+    for label in OUTCOME_WINDOWS {
+        if let Err(e) = app_handle.emit_to(label, "injection-outcome", outcome) {
+            eprintln!("[IPC] Failed to emit injection-outcome to {}: {}", label, e);
+        }
+    }
+    Reply::Ok
+}
 
-                    if msg_clean == "SHOW_SELECTOR" {
-                        if let Some(window) = app_handle.get_webview_window("selector-panel") {
-                            // Show and focus window
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            // Reset frontend state
-                            let _ = window.emit("reset-state", ());
-                            println!("[IPC] Selector window shown via IPC");
-                        } else {
-                            eprintln!("[IPC] Selector window not found!");
-                        }
-                    } else if msg_clean == "SHOW_WHEEL" {
-                        // TW013: Handle SHOW_WHEEL message
-                        if let Some(window) = app_handle.get_webview_window("wheel-panel") {
-                            // Show and focus window
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            // Reset frontend state
-                            let _ = window.emit("reset-state", ());
-                            println!("[IPC] Wheel window shown via IPC");
-                        } else {
-                            eprintln!("[IPC] Wheel window not found!");
-                        }
-                    }
-                }
-                Ok(_) => { /* EOF or empty */ }
-                Err(e) => eprintln!("[IPC] Error reading from pipe: {}", e),
-            }
+/// Latest hotkey registration report; the settings page may load after the service reported it
+static HOTKEY_STATUS: Mutex<Vec<HotkeyRegistration>> = Mutex::new(Vec::new());
+
+pub fn hotkey_status() -> Vec<HotkeyRegistration> {
+    HOTKEY_STATUS.lock().unwrap().clone()
+}
 
-            // Disconnect happens when server is dropped or loop restarts
+fn forward_hotkey_status(app_handle: &AppHandle, registrations: Vec<HotkeyRegistration>) -> Reply {
+    for registration in &registrations {
+        for failure in &registration.failures {
+            eprintln!(
+                "[IPC] Hotkey {} for {} failed: {}",
+                failure.chord, registration.action, failure.error
+            );
         }
-    });
+    }
+    if let Err(e) = app_handle.emit_to("main", "hotkey-status", &registrations) {
+        eprintln!("[IPC] Failed to emit hotkey-status: {}", e);
+    }
+    *HOTKEY_STATUS.lock().unwrap() = registrations;
+    Reply::Ok
+}
+
+#[derive(Clone, serde::Serialize)]
+struct SequenceHintsPayload {
+    pressed: String,
+    hints: Vec<SequenceHint>,
+}
+
+/// Show the leader-key hint overlay without taking focus (the keys still go to the target app);
+/// an empty hint list means the sequence ended and hides it
+fn forward_sequence_hints(
+    app_handle: &AppHandle,
+    pressed: String,
+    hints: Vec<SequenceHint>,
+) -> Reply {
+    let Some(window) = app_handle.get_webview_window("sequence-hint") else {
+        return Reply::Error {
+            error: IpcError::Unavailable {
+                message: "window 'sequence-hint' not found".to_string(),
+            },
+        };
+    };
+    if hints.is_empty() {
+        let _ = window.hide();
+        return Reply::Ok;
+    }
+    if let Err(e) = window.emit("sequence-hints", SequenceHintsPayload { pressed, hints }) {
+        eprintln!("[IPC] Failed to emit sequence-hints: {}", e);
+    }
+    let _ = window.show();
+    Reply::Ok
 }
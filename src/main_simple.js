@@ -193,6 +193,8 @@ async function initializeApp() {
     // 等待并验证Tauri API
     const tauri = await waitForTauri();
     updateDebugInfo('Tauri API状态: ' + (tauri ? '可用' : '不可用'));
+    listenInjectionOutcome(tauri);
+    listenHotkeyStatus(tauri);
     
     // 查找页面元素
     const elements = {
@@ -387,32 +389,28 @@ async function initializeApp() {
                     modifiers.push('Shift');
                 }
                 
-                // 查找主键
+                if (pressedKeys.has('MetaLeft') || pressedKeys.has('MetaRight')) {
+                    modifiers.push('Win');
+                }
+                
+                // 查找主键（KeyboardEvent.code，如 KeyA、Digit1、F13、ArrowUp、NumpadAdd，由服务端解析）
                 for (const code of pressedKeys) {
-                    if (!code.includes('Control') && !code.includes('Alt') && !code.includes('Shift')) {
-                        if (code.startsWith('Key')) {
-                            mainKey = code.slice(3); // KeyA -> A
-                        } else if (code.startsWith('Digit')) {
-                            mainKey = code.slice(5); // Digit1 -> 1
-                        } else if (code === 'Space') {
-                            mainKey = 'Space';
-                        }
+                    if (!/^(Control|Alt|Shift|Meta)/.test(code)) {
+                        mainKey = code;
                         break;
                     }
                 }
                 
                 updateDebugInfo(`修饰键: ${modifiers.join(',')}, 主键: ${mainKey}`);
                 
-                // 如果有修饰键和主键，完成录制
-                if (modifiers.length > 0 && mainKey) {
-                    const recordedHotkey = modifiers.join('+') + '+' + mainKey;
-                    
-                    if ((modifiers.includes('Ctrl') || modifiers.includes('Alt')) && mainKey) {
-                        hotkeyInput.value = recordedHotkey;
-                        localStorage.setItem('recordedHotkey', recordedHotkey);
-                        updateDebugInfo('录制成功: ' + recordedHotkey + '，已保存到localStorage');
-                        stopRecording();
-                    }
+                // 有主键即完成录制；组合是否有效在保存时由服务端校验
+                if (mainKey) {
+                    const recordedHotkey = [...modifiers, mainKey].join('+');
+                    hotkeyInput.value = recordedHotkey;
+                    localStorage.setItem('recordedHotkey', recordedHotkey);
+                    updateDebugInfo('录制成功: ' + recordedHotkey + '，已保存到localStorage');
+                    stopRecording();
+                    testRecordedHotkey(hotkeyInput, recordedHotkey);
                 } else if (modifiers.length > 0) {
                     hotkeyInput.value = modifiers.join('+') + '+';
                 }
@@ -756,6 +754,10 @@ function showAddPromptModal() {
                         <label for="prompt-tags">标签 (可选)</label>
                         <input type="text" id="prompt-tags" class="form-input" placeholder="用逗号分隔多个标签，如：工作,邮件,AI">
                     </div>
+                    <div class="form-group">
+                        <label for="prompt-abbreviation">缩写 (可选)</label>
+                        <input type="text" id="prompt-abbreviation" class="form-input" placeholder="如 ;;sig，输入缩写后按空格即注入此提示词" maxlength="32">
+                    </div>
                 </div>
                 <div class="modal-footer">
                     <button class="secondary-btn" onclick="closeAddPromptModal()">取消</button>
@@ -791,6 +793,7 @@ async function submitPrompt() {
     const name = document.getElementById('prompt-name')?.value?.trim();
     const content = document.getElementById('prompt-content')?.value?.trim();
     const tagsInput = document.getElementById('prompt-tags')?.value?.trim();
+    const abbreviation = document.getElementById('prompt-abbreviation')?.value?.trim() || null;
     
     if (!name) {
         alert('请输入提示词名称');
@@ -822,6 +825,7 @@ async function submitPrompt() {
                 variables_json: null,
                 app_scopes_json: null,
                 inject_order: null,
+                abbreviation: abbreviation,
                 version: 1
             }
         });
@@ -855,6 +859,59 @@ function showNotification(message, type = 'info') {
     }, 3000);
 }
 
+// 监听服务端的注入结果：失败以通知提示，成功只记入调试日志
+async function listenInjectionOutcome(tauri) {
+    if (!tauri || !tauri.event) return;
+    await tauri.event.listen('injection-outcome', (event) => {
+        const outcome = event.payload;
+        const name = outcome.prompt_name ? `「${outcome.prompt_name}」` : '';
+        if (outcome.success) {
+            updateDebugInfo(`注入成功${name}: ${outcome.strategy} (${outcome.elapsed_ms}ms)`);
+        } else {
+            showNotification(`❌ 注入失败${name}: ${outcome.error}`, 'error');
+        }
+    });
+}
+
+// 录制完成后立即检测热键是否可用（是否已被其他程序占用），并显示规范形式
+async function testRecordedHotkey(hotkeyInput, hotkey) {
+    try {
+        const canonical = await safeInvoke('test_hotkey', { hotkey });
+        hotkeyInput.value = canonical;
+        localStorage.setItem('recordedHotkey', canonical);
+        updateDebugInfo(`热键可用: ${canonical}`);
+    } catch (error) {
+        showNotification(`⚠️ 热键不可用: ${error}`, 'error');
+    }
+}
+
+// 显示服务的热键注册结果：主热键冲突、改用备选组合或全部失败时提示
+function showHotkeyStatus(registrations) {
+    for (const registration of registrations || []) {
+        const failures = registration.failures || [];
+        if (!registration.active) {
+            const reason = failures.length ? failures[failures.length - 1].error : '';
+            showNotification(`❌ 热键注册失败 (${registration.action}): ${reason}`, 'error');
+        } else if (failures.length) {
+            const tried = failures.map(f => f.chord).join('、');
+            showNotification(`⚠️ ${tried} 不可用，已改用 ${registration.active}`, 'info');
+        } else {
+            updateDebugInfo(`热键已注册 (${registration.action}): ${registration.active}`);
+        }
+    }
+}
+
+async function listenHotkeyStatus(tauri) {
+    if (!tauri || !tauri.event) return;
+    await tauri.event.listen('hotkey-status', (event) => showHotkeyStatus(event.payload));
+    // 服务可能在页面加载前已完成注册
+    try {
+        showHotkeyStatus(await safeInvoke('get_hotkey_status'));
+    } catch (error) {
+        updateDebugInfo(`获取热键状态失败: ${error}`);
+    }
+}
+
 // 将函数暴露到全局作用域
 window.closeAddPromptModal = closeAddPromptModal;
 window.submitPrompt = submitPrompt;
@@ -897,7 +954,7 @@ async function loadUsageLogs() {
                         <div class="log-summary" onclick="toggleLogDetails(${index})">
                             <div class="log-left">
                                 <span class="log-title">${log.prompt_name}</span>
-                                <span class="log-strategy-badge">${log.strategy}</span>
+                                <span class="log-strategy-badge">${log.strategy || '失败'}</span>
                             </div>
                             <div class="log-right">
                                 <span class="log-time-badge">${log.injection_time_ms}ms</span>
@@ -923,6 +980,12 @@ async function loadUsageLogs() {
                                     <span class="detail-label">注入策略</span>
                                     <span class="detail-value">${log.strategy}</span>
                                 </div>
+                                ${log.fallback_chain ? `
+                                    <div class="detail-item">
+                                        <span class="detail-label">尝试顺序</span>
+                                        <span class="detail-value">${log.fallback_chain}</span>
+                                    </div>
+                                ` : ''}
                                 <div class="detail-item">
                                     <span class="detail-label">执行时间</span>
                                     <span class="detail-value">${log.injection_time_ms}ms</span>
@@ -1144,6 +1207,10 @@ function showEditPromptModal(prompt) {
                         <label for="prompt-tags">标签 (可选)</label>
                         <input type="text" id="prompt-tags" class="form-input" placeholder="用逗号分隔多个标签，如：工作,邮件,AI" value="${tagsString}">
                     </div>
+                    <div class="form-group">
+                        <label for="prompt-abbreviation">缩写 (可选)</label>
+                        <input type="text" id="prompt-abbreviation" class="form-input" placeholder="如 ;;sig，输入缩写后按空格即注入此提示词" maxlength="32" value="${prompt.abbreviation || ''}">
+                    </div>
                 </div>
                 <div class="modal-footer">
                     <button class="secondary-btn" onclick="closeEditPromptModal()">取消</button>
@@ -1180,6 +1247,7 @@ async function updatePrompt() {
     const name = document.getElementById('prompt-name')?.value?.trim();
     const content = document.getElementById('prompt-content')?.value?.trim();
     const tagsInput = document.getElementById('prompt-tags')?.value?.trim();
+    const abbreviation = document.getElementById('prompt-abbreviation')?.value?.trim() || null;
     
     if (!name) {
         alert('请输入提示词名称');
@@ -1208,11 +1276,7 @@ async function updatePrompt() {
                 name: name,
                 content: content,
                 tags: tags,
-                content_type: null,
-                variables_json: null,
-                app_scopes_json: null,
-                inject_order: null,
-                version: 1
+                abbreviation: abbreviation
             }
         });
         
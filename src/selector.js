@@ -79,9 +79,9 @@ async function init() {
 
 /**
  * T1-016: Search Logic
- * Handle search input with Fuse.js filtering and PRD-compliant sorting
+ * Handle search input with backend FTS5 ranking (Fuse.js as offline fallback)
  */
-function handleSearch(e) {
+async function handleSearch(e) {
     const query = e.target.value.trim();
     lastQuery = query;
     
@@ -91,30 +91,15 @@ function handleSearch(e) {
         // Empty query: show Top 10 by usage
         results = sortByUsage(allPrompts).slice(0, 10);
     } else {
-        // Fuzzy search with Fuse.js
-        const fuseResults = fuseInstance.search(query);
-        
-        // Apply PRD sorting: relevance → recency → id
-        results = fuseResults
-            .map(r => ({
-                ...r.item,
-                _score: r.score
-            }))
-            .sort((a, b) => {
-                // 1. Primary sort: relevance score (lower is better for Fuse.js)
-                if (Math.abs(a._score - b._score) > 0.01) {
-                    return a._score - b._score;
-                }
-                // 2. Secondary sort: last used time (newer first)
-                const timeA = a.last_used_at || 0;
-                const timeB = b.last_used_at || 0;
-                if (timeA !== timeB) {
-                    return timeB - timeA; // descending
-                }
-                // 3. Fallback sort: id (ascending)
-                return a.id - b.id;
-            })
-            .slice(0, 10); // Top 10 results
+        try {
+            // Backend FTS5 search (BM25 + usage recency/frequency ranking)
+            results = await invoke('search_prompts', { query, limit: 10 });
+        } catch (error) {
+            console.warn('[Selector] Backend search failed, falling back to Fuse.js:', error);
+            results = fuzzySearch(query);
+        }
+        // Drop stale responses if the user kept typing
+        if (query !== lastQuery) return;
     }
     
     renderResults(results);
@@ -124,6 +109,35 @@ function handleSearch(e) {
     updateFocusStyle();
 }
 
+/**
+ * Client-side fuzzy search fallback with Fuse.js
+ */
+function fuzzySearch(query) {
+    const fuseResults = fuseInstance.search(query);
+    
+    // Apply PRD sorting: relevance → recency → id
+    return fuseResults
+        .map(r => ({
+            ...r.item,
+            _score: r.score
+        }))
+        .sort((a, b) => {
+            // 1. Primary sort: relevance score (lower is better for Fuse.js)
+            if (Math.abs(a._score - b._score) > 0.01) {
+                return a._score - b._score;
+            }
+            // 2. Secondary sort: last used time (newer first)
+            const timeA = a.last_used_at || 0;
+            const timeB = b.last_used_at || 0;
+            if (timeA !== timeB) {
+                return timeB - timeA; // descending
+            }
+            // 3. Fallback sort: id (ascending)
+            return a.id - b.id;
+        })
+        .slice(0, 10); // Top 10 results
+}
+
 /**
  * T1-017: Keyboard Navigation
  * Handle ↑↓Enter ESC with focus cycling
@@ -265,6 +279,7 @@ function renderResults(prompts) {
                 <div class="result-name">${escapeHtml(prompt.name)}</div>
                 <div class="result-preview">${escapeHtml(preview)}</div>
                 <div class="result-meta">
+                    ${prompt.source ? `<span class="tag source-tag" title="来自目录 ${escapeHtml(prompt.source)}">📁 ${escapeHtml(prompt.source)}</span>` : ''}
                     ${category ? `<span class="tag category-tag">${escapeHtml(category)}</span>` : ''}
                     ${tags.slice(1).map(tag => `<span class="tag">${escapeHtml(tag)}</span>`).join('')}
                     ${usageCount > 0 ? `<span class="usage-info">使用 ${usageCount} 次${lastUsed ? ` · ${lastUsed}` : ''}</span>` : ''}
@@ -20,6 +20,7 @@ const nextBtn = document.getElementById('next-page');
 document.addEventListener('DOMContentLoaded', () => {
     loadPrompts();
     setupEventListeners();
+    listenInjectionOutcome();
 });
 
 // Load prompts from backend
@@ -54,12 +55,14 @@ function renderPrompts() {
             petal.dataset.promptId = prompt.id;
             petal.dataset.promptName = prompt.name;
             petal.dataset.promptContent = prompt.content;
+            petal.title = prompt.source ? `📁 ${prompt.source}` : '';
             petal.style.opacity = '1';
             petal.style.pointerEvents = 'auto';
         } else {
             // Empty petal
             nameSpan.textContent = '-';
             petal.dataset.promptId = '';
+            petal.title = '';
             petal.style.opacity = '0.3';
             petal.style.pointerEvents = 'none';
         }
@@ -125,8 +128,12 @@ async function handlePetalClick(index) {
     console.log(`Petal ${index + 1} clicked: ${prompt.name} (ID: ${prompt.id})`);
     
     try {
+        // Fill in template variables before injection
+        const variables = await collectTemplateVariables(prompt.id);
+        if (variables === null) return; // User cancelled
+
         // Trigger injection
-        await invoke('trigger_wheel_injection', { promptId: prompt.id });
+        await invoke('trigger_wheel_injection', { promptId: prompt.id, variables });
         console.log(`✅ Injection triggered for prompt ID ${prompt.id}`);
         
         // Visual feedback
@@ -141,6 +148,48 @@ async function handlePetalClick(index) {
     }
 }
 
+// Ask the user for any template variables the prompt still needs.
+// Returns the collected values, or null if the user cancelled.
+async function collectTemplateVariables(promptId) {
+    const variables = {};
+    let outcome = await invoke('render_prompt_template', { promptId, variables });
+
+    while (outcome.status === 'needs_input') {
+        for (const v of outcome.variables) {
+            const label = v.label || v.name;
+            const hint = v.choices && v.choices.length ? ` (${v.choices.join(' / ')})` : '';
+            const value = window.prompt(`${label}${hint}`, v.default || '');
+            if (value === null) return null;
+            variables[v.name] = value;
+        }
+        outcome = await invoke('render_prompt_template', { promptId, variables });
+    }
+
+    return variables;
+}
+
+// Injection results reported by the service (see ipc_listener::forward_outcome)
+async function listenInjectionOutcome() {
+    const { listen } = window.__TAURI__.event;
+    await listen('injection-outcome', (event) => {
+        const outcome = event.payload;
+        if (outcome.success) {
+            console.log(`✅ Injected via ${outcome.strategy} in ${outcome.elapsed_ms}ms`);
+        } else {
+            const name = outcome.prompt_name ? `${outcome.prompt_name}: ` : '';
+            showToast(`Injection failed – ${name}${outcome.error}`);
+        }
+    });
+}
+
+function showToast(message) {
+    const toast = document.createElement('div');
+    toast.className = 'toast toast-error';
+    toast.textContent = message;
+    document.body.appendChild(toast);
+    setTimeout(() => toast.remove(), 4000);
+}
+
 // Visual feedback for selection
 function highlightPetal(index) {
     const petal = petals[index];